@@ -0,0 +1,288 @@
+use cosmwasm_std::{
+    ensure, ensure_eq, to_json_binary, Addr, Binary, Response, StdError, StdResult, SubMsg,
+    Timestamp, Uint128, WasmMsg,
+};
+use cw2::ContractVersion;
+use cw_storage_plus::Item;
+
+use cw20::{Cw20ExecuteMsg, Cw20QueryMsg, TokenInfoResponse};
+
+use lockup_common::fungible::FungibleLockups;
+use lockup_common::receipts::Receipts;
+
+use sylvia::types::{ExecCtx, InstantiateCtx, QueryCtx};
+use sylvia::{contract, entry_points};
+
+use crate::msg::{ConfigResponse, CountResponse};
+use crate::storage::Lockup;
+use crate::{ACTOR_ID, VERSION};
+
+pub struct Cw20LockupContract {
+    pub(crate) admin: Item<'static, Addr>,
+    /// CW20 token contract accepted for lockups
+    pub(crate) token: Item<'static, Addr>,
+    pub(crate) lockups: FungibleLockups<'static>,
+}
+
+#[entry_points]
+#[contract]
+#[messages(lockup_common::receipts as Receipts)]
+impl Cw20LockupContract {
+    pub const fn new() -> Self {
+        Self {
+            admin: Item::new("admin"),
+            token: Item::new("token"),
+            lockups: FungibleLockups::new(),
+        }
+    }
+
+    #[msg(instantiate)]
+    fn instantiate(
+        &self,
+        ctx: InstantiateCtx,
+        token: String,
+        lockup_interval: Option<Timestamp>,
+    ) -> StdResult<Response> {
+        let token = ctx.deps.api.addr_validate(&token)?;
+
+        // Query the token info to verify that the token is a CW20 contract
+        let token_info: TokenInfoResponse = ctx
+            .deps
+            .querier
+            .query_wasm_smart(token.clone(), &Cw20QueryMsg::TokenInfo {})?;
+
+        self.token.save(ctx.deps.storage, &token)?;
+        self.lockups.lockup_interval.save(
+            ctx.deps.storage,
+            &lockup_interval
+                .or(Some(Timestamp::from_seconds(0)))
+                .unwrap(),
+        )?;
+        self.admin.save(ctx.deps.storage, &ctx.info.sender)?;
+
+        // Lockup positions are represented by receipt NFTs issued by this contract
+        self.lockups.receipts.save_info(
+            ctx.deps.storage,
+            format!("{} Lockup Receipt", token_info.symbol),
+        )?;
+
+        Ok(Response::new())
+    }
+
+    #[msg(exec)]
+    fn update_admin(&self, ctx: ExecCtx, admin: String) -> StdResult<Response> {
+        // Admin only
+        let old_admin = self.admin.load(ctx.deps.storage).unwrap();
+        ensure_eq!(
+            old_admin,
+            ctx.info.sender,
+            StdError::generic_err("Unauthorized")
+        );
+
+        // Update the admin
+        let admin = ctx.deps.api.addr_validate(&admin)?;
+        self.admin.save(ctx.deps.storage, &admin).unwrap();
+
+        Ok(Response::new()
+            .add_attribute("method", "update_admin")
+            .add_attribute("old_admin", old_admin.to_string())
+            .add_attribute("new_admin", admin.to_string()))
+    }
+
+    #[msg(exec)]
+    fn update_config(
+        &self,
+        ctx: ExecCtx,
+        lockup_interval: Option<Timestamp>,
+    ) -> StdResult<Response> {
+        // Admin only
+        let old_admin = self.admin.load(ctx.deps.storage).unwrap();
+        ensure_eq!(
+            old_admin,
+            ctx.info.sender,
+            StdError::generic_err("Unauthorized")
+        );
+
+        // Save the new config
+        self.lockups.lockup_interval.save(
+            ctx.deps.storage,
+            &lockup_interval
+                .or(Some(Timestamp::from_seconds(0)))
+                .unwrap(),
+        )?;
+
+        Ok(Response::new()
+            .add_attribute("method", "update_config")
+            .add_attribute("contract_address", ctx.env.contract.address.to_string())
+            .add_attribute(
+                "lockup_interval",
+                lockup_interval
+                    .or(Some(Timestamp::from_seconds(0)))
+                    .unwrap()
+                    .to_string(),
+            ))
+    }
+
+    #[msg(exec)]
+    fn update_stake_contracts(
+        &self,
+        ctx: ExecCtx,
+        stake_contracts: Vec<String>,
+    ) -> StdResult<Response> {
+        // Admin only
+        let admin = self.admin.load(ctx.deps.storage).unwrap();
+        ensure_eq!(
+            admin,
+            ctx.info.sender,
+            StdError::generic_err("Unauthorized")
+        );
+
+        // Verify stake contract addresses
+        let stake_contracts = stake_contracts
+            .into_iter()
+            .map(|addr| ctx.deps.api.addr_validate(&addr))
+            .collect::<StdResult<Vec<Addr>>>()?;
+
+        self.lockups
+            .stake_contracts
+            .save(ctx.deps.storage, &stake_contracts)?;
+
+        Ok(Response::new()
+            .add_attribute("method", "update_stake_contracts")
+            .add_attribute("contract_address", ctx.env.contract.address.to_string())
+            .add_attribute(
+                "stake_contracts",
+                stake_contracts
+                    .iter()
+                    .map(|addr| addr.to_string())
+                    .collect::<Vec<String>>()
+                    .join(","),
+            ))
+    }
+
+    #[msg(exec)]
+    fn receive(&self, ctx: ExecCtx, sender: String, amount: Uint128) -> StdResult<Response> {
+        // Verify that the tokens sent are in the correct token
+        let token = self.token.load(ctx.deps.storage)?;
+        ensure_eq!(
+            ctx.info.sender,
+            token,
+            StdError::generic_err("Unsupported token sent")
+        );
+
+        // Verify that the amount of tokens sent is over 0
+        ensure!(
+            amount > Uint128::zero(),
+            StdError::generic_err("Funds sent must be greater than 0")
+        );
+
+        let owner = ctx.deps.api.addr_validate(&sender)?;
+        let new_lockup = self
+            .lockups
+            .lock(ctx.deps.storage, &ctx.env, &owner, &owner, amount)?;
+
+        Ok(Response::new()
+            .add_attribute("method", "deposit")
+            .add_attribute("contract_address", ctx.env.contract.address.to_string())
+            .add_attribute("sender", owner.to_string())
+            .add_attribute("receipt_id", new_lockup.receipt_id)
+            .add_attribute("amount", new_lockup.amount.to_string())
+            .add_attribute("locked_until", new_lockup.locked_until.to_string()))
+    }
+
+    #[msg(exec)]
+    fn withdraw(&self, ctx: ExecCtx, amount: Option<Uint128>) -> StdResult<Response> {
+        let (lockup, amount) =
+            self.lockups
+                .unlock(ctx.deps.storage, &ctx.env, &ctx.info.sender, amount)?;
+
+        let token = self.token.load(ctx.deps.storage)?;
+        let msg = Cw20ExecuteMsg::Transfer {
+            recipient: ctx.info.sender.to_string(),
+            amount,
+        };
+        let send_msg = SubMsg::new(WasmMsg::Execute {
+            contract_addr: token.to_string(),
+            msg: to_json_binary(&msg)?,
+            funds: vec![],
+        });
+
+        // Send the funds to the user
+        let res = Response::new()
+            .add_submessage(send_msg)
+            .add_attribute("method", "withdraw")
+            .add_attribute("contract_address", ctx.env.contract.address.to_string())
+            .add_attribute("sender", ctx.info.sender.to_string())
+            .add_attribute("token", token.to_string())
+            .add_attribute("amount", lockup.amount.to_string());
+
+        Ok(res)
+    }
+
+    #[msg(exec)]
+    fn transfer_lockup(&self, ctx: ExecCtx, recipient: String) -> StdResult<Response> {
+        self.lockups.transfer_lockup(ctx, recipient)
+    }
+
+    #[msg(query)]
+    fn count(&self, ctx: QueryCtx) -> StdResult<CountResponse> {
+        let count = self.lockups.total(ctx.deps.storage)?;
+        Ok(CountResponse { count })
+    }
+
+    #[msg(query)]
+    fn contract_type(&self, _ctx: QueryCtx) -> StdResult<ContractVersion> {
+        Ok(ContractVersion {
+            contract: ACTOR_ID.to_string(),
+            version: VERSION.to_string(),
+        })
+    }
+
+    #[msg(query)]
+    fn lockup(&self, ctx: QueryCtx, address: String) -> StdResult<Lockup> {
+        let address = ctx.deps.api.addr_validate(&address)?;
+        let lockup = self.lockups.lockup.load(ctx.deps.storage, address)?;
+        Ok(lockup)
+    }
+
+    #[msg(query)]
+    fn config(&self, ctx: QueryCtx) -> StdResult<ConfigResponse> {
+        let admin = self.admin.load(ctx.deps.storage)?;
+        let lockup_interval = self.lockups.lockup_interval.load(ctx.deps.storage)?;
+        let token = self.token.load(ctx.deps.storage)?;
+        let stake_contracts = self
+            .lockups
+            .stake_contracts
+            .may_load(ctx.deps.storage)?
+            .unwrap_or_default();
+        Ok(ConfigResponse {
+            admin,
+            lockup_interval,
+            token,
+            stake_contracts,
+        })
+    }
+}
+
+impl Receipts for Cw20LockupContract {
+    type Error = StdError;
+
+    fn transfer_nft(
+        &self,
+        ctx: ExecCtx,
+        recipient: String,
+        token_id: String,
+    ) -> StdResult<Response> {
+        self.lockups.transfer_nft(ctx, recipient, token_id)
+    }
+
+    fn send_nft(
+        &self,
+        ctx: ExecCtx,
+        contract: String,
+        token_id: String,
+        msg: Binary,
+    ) -> StdResult<Response> {
+        self.lockups.send_nft(ctx, contract, token_id, msg)
+    }
+}
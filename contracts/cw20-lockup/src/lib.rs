@@ -0,0 +1,7 @@
+pub const CONTRACT: &str = "cw20_lockup";
+pub const ACTOR_ID: &str = "cw20";
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+pub mod contract;
+pub mod msg;
+pub mod storage;
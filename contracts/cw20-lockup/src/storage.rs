@@ -0,0 +1 @@
+pub use lockup_common::fungible::Lockup;
@@ -1,28 +1,38 @@
 use cosmwasm_std::{
-    ensure, ensure_eq, to_json_binary, Addr, Response, StdError, StdResult, SubMsg, Timestamp,
-    WasmMsg,
+    ensure, ensure_eq, to_json_binary, Addr, Binary, Deps, DepsMut, Env, MessageInfo, Order,
+    Response, StdError, StdResult, SubMsg, Timestamp, WasmMsg,
 };
 use cw2::ContractVersion;
-use cw_storage_plus::{IndexedMap, Item, MultiIndex};
+use cw_storage_plus::{IndexedMap, Item, Map, MultiIndex};
 
-use sylvia::types::{ExecCtx, InstantiateCtx, QueryCtx};
+use sylvia::types::{ExecCtx, InstantiateCtx, MigrateCtx, QueryCtx};
 use sylvia::{contract, entry_points};
 
-use cw721::{Cw721ExecuteMsg, Cw721QueryMsg, OwnerOfResponse as Cw721OwnerOfResponse};
+use cw721::{
+    Cw721ExecuteMsg, Cw721QueryMsg, Cw721ReceiveMsg, OwnerOfResponse as Cw721OwnerOfResponse,
+};
+
+use lockup_common::receipts::{LockupReceipts, Receipts};
 
 use crate::msg::{ConfigResponse, CountResponse, LockupsReponse};
-use crate::storage::{Lockup, LockupIndexes};
+use crate::storage::{CollectionRule, CollectionRuleInput, LegacyLockup, Lockup, LockupIndexes};
 use crate::{ACTOR_ID, VERSION};
 
 pub struct Cw721LockupContract {
     pub(crate) admin: Item<'static, Addr>,
     pub(crate) lockup_interval: Item<'static, Timestamp>,
     pub(crate) collections: Item<'static, Vec<Addr>>,
+    /// Rules supporting collections on top of the listed ones
+    pub(crate) collection_rules: Item<'static, Vec<CollectionRule>>,
+    /// Lockup entries, keyed by receipt token ID
     pub(crate) lockup: IndexedMap<'static, &'static str, Lockup, LockupIndexes<'static>>,
+    /// Receipt NFTs representing lockup positions
+    pub(crate) receipts: LockupReceipts<'static>,
 }
 
 #[entry_points]
 #[contract]
+#[messages(lockup_common::receipts as Receipts)]
 impl Cw721LockupContract {
     pub const fn new() -> Self {
         let indexes = LockupIndexes {
@@ -43,7 +53,9 @@ impl Cw721LockupContract {
             admin: Item::new("admin"),
             lockup_interval: Item::new("lockup_interval"),
             collections: Item::new("collections"),
+            collection_rules: Item::new("collection_rules"),
             lockup: IndexedMap::new("lockup", indexes),
+            receipts: LockupReceipts::new(),
         }
     }
 
@@ -53,7 +65,16 @@ impl Cw721LockupContract {
         ctx: InstantiateCtx,
         lockup_interval: Option<Timestamp>,
         collections: Vec<String>,
+        collection_rules: Option<Vec<CollectionRuleInput>>,
     ) -> StdResult<Response> {
+        let collection_rules = collection_rules
+            .unwrap_or_default()
+            .iter()
+            .map(|rule| rule.check(ctx.deps.api))
+            .collect::<StdResult<Vec<_>>>()?;
+        self.collection_rules
+            .save(ctx.deps.storage, &collection_rules)?;
+
         let collections: Result<Vec<Addr>, StdError> = collections
             .into_iter()
             .map(|addr| {
@@ -76,6 +97,10 @@ impl Cw721LockupContract {
                     .save(ctx.deps.storage, &valid_collections)?;
                 self.admin.save(ctx.deps.storage, &ctx.info.sender)?;
 
+                // Lockup positions are represented by receipt NFTs issued by this contract
+                self.receipts
+                    .save_info(ctx.deps.storage, String::from("CW721 Lockup Receipt"))?;
+
                 Ok(Response::new())
             }
             Err(err) => {
@@ -155,14 +180,41 @@ impl Cw721LockupContract {
             ))
     }
 
+    /// Replace the rules supporting collections on top of the listed ones
+    #[msg(exec)]
+    fn update_collection_rules(
+        &self,
+        ctx: ExecCtx,
+        collection_rules: Vec<CollectionRuleInput>,
+    ) -> StdResult<Response> {
+        // Admin only
+        let admin = self.admin.load(ctx.deps.storage)?;
+        ensure_eq!(
+            admin,
+            ctx.info.sender,
+            StdError::generic_err("Unauthorized")
+        );
+
+        let collection_rules = collection_rules
+            .iter()
+            .map(|rule| rule.check(ctx.deps.api))
+            .collect::<StdResult<Vec<_>>>()?;
+        self.collection_rules
+            .save(ctx.deps.storage, &collection_rules)?;
+
+        Ok(Response::new()
+            .add_attribute("method", "update_collection_rules")
+            .add_attribute("contract_address", ctx.env.contract.address.to_string())
+            .add_attribute("collection_rules", collection_rules.len().to_string()))
+    }
+
     #[msg(exec)]
     fn receive_nft(&self, ctx: ExecCtx, sender: String, token_id: String) -> StdResult<Response> {
         let collection_address = ctx.info.sender;
 
         // Verify that the collection is supported
-        let collections = self.collections.load(ctx.deps.storage)?;
         ensure!(
-            collections.contains(&collection_address),
+            self.is_supported(ctx.deps.as_ref(), &collection_address)?,
             StdError::generic_err("Collection is not supported")
         );
 
@@ -191,7 +243,11 @@ impl Cw721LockupContract {
         let lockup_interval = self.lockup_interval.load(ctx.deps.storage)?;
         let locked_until = ctx.env.block.time.plus_seconds(lockup_interval.seconds());
 
+        // Mint a receipt representing the lockup position to the owner
+        let receipt_id = self.receipts.mint(ctx.deps.storage, &owner)?;
+
         let lockup = Lockup::new(
+            receipt_id.clone(),
             owner.clone(),
             collection_address.clone(),
             token_id.clone(),
@@ -199,14 +255,14 @@ impl Cw721LockupContract {
             locked_until.clone(),
         );
 
-        self.lockup
-            .save(ctx.deps.storage, "lockup__depositor", &lockup)?;
+        self.lockup.save(ctx.deps.storage, &receipt_id, &lockup)?;
 
         Ok(Response::new()
             .add_attribute("method", "deposit")
             .add_attribute("contract_address", ctx.env.contract.address.to_string())
             .add_attribute("collection_address", collection_address.to_string())
             .add_attribute("token_id", token_id)
+            .add_attribute("receipt_id", receipt_id)
             .add_attribute("owner", owner.to_string())
             .add_attribute("locked_until", locked_until.to_string()))
     }
@@ -239,9 +295,12 @@ impl Cw721LockupContract {
             }
         };
 
-        // Verify that the sender is the owner of the NFT
+        // Verify that the sender holds the lockup receipt
+        let receipt_owner = self
+            .receipts
+            .owner(ctx.deps.storage, &lockup.1.receipt_id)?;
         ensure_eq!(
-            lockup.1.owner,
+            receipt_owner,
             sender,
             StdError::generic_err("Sender is not the owner of the NFT")
         );
@@ -251,12 +310,13 @@ impl Cw721LockupContract {
             StdError::generic_err("Lockup period has not passed")
         );
 
-        // Delete the lockup entry
+        // Delete the lockup entry and burn its receipt
         self.lockup.remove(ctx.deps.storage, &lockup.0)?;
+        self.receipts.burn(ctx.deps.storage, &lockup.1.receipt_id)?;
 
         // Send the NFT back to the owner
         let msg = Cw721ExecuteMsg::TransferNft {
-            recipient: lockup.1.owner.to_string(),
+            recipient: receipt_owner.to_string(),
             token_id: lockup.1.token_id.to_string(),
         };
         let cw721_msg = WasmMsg::Execute {
@@ -271,7 +331,105 @@ impl Cw721LockupContract {
             .add_attribute("contract_address", ctx.env.contract.address.to_string())
             .add_attribute("collection_address", collection_address)
             .add_attribute("token_id", token_id)
-            .add_attribute("owner", lockup.1.owner.to_string()))
+            .add_attribute("owner", receipt_owner.to_string()))
+    }
+
+    #[msg(exec)]
+    fn transfer_lockup(
+        &self,
+        mut ctx: ExecCtx,
+        recipient: String,
+        collection_address: String,
+        token_id: String,
+    ) -> StdResult<Response> {
+        let collection_address = ctx.deps.api.addr_validate(&collection_address)?;
+
+        // Retrieve the lockup entry for the NFT
+        let lockup_key = (collection_address.clone(), token_id.clone());
+        let lockup_data = self
+            .lockup
+            .idx
+            .token
+            .prefix(lockup_key.clone())
+            .range(ctx.deps.storage, None, None, cosmwasm_std::Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+
+        let receipt_id = match lockup_data.first() {
+            Some(lockup) => lockup.0.clone(),
+            None => {
+                return Err(StdError::generic_err("Lockup entry not found"));
+            }
+        };
+
+        // Move the lockup along with its receipt
+        // Stake contracts key claims by token, so they need no notification
+        let (owner, recipient, lockup) = self.transfer_position(
+            ctx.deps.branch(),
+            &ctx.env,
+            &ctx.info,
+            &recipient,
+            &receipt_id,
+        )?;
+
+        Ok(Response::new()
+            .add_attribute("method", "transfer_lockup")
+            .add_attribute("contract_address", ctx.env.contract.address.to_string())
+            .add_attribute("collection_address", collection_address)
+            .add_attribute("token_id", token_id)
+            .add_attribute("receipt_id", receipt_id)
+            .add_attribute("sender", owner.to_string())
+            .add_attribute("recipient", recipient.to_string())
+            .add_attribute("locked_until", lockup.locked_until.to_string()))
+    }
+
+    /// Issue receipts for lockups created before lockup positions were represented by receipts,
+    /// keying their entries by receipt token ID
+    #[msg(migrate)]
+    fn migrate(&self, ctx: MigrateCtx) -> StdResult<Response> {
+        let legacy_lockup: Map<'static, &str, LegacyLockup> = Map::new("lockup");
+
+        // Receipt information is missing if the contract predates receipts
+        if self
+            .receipts
+            .nfts()
+            .contract_info
+            .may_load(ctx.deps.storage)?
+            .is_none()
+        {
+            self.receipts
+                .save_info(ctx.deps.storage, String::from("CW721 Lockup Receipt"))?;
+        }
+
+        let entries = legacy_lockup
+            .range(ctx.deps.storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        let mut migrated = 0u64;
+        for (key, legacy) in entries {
+            if legacy.receipt_id.is_some() {
+                continue;
+            }
+
+            let receipt_id = self.receipts.mint(ctx.deps.storage, &legacy.owner)?;
+            let lockup = Lockup::new(
+                receipt_id.clone(),
+                legacy.owner,
+                legacy.collection_address,
+                legacy.token_id,
+                legacy.locked_since,
+                legacy.locked_until,
+            );
+
+            // Drop the entry along with its index entries, which only depend on unchanged fields
+            self.lockup
+                .replace(ctx.deps.storage, &key, None, Some(&lockup))?;
+            self.lockup.save(ctx.deps.storage, &receipt_id, &lockup)?;
+            migrated += 1;
+        }
+
+        Ok(Response::new()
+            .add_attribute("method", "migrate")
+            .add_attribute("contract_address", ctx.env.contract.address.to_string())
+            .add_attribute("migrated", migrated.to_string()))
     }
 
     #[msg(query)]
@@ -357,6 +515,123 @@ impl Cw721LockupContract {
             admin,
             lockup_interval,
             collections,
+            collection_rules: self
+                .collection_rules
+                .may_load(ctx.deps.storage)?
+                .unwrap_or_default(),
         })
     }
 }
+
+impl Cw721LockupContract {
+    /// Whether a collection is listed or allowed by one of the collection rules
+    fn is_supported(&self, deps: Deps, collection_address: &Addr) -> StdResult<bool> {
+        if self
+            .collections
+            .load(deps.storage)?
+            .contains(collection_address)
+        {
+            return Ok(true);
+        }
+
+        for rule in self
+            .collection_rules
+            .may_load(deps.storage)?
+            .unwrap_or_default()
+        {
+            if rule.allows(&deps.querier, collection_address)? {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Transfer a lockup receipt and update the owner of the lockup it represents
+    fn transfer_position(
+        &self,
+        mut deps: DepsMut,
+        env: &Env,
+        info: &MessageInfo,
+        recipient: &str,
+        receipt_id: &str,
+    ) -> StdResult<(Addr, Addr, Lockup)> {
+        let owner = self.receipts.owner(deps.storage, receipt_id)?;
+        let recipient = deps.api.addr_validate(recipient)?;
+
+        // Transfer the receipt, verifying that the sender is allowed to do so
+        self.receipts
+            .transfer(deps.branch(), env, info, &recipient, receipt_id)?;
+
+        // Update the owner, keeping the lock period intact
+        let lockup = self.lockup.load(deps.storage, receipt_id)?;
+        let new_lockup = Lockup {
+            owner: recipient.clone(),
+            ..lockup.clone()
+        };
+        self.lockup
+            .replace(deps.storage, receipt_id, Some(&new_lockup), Some(&lockup))?;
+
+        Ok((owner, recipient, new_lockup))
+    }
+}
+
+impl Receipts for Cw721LockupContract {
+    type Error = StdError;
+
+    fn transfer_nft(
+        &self,
+        mut ctx: ExecCtx,
+        recipient: String,
+        token_id: String,
+    ) -> StdResult<Response> {
+        let (owner, recipient, _) = self.transfer_position(
+            ctx.deps.branch(),
+            &ctx.env,
+            &ctx.info,
+            &recipient,
+            &token_id,
+        )?;
+
+        Ok(Response::new()
+            .add_attribute("method", "transfer_nft")
+            .add_attribute("contract_address", ctx.env.contract.address.to_string())
+            .add_attribute("sender", ctx.info.sender.to_string())
+            .add_attribute("owner", owner.to_string())
+            .add_attribute("recipient", recipient.to_string())
+            .add_attribute("token_id", token_id))
+    }
+
+    fn send_nft(
+        &self,
+        mut ctx: ExecCtx,
+        contract: String,
+        token_id: String,
+        msg: Binary,
+    ) -> StdResult<Response> {
+        let (owner, contract, _) =
+            self.transfer_position(ctx.deps.branch(), &ctx.env, &ctx.info, &contract, &token_id)?;
+
+        // Notify the receiving contract
+        let receive_msg = Cw721ReceiveMsg {
+            sender: ctx.info.sender.to_string(),
+            token_id: token_id.clone(),
+            msg,
+        }
+        .into_cosmos_msg(contract.to_string())?;
+
+        Ok(Response::new()
+            .add_message(receive_msg)
+            .add_attribute("method", "send_nft")
+            .add_attribute("contract_address", ctx.env.contract.address.to_string())
+            .add_attribute("sender", ctx.info.sender.to_string())
+            .add_attribute("owner", owner.to_string())
+            .add_attribute("recipient", contract.to_string())
+            .add_attribute("token_id", token_id))
+    }
+}
+
+/// Migrate entry point, not generated by `#[entry_points]`
+#[cosmwasm_std::entry_point]
+pub fn migrate(deps: DepsMut, env: Env, msg: MigrateMsg) -> StdResult<Response> {
+    msg.dispatch(&Cw721LockupContract::new(), (deps, env))
+}
@@ -1,7 +1,7 @@
 use cosmwasm_schema::cw_serde;
 use cosmwasm_std::{Addr, Timestamp};
 
-use crate::storage::Lockup;
+use crate::storage::{CollectionRule, Lockup};
 
 #[cw_serde]
 pub struct CountResponse {
@@ -18,4 +18,5 @@ pub struct ConfigResponse {
     pub admin: Addr,
     pub lockup_interval: Timestamp,
     pub collections: Vec<Addr>,
+    pub collection_rules: Vec<CollectionRule>,
 }
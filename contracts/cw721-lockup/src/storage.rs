@@ -1,9 +1,10 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Addr, Timestamp};
+use cosmwasm_std::{Addr, Api, QuerierWrapper, StdResult, Timestamp};
 use cw_storage_plus::{Index, IndexList, MultiIndex};
 
 #[cw_serde]
 pub struct Lockup {
+    pub receipt_id: String,
     pub owner: Addr,
     pub collection_address: Addr,
     pub token_id: String,
@@ -13,6 +14,7 @@ pub struct Lockup {
 
 impl Lockup {
     pub fn new(
+        receipt_id: String,
         owner: Addr,
         collection_address: Addr,
         token_id: String,
@@ -20,6 +22,7 @@ impl Lockup {
         locked_until: Timestamp,
     ) -> Self {
         Self {
+            receipt_id,
             owner,
             collection_address,
             token_id,
@@ -29,6 +32,66 @@ impl Lockup {
     }
 }
 
+/// Lockup entry that may predate receipts, used when migrating
+#[cw_serde]
+pub struct LegacyLockup {
+    #[serde(default)]
+    pub receipt_id: Option<String>,
+    pub owner: Addr,
+    pub collection_address: Addr,
+    pub token_id: String,
+    pub locked_since: Timestamp,
+    pub locked_until: Timestamp,
+}
+
+/// Unvalidated collection rule, as passed in messages
+#[cw_serde]
+pub enum CollectionRuleInput {
+    CodeId(u64),
+    Factory(String),
+}
+
+impl CollectionRuleInput {
+    pub fn check(&self, api: &dyn Api) -> StdResult<CollectionRule> {
+        Ok(match self {
+            CollectionRuleInput::CodeId(code_id) => CollectionRule::CodeId(*code_id),
+            CollectionRuleInput::Factory(address) => {
+                CollectionRule::Factory(api.addr_validate(address)?)
+            }
+        })
+    }
+}
+
+/// Rule supporting collections that are not listed one by one
+#[cw_serde]
+pub enum CollectionRule {
+    /// Collections instantiated from a code ID
+    CodeId(u64),
+    /// Collections instantiated by a factory, or by a minter the factory instantiated
+    /// (as sg721 collections are through their vending minter)
+    Factory(Addr),
+}
+
+impl CollectionRule {
+    pub fn allows(&self, querier: &QuerierWrapper, collection_address: &Addr) -> StdResult<bool> {
+        let info = querier.query_wasm_contract_info(collection_address)?;
+        match self {
+            CollectionRule::CodeId(code_id) => Ok(info.code_id == *code_id),
+            CollectionRule::Factory(factory) => {
+                if info.creator == factory.as_str() {
+                    return Ok(true);
+                }
+
+                // The creator may not be a contract
+                Ok(querier
+                    .query_wasm_contract_info(&info.creator)
+                    .map(|creator_info| creator_info.creator == factory.as_str())
+                    .unwrap_or(false))
+            }
+        }
+    }
+}
+
 type Token = (Addr, String);
 
 pub struct LockupIndexes<'a> {
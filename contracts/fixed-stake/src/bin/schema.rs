@@ -1,5 +1,5 @@
 use cosmwasm_schema::write_api;
-use fixed_stake::contract::{ContractExecMsg, ContractQueryMsg, InstantiateMsg};
+use fixed_stake::contract::{ContractExecMsg, ContractQueryMsg, InstantiateMsg, MigrateMsg};
 
 #[cfg(not(tarpaulin_include))]
 fn main() {
@@ -7,5 +7,6 @@ fn main() {
         instantiate: InstantiateMsg,
         execute: ContractExecMsg,
         query: ContractQueryMsg,
+        migrate: MigrateMsg,
     }
 }
@@ -1,30 +1,55 @@
 use cosmwasm_std::{
-    coin, ensure, ensure_eq, Addr, BankMsg, Decimal, Response, StdError, StdResult, SubMsg,
-    Timestamp, Uint128,
+    ensure, ensure_eq, Addr, Coin, Deps, DepsMut, Empty, Env, Event, Order, Response, StdError,
+    StdResult, Storage, Timestamp, Uint128,
 };
 use cw_storage_plus::{Item, Map};
 
-use sylvia::types::{ExecCtx, InstantiateCtx, QueryCtx};
+use sylvia::types::{ExecCtx, InstantiateCtx, MigrateCtx, QueryCtx};
 use sylvia::{contract, entry_points};
 
-use crate::msg::ConfigResponse;
-use crate::storage::{LockupInfo, Stake};
+use crate::helpers::{accrued_rewards, transfer_msgs};
+use crate::msg::{ConfigResponse, FeeConfigResponse, FundingResponse, VestingScheduleResponse};
+use crate::storage::{
+    AssetInfo, AssetInfoInput, Emission, FeeConfig, FeeConfigInput, InstantiateOptions, LockupInfo,
+    Period, Position, ProgramTime, Stake, VestingConfig, VestingTranche, MAX_FEE_BPS,
+};
 
 pub struct FixedStakeContract {
     pub admin: Item<'static, Addr>,
     pub lockup_contract: Item<'static, Addr>,
     /// Time interval between reward distributions
     pub distribution_interval: Item<'static, Timestamp>,
-    /// Reward denomination (e.g. `ustars`, must be a factory token for `inflation-stake`)
-    pub reward_denom: Item<'static, String>,
-    /// Total rewards to distribute
-    pub total_rewards: Item<'static, Uint128>,
+    /// Reward assets, native denominations (e.g. `ustars`) or CW20 tokens, and
+    /// the total amount of each to distribute
+    pub rewards: Item<'static, Vec<(AssetInfo, Uint128)>>,
     /// Reward distribution start time
     pub start_time: Item<'static, Timestamp>,
-    /// Staking claim information (key type depends on lockup contract type)
-    pub staking: Map<'static, (Addr, String), Stake>,
+    /// Staking claim information of each position (keyed by position)
+    pub staking: Map<'static, String, Stake>,
     /// Reward distribution end time (`fixed-stake` only)
     pub end_time: Item<'static, Timestamp>,
+    /// Schedule periods, a new one starting whenever the program is reconfigured
+    pub periods: Item<'static, Vec<Period>>,
+    /// Whether the program was cancelled before its end time
+    pub cancelled: Item<'static, bool>,
+    /// Whether rewards accrue every second rather than every whole interval
+    pub streaming: Item<'static, bool>,
+    /// Vesting applied to claimed rewards, if any
+    pub vesting: Item<'static, Option<VestingConfig>>,
+    /// Vesting tranches of each reward recipient
+    pub vesting_tranches: Map<'static, Addr, Vec<VestingTranche>>,
+    /// Amount of each reward asset claimed but not released yet (keyed by asset)
+    pub vesting_amount: Map<'static, String, Uint128>,
+    /// Protocol fee taken on each claim, if any
+    pub fee: Item<'static, Option<FeeConfig>>,
+    /// Address receiving the rewards of each lockup owner, if not the owner
+    pub reward_recipients: Map<'static, Addr, Addr>,
+    /// Operators allowed to claim on behalf of each lockup owner
+    pub claimers: Map<'static, (Addr, Addr), Empty>,
+    /// Amount of each reward asset funded so far (keyed by asset)
+    pub funded_amount: Map<'static, String, Uint128>,
+    /// Amount of each reward asset distributed so far (keyed by asset)
+    pub distributed_amount: Map<'static, String, Uint128>,
 }
 
 #[entry_points]
@@ -35,11 +60,21 @@ impl FixedStakeContract {
             admin: Item::new("admin"),
             lockup_contract: Item::new("lockup_contract"),
             distribution_interval: Item::new("distribution_interval"),
-            reward_denom: Item::new("reward_denom"),
-            total_rewards: Item::new("total_rewards"),
+            rewards: Item::new("rewards"),
             start_time: Item::new("start_time"),
-            staking: Map::new("staking"),
+            staking: Map::new("positions"),
             end_time: Item::new("end_time"),
+            periods: Item::new("periods"),
+            cancelled: Item::new("cancelled"),
+            streaming: Item::new("streaming"),
+            vesting: Item::new("vesting"),
+            vesting_tranches: Map::new("vesting_tranches"),
+            vesting_amount: Map::new("vesting_amount"),
+            fee: Item::new("fee"),
+            reward_recipients: Map::new("reward_recipients"),
+            claimers: Map::new("claimers"),
+            funded_amount: Map::new("funded_amount"),
+            distributed_amount: Map::new("distributed_amount"),
         }
     }
 
@@ -49,17 +84,41 @@ impl FixedStakeContract {
         ctx: InstantiateCtx,
         lockup_contract: String,
         distribution_interval: Timestamp,
-        reward_denom: String,
-        total_rewards: Uint128,
-        start_time: Timestamp,
-        end_time: Timestamp,
+        rewards: Vec<(AssetInfoInput, Uint128)>,
+        time: ProgramTime,
+        options: Option<InstantiateOptions>,
     ) -> StdResult<Response> {
+        let ProgramTime {
+            start_time,
+            end_time,
+        } = time;
+        let InstantiateOptions {
+            emission,
+            streaming,
+            vesting,
+            fee,
+        } = options.unwrap_or_default();
         ensure!(
             end_time > start_time,
             StdError::generic_err("End time must be after start time")
         );
 
         let lockup_contract = ctx.deps.api.addr_validate(&lockup_contract)?;
+        ensure!(
+            !rewards.is_empty(),
+            StdError::generic_err("At least one reward must be provided")
+        );
+        let rewards = rewards
+            .into_iter()
+            .map(|(asset, total)| Ok((asset.check(ctx.deps.api)?, total)))
+            .collect::<StdResult<Vec<(AssetInfo, Uint128)>>>()?;
+        ensure!(
+            rewards
+                .iter()
+                .enumerate()
+                .all(|(i, (asset, _))| !rewards[..i].iter().any(|(other, _)| other == asset)),
+            StdError::generic_err("Duplicate reward asset")
+        );
 
         // Query `contract_type` from lockup_contract to verify validity
         let query_msg = native_lockup::contract::QueryMsg::ContractType {};
@@ -69,20 +128,47 @@ impl FixedStakeContract {
             .query_wasm_smart(lockup_contract.clone(), &query_msg)
             .map_err(|error| error)?;
 
-        if contract_type_response.contract != "native" && contract_type_response.contract != "cw721"
-        {
+        if !["native", "cw20", "cw721"].contains(&contract_type_response.contract.as_str()) {
             return Err(StdError::generic_err("Invalid lockup contract type"));
         }
 
+        // Rewards are emitted evenly unless an emission schedule is given
+        let emission = emission.unwrap_or(Emission::Flat);
+        emission.validate()?;
+        let period = Period::new(
+            start_time,
+            end_time,
+            distribution_interval,
+            rewards.clone(),
+            emission,
+        );
+        ensure!(
+            !period.weight()?.is_zero(),
+            StdError::generic_err("Emission schedule must emit rewards")
+        );
+
         self.admin.save(ctx.deps.storage, &ctx.info.sender)?;
         self.lockup_contract
             .save(ctx.deps.storage, &lockup_contract)?;
         self.distribution_interval
             .save(ctx.deps.storage, &distribution_interval)?;
-        self.reward_denom.save(ctx.deps.storage, &reward_denom)?;
-        self.total_rewards.save(ctx.deps.storage, &total_rewards)?;
+        self.rewards.save(ctx.deps.storage, &rewards)?;
         self.start_time.save(ctx.deps.storage, &start_time)?;
         self.end_time.save(ctx.deps.storage, &end_time)?;
+        self.periods.save(ctx.deps.storage, &vec![period])?;
+        self.streaming
+            .save(ctx.deps.storage, &streaming.unwrap_or_default())?;
+        if let Some(vesting) = &vesting {
+            vesting.validate()?;
+        }
+        self.vesting.save(ctx.deps.storage, &vesting)?;
+        let fee = fee.map(|fee| fee.check(ctx.deps.api)).transpose()?;
+        self.fee.save(ctx.deps.storage, &fee)?;
+
+        // Record any rewards sent along with the instantiation
+        for (asset, amount) in self.native_funding(&rewards, &ctx.info.funds)? {
+            self.record_amount(ctx.deps.storage, &self.funded_amount, &asset, amount)?;
+        }
 
         Ok(Response::new())
     }
@@ -108,15 +194,217 @@ impl FixedStakeContract {
             .add_attribute("new_admin", admin.to_string()))
     }
 
+    /// Push back the end of the program, adding `additional_rewards` to be
+    /// distributed from now on
     #[msg(exec)]
-    fn claim_rewards(&self, ctx: ExecCtx, of: (String, String)) -> StdResult<Response> {
-        let of_address = ctx.deps.api.addr_validate(&of.0)?;
-        let claimer = (of_address, of.1);
+    fn extend_program(
+        &self,
+        ctx: ExecCtx,
+        new_end_time: Timestamp,
+        additional_rewards: Vec<(AssetInfoInput, Uint128)>,
+    ) -> StdResult<Response> {
+        // Admin only
+        let admin = self.admin.load(ctx.deps.storage)?;
+        ensure_eq!(
+            admin,
+            ctx.info.sender,
+            StdError::generic_err("Unauthorized")
+        );
+
+        let end_time = self.end_time.load(ctx.deps.storage)?;
+        ensure!(
+            ctx.env.block.time < end_time,
+            StdError::generic_err("Reward distribution period has ended")
+        );
+        ensure!(
+            !self
+                .cancelled
+                .may_load(ctx.deps.storage)?
+                .unwrap_or_default(),
+            StdError::generic_err("Reward program has been cancelled")
+        );
+        ensure!(
+            new_end_time > end_time,
+            StdError::generic_err("New end time must be after the current end time")
+        );
+
+        // Settle the running period and distribute what is left over the extension
+        let (mut periods, mut next) = self.settle(ctx.deps.storage, ctx.env.block.time)?;
+        next.end_time = new_end_time;
 
-        let staking = self.staking.may_load(ctx.deps.storage, claimer.clone())?;
+        // Additional rewards must be paid in the existing reward assets
+        let mut rewards = self.rewards.load(ctx.deps.storage)?;
+        let was_fully_funded = self.is_fully_funded(ctx.deps.storage, &rewards)?;
+        for (asset, amount) in additional_rewards {
+            let asset = asset.check(ctx.deps.api)?;
+            let (_, total) = rewards
+                .iter_mut()
+                .find(|(reward, _)| reward == &asset)
+                .ok_or_else(|| StdError::generic_err("Unknown reward asset"))?;
+            *total = total.checked_add(amount)?;
 
-        let reward_denom = self.reward_denom.load(ctx.deps.storage)?;
-        let total_rewards = self.total_rewards.load(ctx.deps.storage)?;
+            let (_, remaining) = next
+                .rewards
+                .iter_mut()
+                .find(|(reward, _)| reward == &asset)
+                .unwrap();
+            *remaining = remaining.checked_add(amount)?;
+        }
+        ensure!(
+            !next.weight()?.is_zero(),
+            StdError::generic_err("Emission schedule must emit rewards")
+        );
+        periods.push(next);
+
+        self.rewards.save(ctx.deps.storage, &rewards)?;
+        self.periods.save(ctx.deps.storage, &periods)?;
+        self.end_time.save(ctx.deps.storage, &new_end_time)?;
+
+        // Record any rewards sent along with the extension
+        for (asset, amount) in self.native_funding(&rewards, &ctx.info.funds)? {
+            self.record_amount(ctx.deps.storage, &self.funded_amount, &asset, amount)?;
+        }
+
+        // Claims are only open on fully funded programs, so the additional rewards
+        // must be sent along with the extension, or funded beforehand for CW20 assets
+        ensure!(
+            !was_fully_funded || self.is_fully_funded(ctx.deps.storage, &rewards)?,
+            StdError::generic_err("Additional rewards must be funded")
+        );
+
+        Ok(Response::new()
+            .add_attribute("method", "extend_program")
+            .add_attribute("contract_address", ctx.env.contract.address.to_string())
+            .add_attribute("old_end_time", end_time.to_string())
+            .add_attribute("new_end_time", new_end_time.to_string()))
+    }
+
+    /// Change the distribution interval from now on
+    #[msg(exec)]
+    fn update_interval(
+        &self,
+        ctx: ExecCtx,
+        distribution_interval: Timestamp,
+    ) -> StdResult<Response> {
+        // Admin only
+        let admin = self.admin.load(ctx.deps.storage)?;
+        ensure_eq!(
+            admin,
+            ctx.info.sender,
+            StdError::generic_err("Unauthorized")
+        );
+
+        let end_time = self.end_time.load(ctx.deps.storage)?;
+        ensure!(
+            ctx.env.block.time < end_time,
+            StdError::generic_err("Reward distribution period has ended")
+        );
+        ensure!(
+            !self
+                .cancelled
+                .may_load(ctx.deps.storage)?
+                .unwrap_or_default(),
+            StdError::generic_err("Reward program has been cancelled")
+        );
+        ensure!(
+            distribution_interval.seconds() > 0,
+            StdError::generic_err("Distribution interval must be greater than 0")
+        );
+
+        // Settle the running period and distribute what is left at the new interval
+        // The emission schedule restarts on the new interval grid
+        let (mut periods, mut next) = self.settle(ctx.deps.storage, ctx.env.block.time)?;
+        next.distribution_interval = distribution_interval;
+        next.emission_start = next.start_time;
+        ensure!(
+            !next.weight()?.is_zero(),
+            StdError::generic_err("Emission schedule must emit rewards")
+        );
+        periods.push(next);
+
+        let old_interval = self.distribution_interval.load(ctx.deps.storage)?;
+        self.periods.save(ctx.deps.storage, &periods)?;
+        self.distribution_interval
+            .save(ctx.deps.storage, &distribution_interval)?;
+
+        Ok(Response::new()
+            .add_attribute("method", "update_interval")
+            .add_attribute("contract_address", ctx.env.contract.address.to_string())
+            .add_attribute("old_interval", old_interval.seconds().to_string())
+            .add_attribute("new_interval", distribution_interval.seconds().to_string()))
+    }
+
+    /// Stop the program now, reserving the rewards accrued so far for their
+    /// claimers and refunding the rest to the admin
+    #[msg(exec)]
+    fn cancel_program(&self, ctx: ExecCtx) -> StdResult<Response> {
+        // Admin only
+        let admin = self.admin.load(ctx.deps.storage)?;
+        ensure_eq!(
+            admin,
+            ctx.info.sender,
+            StdError::generic_err("Unauthorized")
+        );
+
+        let end_time = self.end_time.load(ctx.deps.storage)?;
+        ensure!(
+            ctx.env.block.time < end_time,
+            StdError::generic_err("Reward distribution period has ended")
+        );
+        ensure!(
+            !self
+                .cancelled
+                .may_load(ctx.deps.storage)?
+                .unwrap_or_default(),
+            StdError::generic_err("Reward program has been cancelled")
+        );
+
+        // Freeze accrual by closing the running period
+        let (periods, next) = self.settle(ctx.deps.storage, ctx.env.block.time)?;
+
+        // Only the rewards emitted until now remain to be distributed
+        let rewards: Vec<(AssetInfo, Uint128)> = self
+            .rewards
+            .load(ctx.deps.storage)?
+            .into_iter()
+            .map(|(asset, total)| {
+                let (_, remaining) = next.rewards.iter().find(|(a, _)| a == &asset).unwrap();
+                (asset, total - *remaining)
+            })
+            .collect();
+
+        self.periods.save(ctx.deps.storage, &periods)?;
+        self.rewards.save(ctx.deps.storage, &rewards)?;
+        self.end_time.save(ctx.deps.storage, &next.start_time)?;
+        self.cancelled.save(ctx.deps.storage, &true)?;
+
+        // Refund everything that is not reserved for claims
+        let contract_address = ctx.env.contract.address;
+        let refunds = self.excess_balances(ctx.deps.as_ref(), &contract_address)?;
+        let send_msgs = transfer_msgs(&ctx.info.sender, &refunds)?;
+
+        let mut res = Response::new()
+            .add_messages(send_msgs)
+            .add_attribute("method", "cancel_program")
+            .add_attribute("contract_address", contract_address.to_string())
+            .add_attribute("sender", ctx.info.sender.to_string())
+            .add_attribute("end_time", next.start_time.to_string());
+        for (asset, amount) in refunds {
+            res = res
+                .add_attribute("asset", asset.to_string())
+                .add_attribute("amount", amount.to_string());
+        }
+
+        Ok(res)
+    }
+
+    #[msg(exec)]
+    fn claim_rewards(&self, ctx: ExecCtx, of: Position) -> StdResult<Response> {
+        of.check(ctx.deps.api)?;
+
+        let staking = self.staking.may_load(ctx.deps.storage, of.to_string())?;
+
+        let rewards = self.rewards.load(ctx.deps.storage)?;
         let start_time = self.start_time.load(ctx.deps.storage)?;
         let end_time = self.end_time.load(ctx.deps.storage)?;
         let distribution_interval = self.distribution_interval.load(ctx.deps.storage)?;
@@ -125,101 +413,127 @@ impl FixedStakeContract {
             ctx.env.block.time > start_time,
             StdError::generic_err("Reward distribution period has not started")
         );
+        // Cancelled programs keep claims open for the rewards accrued until cancellation
+        let cancelled = self
+            .cancelled
+            .may_load(ctx.deps.storage)?
+            .unwrap_or_default();
         ensure!(
-            ctx.env.block.time < end_time,
+            ctx.env.block.time < end_time || cancelled,
             StdError::generic_err("Reward distribution period has ended")
         );
+        ensure!(
+            self.is_fully_funded(ctx.deps.storage, &rewards)?,
+            StdError::generic_err("Reward program is not fully funded")
+        );
 
         // Retrieve lockup contract type
         let lockup_contract = self.lockup_contract.load(ctx.deps.storage)?;
+        let lockup_type = self.lockup_type(ctx.deps.as_ref())?;
+        ensure_eq!(
+            lockup_type,
+            of.lockup_type(),
+            StdError::generic_err("Position does not match lockup contract type")
+        );
 
-        // Make `contract_type` query to lockup_contract
-        let query_msg = cw721_lockup::contract::QueryMsg::ContractType {};
-        let contract_type_response: cw2::ContractVersion = ctx
-            .deps
-            .querier
-            .query_wasm_smart(lockup_contract.clone(), &query_msg)
-            .map_err(|error| error)?;
+        let (lockup, count, owner): (LockupInfo, u128, String) = match &of {
+            // CW20 lockups share the native lockup query interface
+            Position::Native { address } | Position::Cw20 { address } => {
+                let query_msg = native_lockup::contract::QueryMsg::Lockup {
+                    address: address.to_string(),
+                };
+                let lockup: native_lockup::storage::Lockup = ctx
+                    .deps
+                    .querier
+                    .query_wasm_smart(lockup_contract.clone(), &query_msg)
+                    .map_err(|error| error)?;
 
-        let (lockup, count, claimer_token_id): (LockupInfo, u128, String) =
-            match contract_type_response.contract.as_str() {
-                "native" => {
-                    // Verify that claimer.0 is the sender
-                    ensure_eq!(
-                        claimer.0,
-                        ctx.info.sender,
-                        StdError::generic_err("Unauthorized")
-                    );
-
-                    let query_msg = native_lockup::contract::QueryMsg::Lockup {
-                        address: claimer.0.to_string(),
-                    };
-                    let lockup: native_lockup::storage::Lockup = ctx
-                        .deps
-                        .querier
-                        .query_wasm_smart(lockup_contract.clone(), &query_msg)
-                        .map_err(|error| error)?;
-
-                    let count_response: native_lockup::msg::CountResponse = ctx
-                        .deps
-                        .querier
-                        .query_wasm_smart(
-                            lockup_contract,
-                            &native_lockup::contract::QueryMsg::Count {},
-                        )
-                        .map_err(|error| error)?;
-
-                    Ok((
-                        LockupInfo {
-                            owner: None,
-                            amount: lockup.amount,
-                            locked_since: lockup.locked_since,
-                            locked_until: lockup.locked_until,
-                        },
-                        count_response.count,
-                        String::from(""),
-                    ))
-                }
-                "cw721" => {
-                    let query_msg = cw721_lockup::contract::QueryMsg::LockupByToken {
-                        collection_address: claimer.0.to_string(),
-                        token_id: claimer.1.to_string(),
-                    };
-                    let lockup: cw721_lockup::storage::Lockup = ctx
-                        .deps
-                        .querier
-                        .query_wasm_smart(lockup_contract.clone(), &query_msg)
-                        .map_err(|error| error)?;
-
-                    // Verify that the sender is the owner of the lockup
-                    ensure_eq!(
-                        lockup.owner,
-                        ctx.info.sender,
-                        StdError::generic_err("Unauthorized")
-                    );
-
-                    let count_response: cw721_lockup::msg::CountResponse = ctx
-                        .deps
-                        .querier
-                        .query_wasm_smart(
-                            lockup_contract,
-                            &cw721_lockup::contract::QueryMsg::Count {},
-                        )
-                        .map_err(|error| error)?;
-
-                    Ok((
-                        LockupInfo {
-                            owner: Some(lockup.owner),
-                            amount: Uint128::from(1u128),
-                            locked_since: lockup.locked_since,
-                            locked_until: lockup.locked_until,
-                        },
-                        count_response.count,
-                        claimer.clone().1,
-                    ))
-                }
-                &_ => Err(StdError::generic_err("Invalid lockup contract type")),
-            }?;
+                // The receipt holder owns the rewards of the lockup
+                let query_msg = cw721::Cw721QueryMsg::OwnerOf {
+                    token_id: lockup.receipt_id.clone(),
+                    include_expired: None,
+                };
+                let receipt_owner: cw721::OwnerOfResponse = ctx
+                    .deps
+                    .querier
+                    .query_wasm_smart(lockup_contract.clone(), &query_msg)?;
+
+                let count_response: native_lockup::msg::CountResponse = ctx
+                    .deps
+                    .querier
+                    .query_wasm_smart(
+                        lockup_contract,
+                        &native_lockup::contract::QueryMsg::Count {},
+                    )
+                    .map_err(|error| error)?;
+
+                (
+                    LockupInfo {
+                        owner: None,
+                        amount: lockup.amount,
+                        locked_since: lockup.locked_since,
+                        locked_until: lockup.locked_until,
+                    },
+                    count_response.count,
+                    receipt_owner.owner,
+                )
+            }
+            Position::Nft {
+                collection,
+                token_id,
+            } => {
+                let query_msg = cw721_lockup::contract::QueryMsg::LockupByToken {
+                    collection_address: collection.to_string(),
+                    token_id: token_id.to_string(),
+                };
+                let lockup: cw721_lockup::storage::Lockup = ctx
+                    .deps
+                    .querier
+                    .query_wasm_smart(lockup_contract.clone(), &query_msg)
+                    .map_err(|error| error)?;
+
+                // The receipt holder owns the rewards of the lockup
+                let query_msg = cw721::Cw721QueryMsg::OwnerOf {
+                    token_id: lockup.receipt_id.clone(),
+                    include_expired: None,
+                };
+                let receipt_owner: cw721::OwnerOfResponse = ctx
+                    .deps
+                    .querier
+                    .query_wasm_smart(lockup_contract.clone(), &query_msg)?;
+
+                let count_response: cw721_lockup::msg::CountResponse = ctx
+                    .deps
+                    .querier
+                    .query_wasm_smart(lockup_contract, &cw721_lockup::contract::QueryMsg::Count {})
+                    .map_err(|error| error)?;
+
+                (
+                    LockupInfo {
+                        owner: Some(lockup.owner),
+                        amount: Uint128::from(1u128),
+                        locked_since: lockup.locked_since,
+                        locked_until: lockup.locked_until,
+                    },
+                    count_response.count,
+                    receipt_owner.owner,
+                )
+            }
+        };
+
+        // Only the receipt holder or an operator they approved can claim
+        let owner = ctx.deps.api.addr_validate(&owner)?;
+        ensure!(
+            ctx.info.sender == owner
+                || self
+                    .claimers
+                    .has(ctx.deps.storage, (owner.clone(), ctx.info.sender.clone())),
+            StdError::generic_err("Unauthorized")
+        );
+        let recipient = self
+            .reward_recipients
+            .may_load(ctx.deps.storage, owner.clone())?
+            .unwrap_or(owner);
 
         let last_claim = match staking {
             Some(stake) => stake.last_claim,
@@ -229,58 +543,413 @@ impl FixedStakeContract {
             },
         };
 
-        if last_claim.plus_seconds(distribution_interval.seconds()) > ctx.env.block.time {
+        let streaming = self
+            .streaming
+            .may_load(ctx.deps.storage)?
+            .unwrap_or_default();
+        if !streaming
+            && last_claim.plus_seconds(distribution_interval.seconds()) > ctx.env.block.time
+        {
             return Err(StdError::generic_err("Distribution interval not reached"));
         };
 
-        let time =
-            Decimal::from_atomics(ctx.env.block.time.seconds(), 0).unwrap_or(Decimal::zero());
-        let last_claim = Decimal::from_atomics(last_claim.seconds(), 0).unwrap_or(Decimal::zero());
-        let distribution_interval =
-            Decimal::from_atomics(distribution_interval.seconds(), 0).unwrap_or(Decimal::zero());
-
-        let time_factor = (time - last_claim) / distribution_interval;
-        let modulated_time_factor =
-            ((time - last_claim) % distribution_interval) / distribution_interval;
-
-        let total_rewards = Decimal::from_atomics(total_rewards, 0).unwrap_or(Decimal::zero());
-        let end_time = Decimal::from_atomics(end_time.seconds(), 0).unwrap_or(Decimal::zero());
-        let start_time = Decimal::from_atomics(start_time.seconds(), 0).unwrap_or(Decimal::zero());
-        let count = Decimal::from_atomics(count, 0).unwrap_or(Decimal::zero());
-
-        let reward_factor =
-            total_rewards / ((end_time - start_time) / distribution_interval) / count;
-        let reward = (time_factor - modulated_time_factor)
-            * reward_factor
-            * Decimal::from_atomics(lockup.amount, 0).unwrap_or(Decimal::zero());
-
-        let msg = BankMsg::Send {
-            to_address: ctx.info.sender.to_string(),
-            amount: vec![coin(reward.to_uint_floor().u128(), reward_denom.clone())],
+        // Compute the reward owed in each asset
+        let periods = self.periods.load(ctx.deps.storage)?;
+        let (accrued, paid_until) = accrued_rewards(
+            &periods,
+            last_claim,
+            ctx.env.block.time,
+            lockup.amount,
+            count,
+            streaming,
+        )?;
+
+        // Never pay out more than what is left of the program
+        let rewards = accrued
+            .into_iter()
+            .map(|(asset, reward)| {
+                let (_, total) = rewards.iter().find(|(a, _)| a == &asset).unwrap();
+                let distributed = self
+                    .distributed_amount
+                    .may_load(ctx.deps.storage, asset.to_string())?
+                    .unwrap_or_default();
+                Ok((asset, reward.min(total.saturating_sub(distributed))))
+            })
+            .collect::<StdResult<Vec<(AssetInfo, Uint128)>>>()?;
+
+        for (asset, amount) in rewards.iter() {
+            self.record_amount(ctx.deps.storage, &self.distributed_amount, asset, *amount)?;
+        }
+
+        // Take the protocol fee out of each reward
+        let fee = self.fee.may_load(ctx.deps.storage)?.flatten();
+        let (rewards, fees): (Vec<_>, Vec<_>) = match &fee {
+            Some(fee) => rewards
+                .into_iter()
+                .map(|(asset, amount)| {
+                    let (amount, fee_amount) = fee.split(amount);
+                    ((asset.clone(), amount), (asset, fee_amount))
+                })
+                .unzip(),
+            None => (rewards, vec![]),
+        };
+
+        // Route the rewards into a vesting tranche if the program vests them
+        let vesting = self.vesting.may_load(ctx.deps.storage)?.flatten();
+        let send_msgs = match vesting {
+            Some(ref vesting) => {
+                let tranche = VestingTranche::new(ctx.env.block.time, rewards.clone());
+                let tranches = self
+                    .vesting_tranches
+                    .may_load(ctx.deps.storage, recipient.clone())?
+                    .unwrap_or_default();
+                let tranches = vesting.add_tranche(tranches, tranche);
+                self.vesting_tranches
+                    .save(ctx.deps.storage, recipient.clone(), &tranches)?;
+
+                for (asset, amount) in rewards.iter() {
+                    self.record_amount(ctx.deps.storage, &self.vesting_amount, asset, *amount)?;
+                }
+                vec![]
+            }
+            None => transfer_msgs(&recipient, &rewards)?,
         };
-        let send_msg = SubMsg::new(msg);
 
-        // Update staking information
+        // Update staking information, keeping any partial interval for the next claim
         let new_staking = Stake {
-            last_claim: ctx.env.block.time,
+            last_claim: paid_until,
         };
-        self.staking.save(
+        self.staking
+            .save(ctx.deps.storage, of.to_string(), &new_staking)?;
+
+        let mut res = Response::new().add_messages(send_msgs);
+        if let Some(fee) = fee {
+            res = res.add_messages(transfer_msgs(&fee.collector, &fees)?);
+            for (asset, amount) in fees.into_iter().filter(|(_, amount)| !amount.is_zero()) {
+                res = res.add_event(
+                    Event::new("protocol_fee")
+                        .add_attribute("collector", fee.collector.to_string())
+                        .add_attribute("asset", asset.to_string())
+                        .add_attribute("amount", amount.to_string()),
+                );
+            }
+        }
+
+        res = res
+            .add_attribute("method", "claim_rewards")
+            .add_attribute("contract_address", ctx.env.contract.address.to_string())
+            .add_attribute("sender", ctx.info.sender.to_string())
+            .add_attribute("recipient", recipient.to_string())
+            .add_attribute("vested", vesting.is_some().to_string());
+        for (asset, amount) in rewards {
+            res = res
+                .add_attribute("asset", asset.to_string())
+                .add_attribute("amount", amount.to_string());
+        }
+
+        Ok(res)
+    }
+
+    /// Convert the single reward of contracts deployed before multiple rewards into
+    /// a reward list and schedule, and move claim information stored under
+    /// `(address, token_id)` keys to positions
+    #[msg(migrate)]
+    fn migrate(&self, ctx: MigrateCtx) -> StdResult<Response> {
+        let legacy_reward_denom: Item<'static, String> = Item::new("reward_denom");
+        let legacy_total_rewards: Item<'static, Uint128> = Item::new("total_rewards");
+        let legacy_staking: Map<'static, (Addr, String), Stake> = Map::new("staking");
+
+        if let Some(reward_denom) = legacy_reward_denom.may_load(ctx.deps.storage)? {
+            let total_rewards = legacy_total_rewards.load(ctx.deps.storage)?;
+            let asset = AssetInfo::Native(reward_denom);
+            let rewards = vec![(asset.clone(), total_rewards)];
+
+            // The legacy schedule distributed the total evenly over the whole program
+            let period = Period::new(
+                self.start_time.load(ctx.deps.storage)?,
+                self.end_time.load(ctx.deps.storage)?,
+                self.distribution_interval.load(ctx.deps.storage)?,
+                rewards.clone(),
+                Emission::Flat,
+            );
+
+            // Funding was not tracked, the legacy contract was funded with the total
+            // up front and what it no longer holds was paid out in claims
+            let balance = asset.query_balance(&ctx.deps.querier, &ctx.env.contract.address)?;
+            let distributed = total_rewards.saturating_sub(balance);
+
+            self.rewards.save(ctx.deps.storage, &rewards)?;
+            self.periods.save(ctx.deps.storage, &vec![period])?;
+            self.funded_amount
+                .save(ctx.deps.storage, asset.to_string(), &total_rewards)?;
+            self.distributed_amount
+                .save(ctx.deps.storage, asset.to_string(), &distributed)?;
+            legacy_reward_denom.remove(ctx.deps.storage);
+            legacy_total_rewards.remove(ctx.deps.storage);
+        }
+
+        let lockup_type = self.lockup_type(ctx.deps.as_ref())?;
+
+        let entries = legacy_staking
+            .range(ctx.deps.storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        for ((address, token_id), stake) in entries.iter() {
+            let position =
+                Position::from_lockup(&lockup_type, address.to_string(), token_id.to_string())?;
+            self.staking
+                .save(ctx.deps.storage, position.to_string(), stake)?;
+            legacy_staking.remove(ctx.deps.storage, (address.clone(), token_id.clone()));
+        }
+
+        Ok(Response::new()
+            .add_attribute("method", "migrate")
+            .add_attribute("contract_address", ctx.env.contract.address.to_string())
+            .add_attribute("migrated", entries.len().to_string()))
+    }
+
+    /// Replace the protocol fee, up to the maximum fee
+    #[msg(exec)]
+    fn update_fee(&self, ctx: ExecCtx, fee: Option<FeeConfigInput>) -> StdResult<Response> {
+        // Admin only
+        let admin = self.admin.load(ctx.deps.storage)?;
+        ensure_eq!(
+            admin,
+            ctx.info.sender,
+            StdError::generic_err("Unauthorized")
+        );
+
+        let fee = fee.map(|fee| fee.check(ctx.deps.api)).transpose()?;
+        self.fee.save(ctx.deps.storage, &fee)?;
+
+        Ok(Response::new()
+            .add_attribute("method", "update_fee")
+            .add_attribute("contract_address", ctx.env.contract.address.to_string())
+            .add_attribute("sender", ctx.info.sender.to_string())
+            .add_attribute(
+                "fee_bps",
+                fee.map(|fee| fee.fee_bps).unwrap_or_default().to_string(),
+            ))
+    }
+
+    /// Send the sender's rewards to `recipient` from now on. Vested rewards are
+    /// merged into the recipient's existing tranches, so redirected claims cannot
+    /// grow the recipient's vesting schedule without bound
+    #[msg(exec)]
+    fn set_reward_recipient(&self, ctx: ExecCtx, recipient: String) -> StdResult<Response> {
+        let recipient = ctx.deps.api.addr_validate(&recipient)?;
+
+        match recipient == ctx.info.sender {
+            true => self
+                .reward_recipients
+                .remove(ctx.deps.storage, ctx.info.sender.clone()),
+            false => self.reward_recipients.save(
+                ctx.deps.storage,
+                ctx.info.sender.clone(),
+                &recipient,
+            )?,
+        }
+
+        Ok(Response::new()
+            .add_attribute("method", "set_reward_recipient")
+            .add_attribute("contract_address", ctx.env.contract.address.to_string())
+            .add_attribute("sender", ctx.info.sender.to_string())
+            .add_attribute("recipient", recipient.to_string()))
+    }
+
+    /// Allow `operator` to claim the sender's rewards
+    #[msg(exec)]
+    fn approve_claimer(&self, ctx: ExecCtx, operator: String) -> StdResult<Response> {
+        let operator = ctx.deps.api.addr_validate(&operator)?;
+
+        self.claimers.save(
             ctx.deps.storage,
-            (claimer.0, claimer_token_id),
-            &new_staking,
+            (ctx.info.sender.clone(), operator.clone()),
+            &Empty {},
         )?;
 
-        let res = Response::new()
-            .add_submessage(send_msg)
-            .add_attribute("method", "claim_rewards")
+        Ok(Response::new()
+            .add_attribute("method", "approve_claimer")
+            .add_attribute("contract_address", ctx.env.contract.address.to_string())
+            .add_attribute("sender", ctx.info.sender.to_string())
+            .add_attribute("operator", operator.to_string()))
+    }
+
+    #[msg(exec)]
+    fn revoke_claimer(&self, ctx: ExecCtx, operator: String) -> StdResult<Response> {
+        let operator = ctx.deps.api.addr_validate(&operator)?;
+
+        ensure!(
+            self.claimers.has(
+                ctx.deps.storage,
+                (ctx.info.sender.clone(), operator.clone())
+            ),
+            StdError::generic_err("Operator is not approved")
+        );
+        self.claimers.remove(
+            ctx.deps.storage,
+            (ctx.info.sender.clone(), operator.clone()),
+        );
+
+        Ok(Response::new()
+            .add_attribute("method", "revoke_claimer")
             .add_attribute("contract_address", ctx.env.contract.address.to_string())
             .add_attribute("sender", ctx.info.sender.to_string())
-            .add_attribute("denom", reward_denom)
-            .add_attribute("amount", reward.to_string());
+            .add_attribute("operator", operator.to_string()))
+    }
+
+    /// Release the vested part of the sender's claimed rewards
+    #[msg(exec)]
+    fn claim_vested(&self, ctx: ExecCtx) -> StdResult<Response> {
+        let vesting = self
+            .vesting
+            .may_load(ctx.deps.storage)?
+            .flatten()
+            .ok_or_else(|| StdError::generic_err("Rewards do not vest"))?;
+
+        let tranches = self
+            .vesting_tranches
+            .may_load(ctx.deps.storage, ctx.info.sender.clone())?
+            .unwrap_or_default();
+
+        let mut released: Vec<(AssetInfo, Uint128)> = vec![];
+        let mut remaining: Vec<VestingTranche> = vec![];
+        for mut tranche in tranches {
+            let releasable = tranche.releasable(&vesting, ctx.env.block.time);
+            for ((asset, amount), (_, tranche_released)) in
+                releasable.into_iter().zip(tranche.released.iter_mut())
+            {
+                *tranche_released += amount;
+                match released.iter_mut().find(|(released, _)| released == &asset) {
+                    Some((_, total)) => *total += amount,
+                    None => released.push((asset, amount)),
+                }
+            }
+
+            if !tranche.is_released() {
+                remaining.push(tranche);
+            }
+        }
+
+        ensure!(
+            released.iter().any(|(_, amount)| !amount.is_zero()),
+            StdError::generic_err("No vested rewards to claim")
+        );
+
+        self.vesting_tranches
+            .save(ctx.deps.storage, ctx.info.sender.clone(), &remaining)?;
+        for (asset, amount) in released.iter() {
+            self.vesting_amount.update(
+                ctx.deps.storage,
+                asset.to_string(),
+                |total| -> StdResult<_> { Ok(total.unwrap_or_default().checked_sub(*amount)?) },
+            )?;
+        }
+
+        let send_msgs = transfer_msgs(&ctx.info.sender, &released)?;
+
+        let mut res = Response::new()
+            .add_messages(send_msgs)
+            .add_attribute("method", "claim_vested")
+            .add_attribute("contract_address", ctx.env.contract.address.to_string())
+            .add_attribute("sender", ctx.info.sender.to_string());
+        for (asset, amount) in released {
+            res = res
+                .add_attribute("asset", asset.to_string())
+                .add_attribute("amount", amount.to_string());
+        }
 
         Ok(res)
     }
 
+    #[msg(exec)]
+    fn migrate_claim(
+        &self,
+        ctx: ExecCtx,
+        from: (String, String),
+        to: (String, String),
+    ) -> StdResult<Response> {
+        // Lockup contract only
+        let lockup_contract = self.lockup_contract.load(ctx.deps.storage)?;
+        ensure_eq!(
+            lockup_contract,
+            ctx.info.sender,
+            StdError::generic_err("Unauthorized")
+        );
+
+        let lockup_type = self.lockup_type(ctx.deps.as_ref())?;
+        let from = Position::from_lockup(&lockup_type, from.0, from.1)?;
+        let to = Position::from_lockup(&lockup_type, to.0, to.1)?;
+        from.check(ctx.deps.api)?;
+        to.check(ctx.deps.api)?;
+
+        // Move the claim information to the new position
+        // If the previous owner never claimed, clear any stale entry of the recipient
+        match self.staking.may_load(ctx.deps.storage, from.to_string())? {
+            Some(stake) => {
+                self.staking.remove(ctx.deps.storage, from.to_string());
+                self.staking
+                    .save(ctx.deps.storage, to.to_string(), &stake)?;
+            }
+            None => self.staking.remove(ctx.deps.storage, to.to_string()),
+        }
+
+        Ok(Response::new()
+            .add_attribute("method", "migrate_claim")
+            .add_attribute("contract_address", ctx.env.contract.address.to_string())
+            .add_attribute("from", from.to_string())
+            .add_attribute("to", to.to_string()))
+    }
+
+    /// Fund the program with native rewards
+    #[msg(exec)]
+    fn fund(&self, ctx: ExecCtx) -> StdResult<Response> {
+        ensure!(
+            !ctx.info.funds.is_empty(),
+            StdError::generic_err("No funds sent")
+        );
+
+        let rewards = self.rewards.load(ctx.deps.storage)?;
+        let funding = self.native_funding(&rewards, &ctx.info.funds)?;
+
+        let mut res = Response::new()
+            .add_attribute("method", "fund")
+            .add_attribute("contract_address", ctx.env.contract.address.to_string())
+            .add_attribute("sender", ctx.info.sender.to_string());
+        for (asset, amount) in funding {
+            self.record_amount(ctx.deps.storage, &self.funded_amount, &asset, amount)?;
+            res = res
+                .add_attribute("asset", asset.to_string())
+                .add_attribute("amount", amount.to_string());
+        }
+
+        Ok(res)
+    }
+
+    /// Fund the program with CW20 rewards through `Cw20ExecuteMsg::Send`
+    #[msg(exec)]
+    fn receive(&self, ctx: ExecCtx, sender: String, amount: Uint128) -> StdResult<Response> {
+        // Only CW20 reward tokens can fund the program
+        let reward_asset = AssetInfo::Cw20(ctx.info.sender.clone());
+        let rewards = self.rewards.load(ctx.deps.storage)?;
+        ensure!(
+            rewards.iter().any(|(asset, _)| asset == &reward_asset),
+            StdError::generic_err("Unsupported token sent")
+        );
+        ensure!(
+            !amount.is_zero(),
+            StdError::generic_err("Funds sent must be greater than 0")
+        );
+
+        self.record_amount(ctx.deps.storage, &self.funded_amount, &reward_asset, amount)?;
+
+        Ok(Response::new()
+            .add_attribute("method", "receive")
+            .add_attribute("contract_address", ctx.env.contract.address.to_string())
+            .add_attribute("sender", sender)
+            .add_attribute("asset", reward_asset.to_string())
+            .add_attribute("amount", amount.to_string()))
+    }
+
     #[msg(exec)]
     fn withdraw_excess_balance(&self, ctx: ExecCtx) -> StdResult<Response> {
         // Admin only
@@ -298,30 +967,24 @@ impl FixedStakeContract {
             StdError::generic_err("End time has not been reached")
         );
 
-        let reward_denom = self.reward_denom.load(ctx.deps.storage)?;
-
-        let contract_address = ctx.env.contract.address.to_string();
-        let contract_balance = ctx
-            .deps
-            .querier
-            .query_balance(&contract_address, reward_denom.clone())
-            .map_err(|error| error)?;
-
-        // Send remaining balance to caller
-        let msg = BankMsg::Send {
-            to_address: ctx.info.sender.to_string(),
-            amount: vec![coin(contract_balance.amount.u128(), reward_denom.clone())],
-        };
+        let contract_address = ctx.env.contract.address;
+        let balances = self.excess_balances(ctx.deps.as_ref(), &contract_address)?;
 
-        let send_msg = SubMsg::new(msg);
+        // Send remaining balances to caller
+        let send_msgs = transfer_msgs(&ctx.info.sender, &balances)?;
 
-        Ok(Response::new()
-            .add_submessage(send_msg)
+        let mut res = Response::new()
+            .add_messages(send_msgs)
             .add_attribute("method", "withdraw_excess_balance")
-            .add_attribute("contract_address", contract_address)
-            .add_attribute("sender", ctx.info.sender.to_string())
-            .add_attribute("denom", reward_denom)
-            .add_attribute("amount", contract_balance.amount.to_string()))
+            .add_attribute("contract_address", contract_address.to_string())
+            .add_attribute("sender", ctx.info.sender.to_string());
+        for (asset, amount) in balances {
+            res = res
+                .add_attribute("asset", asset.to_string())
+                .add_attribute("amount", amount.to_string());
+        }
+
+        Ok(res)
     }
 
     #[msg(query)]
@@ -329,8 +992,7 @@ impl FixedStakeContract {
         let admin = self.admin.load(ctx.deps.storage)?;
         let lockup_contract = self.lockup_contract.load(ctx.deps.storage)?;
         let distribution_interval = self.distribution_interval.load(ctx.deps.storage)?;
-        let reward_denom = self.reward_denom.load(ctx.deps.storage)?;
-        let total_rewards = self.total_rewards.load(ctx.deps.storage)?;
+        let rewards = self.rewards.load(ctx.deps.storage)?;
         let start_time = self.start_time.load(ctx.deps.storage)?;
         let end_time = self.end_time.load(ctx.deps.storage)?;
 
@@ -338,20 +1000,264 @@ impl FixedStakeContract {
             admin,
             lockup_contract,
             distribution_interval,
-            reward_denom,
-            total_rewards,
+            rewards,
             start_time,
             end_time,
+            cancelled: self
+                .cancelled
+                .may_load(ctx.deps.storage)?
+                .unwrap_or_default(),
+            streaming: self
+                .streaming
+                .may_load(ctx.deps.storage)?
+                .unwrap_or_default(),
+            vesting: self.vesting.may_load(ctx.deps.storage)?.flatten(),
         })
     }
 
     #[msg(query)]
-    fn query_last_claim(&self, ctx: QueryCtx, of: (String, String)) -> StdResult<Timestamp> {
-        let of_address = ctx.deps.api.addr_validate(&of.0)?;
-        let claimer = (of_address, of.1);
+    fn funding(&self, ctx: QueryCtx) -> StdResult<FundingResponse> {
+        let rewards = self.rewards.load(ctx.deps.storage)?;
+
+        let load_amounts = |ledger: &Map<'static, String, Uint128>| {
+            rewards
+                .iter()
+                .map(|(asset, _)| {
+                    let amount = ledger
+                        .may_load(ctx.deps.storage, asset.to_string())?
+                        .unwrap_or_default();
+                    Ok((asset.clone(), amount))
+                })
+                .collect::<StdResult<Vec<(AssetInfo, Uint128)>>>()
+        };
+
+        Ok(FundingResponse {
+            funded_amount: load_amounts(&self.funded_amount)?,
+            distributed_amount: load_amounts(&self.distributed_amount)?,
+            fully_funded: self.is_fully_funded(ctx.deps.storage, &rewards)?,
+        })
+    }
+
+    #[msg(query)]
+    fn periods(&self, ctx: QueryCtx) -> StdResult<Vec<Period>> {
+        self.periods.load(ctx.deps.storage)
+    }
+
+    #[msg(query)]
+    fn emission_at(&self, ctx: QueryCtx, time: Timestamp) -> StdResult<Vec<(AssetInfo, Uint128)>> {
+        let periods = self.periods.load(ctx.deps.storage)?;
+
+        match periods
+            .iter()
+            .find(|period| period.start_time <= time && time < period.end_time)
+        {
+            Some(period) => period.emission_at(time),
+            // Nothing is emitted outside of the program
+            None => Ok(self
+                .rewards
+                .load(ctx.deps.storage)?
+                .into_iter()
+                .map(|(asset, _)| (asset, Uint128::zero()))
+                .collect()),
+        }
+    }
+
+    #[msg(query)]
+    fn fee_config(&self, ctx: QueryCtx) -> StdResult<FeeConfigResponse> {
+        Ok(FeeConfigResponse {
+            fee: self.fee.may_load(ctx.deps.storage)?.flatten(),
+            max_fee_bps: MAX_FEE_BPS,
+        })
+    }
+
+    #[msg(query)]
+    fn reward_recipient(&self, ctx: QueryCtx, owner: String) -> StdResult<Addr> {
+        let owner = ctx.deps.api.addr_validate(&owner)?;
+
+        Ok(self
+            .reward_recipients
+            .may_load(ctx.deps.storage, owner.clone())?
+            .unwrap_or(owner))
+    }
+
+    #[msg(query)]
+    fn claimers(&self, ctx: QueryCtx, owner: String) -> StdResult<Vec<Addr>> {
+        let owner = ctx.deps.api.addr_validate(&owner)?;
+
+        self.claimers
+            .prefix(owner)
+            .keys(ctx.deps.storage, None, None, Order::Ascending)
+            .collect()
+    }
+
+    #[msg(query)]
+    fn vesting_schedule(
+        &self,
+        ctx: QueryCtx,
+        address: String,
+    ) -> StdResult<VestingScheduleResponse> {
+        let address = ctx.deps.api.addr_validate(&address)?;
+        let tranches = self
+            .vesting_tranches
+            .may_load(ctx.deps.storage, address)?
+            .unwrap_or_default();
 
-        let staking = self.staking.load(ctx.deps.storage, claimer)?;
+        let mut releasable: Vec<(AssetInfo, Uint128)> = vec![];
+        if let Some(vesting) = self.vesting.may_load(ctx.deps.storage)?.flatten() {
+            for tranche in tranches.iter() {
+                for (asset, amount) in tranche.releasable(&vesting, ctx.env.block.time) {
+                    match releasable
+                        .iter_mut()
+                        .find(|(releasable, _)| releasable == &asset)
+                    {
+                        Some((_, total)) => *total += amount,
+                        None => releasable.push((asset, amount)),
+                    }
+                }
+            }
+        }
+
+        Ok(VestingScheduleResponse {
+            tranches,
+            releasable,
+        })
+    }
+
+    #[msg(query)]
+    fn query_last_claim(&self, ctx: QueryCtx, of: Position) -> StdResult<Timestamp> {
+        let staking = self.staking.load(ctx.deps.storage, of.to_string())?;
 
         Ok(staking.last_claim)
     }
 }
+
+impl FixedStakeContract {
+    /// Type of the linked lockup contract, as reported by its `contract_type` query
+    fn lockup_type(&self, deps: Deps) -> StdResult<String> {
+        let lockup_contract = self.lockup_contract.load(deps.storage)?;
+        let contract_type_response: cw2::ContractVersion = deps.querier.query_wasm_smart(
+            lockup_contract,
+            &native_lockup::contract::QueryMsg::ContractType {},
+        )?;
+
+        Ok(contract_type_response.contract)
+    }
+
+    /// Match the coins sent to a native reward asset each
+    fn native_funding(
+        &self,
+        rewards: &[(AssetInfo, Uint128)],
+        funds: &[Coin],
+    ) -> StdResult<Vec<(AssetInfo, Uint128)>> {
+        funds
+            .iter()
+            .map(|coin| {
+                let asset = AssetInfo::Native(coin.denom.clone());
+                ensure!(
+                    rewards.iter().any(|(reward, _)| reward == &asset),
+                    StdError::generic_err("Unsupported token sent")
+                );
+                ensure!(
+                    !coin.amount.is_zero(),
+                    StdError::generic_err("Funds sent must be greater than 0")
+                );
+                Ok((asset, coin.amount))
+            })
+            .collect()
+    }
+
+    /// Add `amount` to the ledger entry of `asset`
+    fn record_amount(
+        &self,
+        storage: &mut dyn Storage,
+        ledger: &Map<'static, String, Uint128>,
+        asset: &AssetInfo,
+        amount: Uint128,
+    ) -> StdResult<()> {
+        ledger.update(storage, asset.to_string(), |total| -> StdResult<_> {
+            Ok(total.unwrap_or_default().checked_add(amount)?)
+        })?;
+        Ok(())
+    }
+
+    /// Close the running period at `time` (or at its start if it has not begun
+    /// yet), returning the closed periods and the continuation of the running one
+    fn settle(&self, storage: &dyn Storage, time: Timestamp) -> StdResult<(Vec<Period>, Period)> {
+        let mut periods = self.periods.load(storage)?;
+        let mut last = periods.pop().unwrap();
+
+        let time = time.max(last.start_time);
+        let end_time = last.end_time;
+        let remaining = last.split_at(time)?;
+
+        // Drop the period if it closed before distributing anything
+        if last.end_time > last.start_time {
+            periods.push(last.clone());
+        }
+
+        let mut next = Period::new(
+            time,
+            end_time,
+            last.distribution_interval,
+            remaining,
+            last.emission.clone(),
+        );
+        next.emission_start = last.emission_start;
+
+        Ok((periods, next))
+    }
+
+    /// Balance of each reward asset held by the contract, minus the rewards
+    /// still vesting and those reserved for claims if the program was cancelled
+    fn excess_balances(&self, deps: Deps, address: &Addr) -> StdResult<Vec<(AssetInfo, Uint128)>> {
+        let cancelled = self.cancelled.may_load(deps.storage)?.unwrap_or_default();
+
+        self.rewards
+            .load(deps.storage)?
+            .into_iter()
+            .map(|(asset, total)| {
+                let vesting = self
+                    .vesting_amount
+                    .may_load(deps.storage, asset.to_string())?
+                    .unwrap_or_default();
+                let balance = asset
+                    .query_balance(&deps.querier, address)?
+                    .saturating_sub(vesting);
+                if !cancelled {
+                    return Ok((asset, balance));
+                }
+
+                let distributed = self
+                    .distributed_amount
+                    .may_load(deps.storage, asset.to_string())?
+                    .unwrap_or_default();
+                let reserved = total.saturating_sub(distributed);
+                Ok((asset, balance.saturating_sub(reserved)))
+            })
+            .collect()
+    }
+
+    /// Whether every reward asset has been funded up to its total
+    fn is_fully_funded(
+        &self,
+        storage: &dyn Storage,
+        rewards: &[(AssetInfo, Uint128)],
+    ) -> StdResult<bool> {
+        for (asset, total) in rewards {
+            let funded = self
+                .funded_amount
+                .may_load(storage, asset.to_string())?
+                .unwrap_or_default();
+            if funded < *total {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+}
+
+/// Migrate entry point, not generated by `#[entry_points]`
+#[cosmwasm_std::entry_point]
+pub fn migrate(deps: DepsMut, env: Env, msg: MigrateMsg) -> StdResult<Response> {
+    msg.dispatch(&FixedStakeContract::new(), (deps, env))
+}
@@ -0,0 +1,129 @@
+use cosmwasm_std::{
+    coin, ensure, Addr, BankMsg, Coin, CosmosMsg, Decimal256, StdError, StdResult, Timestamp,
+    Uint128, Uint256,
+};
+
+use crate::storage::{AssetInfo, Period};
+
+/// Build the messages sending `assets` to `recipient`, batching all native
+/// coins into a single `BankMsg::Send` and skipping empty amounts
+pub fn transfer_msgs(
+    recipient: &Addr,
+    assets: &[(AssetInfo, Uint128)],
+) -> StdResult<Vec<CosmosMsg>> {
+    let mut coins: Vec<Coin> = vec![];
+    let mut msgs: Vec<CosmosMsg> = vec![];
+
+    for (asset, amount) in assets.iter().filter(|(_, amount)| !amount.is_zero()) {
+        match asset {
+            AssetInfo::Native(denom) => coins.push(coin(amount.u128(), denom)),
+            AssetInfo::Cw20(_) => msgs.push(asset.transfer_msg(recipient, *amount)?),
+        }
+    }
+
+    if !coins.is_empty() {
+        msgs.insert(
+            0,
+            BankMsg::Send {
+                to_address: recipient.to_string(),
+                amount: coins,
+            }
+            .into(),
+        );
+    }
+
+    Ok(msgs)
+}
+
+/// Convert an integer to a `Decimal256`, failing instead of saturating
+pub fn to_decimal256(value: impl Into<Uint256>) -> StdResult<Decimal256> {
+    Decimal256::checked_from_ratio(value, 1u8)
+        .map_err(|_| StdError::generic_err("Reward calculation overflow"))
+}
+
+/// Divide two decimals, failing on a zero denominator or an overflow
+pub fn checked_div(numerator: Decimal256, denominator: Decimal256) -> StdResult<Decimal256> {
+    numerator
+        .checked_div(denominator)
+        .map_err(|error| StdError::generic_err(format!("Reward calculation error: {}", error)))
+}
+
+/// Compute `value * numerator / denominator` without intermediate rounding
+pub fn checked_mul_ratio(
+    value: Decimal256,
+    numerator: Decimal256,
+    denominator: Decimal256,
+) -> StdResult<Decimal256> {
+    let atomics = value
+        .atomics()
+        .checked_multiply_ratio(numerator.atomics(), denominator.atomics())
+        .map_err(|error| StdError::generic_err(format!("Reward calculation error: {}", error)))?;
+    Ok(Decimal256::new(atomics))
+}
+
+/// Compute the rewards accrued by a lockup of `amount` out of `count` between
+/// `last_claim` and `time`, along with the time they are paid until. Unless
+/// `streaming`, only whole intervals are paid in the running period, while
+/// periods that were closed early are settled exactly up to their end.
+pub fn accrued_rewards(
+    periods: &[Period],
+    last_claim: Timestamp,
+    time: Timestamp,
+    amount: Uint128,
+    count: u128,
+    streaming: bool,
+) -> StdResult<(Vec<(AssetInfo, Uint128)>, Timestamp)> {
+    ensure!(
+        count > 0,
+        StdError::generic_err("Lockup count must be greater than 0")
+    );
+
+    let mut accrued: Vec<(AssetInfo, Decimal256)> = vec![];
+    let mut paid_until = time;
+
+    for period in periods {
+        let from = last_claim.max(period.start_time);
+        let mut to = time.min(period.end_time);
+        if to <= from {
+            continue;
+        }
+
+        // Round down to whole intervals in the running period
+        if period.end_time > time && !streaming {
+            let interval = period.distribution_interval.seconds();
+            let elapsed = to.seconds() - from.seconds();
+            to = from.plus_seconds(elapsed - elapsed % interval);
+            paid_until = to;
+        }
+
+        let weight = period.weight_between(from, to)?;
+        let period_weight = period.weight()?;
+        if weight.is_zero() || period_weight.is_zero() {
+            continue;
+        }
+
+        for (asset, total_rewards) in period.rewards.iter() {
+            // Share of the period owed to the lockup, then the part emitted so far
+            let holder_rewards =
+                Decimal256::checked_from_ratio(total_rewards.full_mul(amount), count).map_err(
+                    |error| StdError::generic_err(format!("Reward calculation error: {}", error)),
+                )?;
+            let reward = checked_mul_ratio(holder_rewards, weight, period_weight)?;
+
+            match accrued
+                .iter_mut()
+                .find(|(accrued_asset, _)| accrued_asset == asset)
+            {
+                Some((_, total)) => *total = total.checked_add(reward)?,
+                None => accrued.push((asset.clone(), reward)),
+            }
+        }
+    }
+
+    let accrued = accrued
+        .into_iter()
+        .map(|(asset, reward)| Ok((asset, Uint128::try_from(reward.to_uint_floor())?)))
+        .collect::<StdResult<Vec<(AssetInfo, Uint128)>>>()?;
+
+    Ok((accrued, paid_until))
+}
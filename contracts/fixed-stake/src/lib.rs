@@ -3,5 +3,6 @@ pub const ACTOR_ID: &str = "fixed";
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
 
 pub mod contract;
+pub mod helpers;
 pub mod msg;
 pub mod storage;
@@ -1,13 +1,36 @@
 use cosmwasm_schema::cw_serde;
 use cosmwasm_std::{Addr, Timestamp, Uint128};
 
+use crate::storage::{AssetInfo, FeeConfig, VestingConfig, VestingTranche};
+
 #[cw_serde]
 pub struct ConfigResponse {
     pub admin: Addr,
     pub lockup_contract: Addr,
     pub distribution_interval: Timestamp,
-    pub reward_denom: String,
-    pub total_rewards: Uint128,
+    pub rewards: Vec<(AssetInfo, Uint128)>,
     pub start_time: Timestamp,
     pub end_time: Timestamp,
+    pub cancelled: bool,
+    pub streaming: bool,
+    pub vesting: Option<VestingConfig>,
+}
+
+#[cw_serde]
+pub struct FundingResponse {
+    pub funded_amount: Vec<(AssetInfo, Uint128)>,
+    pub distributed_amount: Vec<(AssetInfo, Uint128)>,
+    pub fully_funded: bool,
+}
+
+#[cw_serde]
+pub struct VestingScheduleResponse {
+    pub tranches: Vec<VestingTranche>,
+    pub releasable: Vec<(AssetInfo, Uint128)>,
+}
+
+#[cw_serde]
+pub struct FeeConfigResponse {
+    pub fee: Option<FeeConfig>,
+    pub max_fee_bps: u16,
 }
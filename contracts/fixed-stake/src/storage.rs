@@ -1,5 +1,13 @@
+use std::fmt;
+
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Addr, Timestamp, Uint128};
+use cosmwasm_std::{
+    coin, ensure, to_json_binary, Addr, Api, BankMsg, CosmosMsg, Decimal, Decimal256,
+    QuerierWrapper, StdError, StdResult, Timestamp, Uint128, Uint256, WasmMsg,
+};
+use cw20::{BalanceResponse, Cw20ExecuteMsg, Cw20QueryMsg};
+
+use crate::helpers::{checked_div, checked_mul_ratio, to_decimal256};
 
 #[cw_serde]
 pub struct Stake {
@@ -12,6 +20,70 @@ impl Stake {
     }
 }
 
+/// Staking position rewards are claimed for
+#[cw_serde]
+pub enum Position {
+    Native {
+        address: String,
+    },
+    Nft {
+        collection: String,
+        token_id: String,
+    },
+    Cw20 {
+        address: String,
+    },
+}
+
+impl Position {
+    /// Build a position from the `(address, token_id)` pair used by lockup contracts
+    pub fn from_lockup(lockup_type: &str, address: String, token_id: String) -> StdResult<Self> {
+        match lockup_type {
+            "native" => Ok(Position::Native { address }),
+            "cw20" => Ok(Position::Cw20 { address }),
+            "cw721" => Ok(Position::Nft {
+                collection: address,
+                token_id,
+            }),
+            _ => Err(StdError::generic_err("Invalid lockup contract type")),
+        }
+    }
+
+    /// Type of the lockup contract holding the position
+    pub fn lockup_type(&self) -> &str {
+        match self {
+            Position::Native { .. } => "native",
+            Position::Nft { .. } => "cw721",
+            Position::Cw20 { .. } => "cw20",
+        }
+    }
+
+    pub fn check(&self, api: &dyn Api) -> StdResult<()> {
+        match self {
+            Position::Native { address } | Position::Cw20 { address } => {
+                api.addr_validate(address)?;
+            }
+            Position::Nft { collection, .. } => {
+                api.addr_validate(collection)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Position::Native { address } => write!(f, "native:{}", address),
+            Position::Nft {
+                collection,
+                token_id,
+            } => write!(f, "nft:{}:{}", collection, token_id),
+            Position::Cw20 { address } => write!(f, "cw20:{}", address),
+        }
+    }
+}
+
 #[cw_serde]
 pub struct LockupInfo {
     pub owner: Option<Addr>,
@@ -19,3 +91,474 @@ pub struct LockupInfo {
     pub locked_since: Timestamp,
     pub locked_until: Timestamp,
 }
+
+/// Shape of the emission over the intervals of a period, as relative weights
+#[cw_serde]
+pub enum Emission {
+    /// Same emission every interval
+    Flat,
+    /// Emission decreasing linearly to zero over `intervals` intervals
+    LinearDecay { intervals: u64 },
+    /// Emission halving every `intervals` intervals
+    Halving { intervals: u64 },
+    /// Explicit weight of each interval, the last one applying to all later intervals
+    Table { weights: Vec<Decimal> },
+}
+
+impl Emission {
+    pub fn validate(&self) -> StdResult<()> {
+        match self {
+            Emission::LinearDecay { intervals } | Emission::Halving { intervals } => ensure!(
+                *intervals > 0,
+                StdError::generic_err("Emission intervals must be greater than 0")
+            ),
+            Emission::Table { weights } => ensure!(
+                !weights.is_empty(),
+                StdError::generic_err("Emission table must not be empty")
+            ),
+            Emission::Flat => {}
+        }
+        Ok(())
+    }
+
+    /// Weight of the `interval`-th interval
+    pub fn weight(&self, interval: u64) -> StdResult<Decimal256> {
+        match self {
+            Emission::Flat => Ok(Decimal256::one()),
+            Emission::LinearDecay { intervals } => {
+                to_decimal256(intervals.saturating_sub(interval))
+            }
+            Emission::Halving { intervals } => Ok(halving_factor(interval / intervals)),
+            Emission::Table { weights } => Ok(Decimal256::from(
+                weights[(interval as usize).min(weights.len() - 1)],
+            )),
+        }
+    }
+
+    /// Total weight of the first `x` intervals, `x` being fractional
+    pub fn cumulative(&self, x: Decimal256) -> StdResult<Decimal256> {
+        let whole = x.to_uint_floor();
+        let fraction = x.checked_sub(to_decimal256(whole)?)?;
+        // Intervals past `u64::MAX` all share the weight of the last one
+        let last_weight = self.weight(saturating_u64(whole))?;
+
+        match self {
+            Emission::Flat => Ok(x),
+            Emission::LinearDecay { intervals } => {
+                let intervals = Uint256::from(*intervals);
+                if whole >= intervals {
+                    let sum =
+                        intervals.checked_mul(intervals + Uint256::one())? / Uint256::from(2u8);
+                    return to_decimal256(sum);
+                }
+                // Arithmetic series `n + (n - 1) + ...` over the whole intervals
+                let sum = whole.checked_mul(intervals)?.checked_sub(
+                    whole.checked_mul(whole.saturating_sub(Uint256::one()))? / Uint256::from(2u8),
+                )?;
+                Ok(to_decimal256(sum)?.checked_add(fraction.checked_mul(last_weight)?)?)
+            }
+            Emission::Halving { intervals } => {
+                // Geometric series over the whole halving epochs
+                let epochs = whole / Uint256::from(*intervals);
+                let factor = halving_factor(saturating_u64(epochs));
+                let epoch_weight = to_decimal256(*intervals)?;
+                let two = Decimal256::percent(200);
+                let full = epoch_weight.checked_mul(two.checked_sub(factor.checked_mul(two)?)?)?;
+                let rest = x.checked_sub(epoch_weight.checked_mul(to_decimal256(epochs)?)?)?;
+                Ok(full.checked_add(rest.checked_mul(factor)?)?)
+            }
+            Emission::Table { weights } => {
+                let listed = whole.min(Uint256::from(weights.len() as u64));
+                let listed = saturating_u64(listed) as usize;
+                let mut sum = Decimal256::zero();
+                for weight in &weights[..listed] {
+                    sum = sum.checked_add(Decimal256::from(*weight))?;
+                }
+                let unlisted = whole.checked_sub(Uint256::from(listed as u64))?;
+                sum = sum.checked_add(
+                    Decimal256::from(weights[weights.len() - 1])
+                        .checked_mul(to_decimal256(unlisted)?)?,
+                )?;
+                Ok(sum.checked_add(fraction.checked_mul(last_weight)?)?)
+            }
+        }
+    }
+}
+
+/// Stretch of the reward program distributing `rewards` over
+/// `distribution_interval`s between `start_time` and `end_time`, following
+/// `emission` from `emission_start` on
+#[cw_serde]
+pub struct Period {
+    pub start_time: Timestamp,
+    pub end_time: Timestamp,
+    pub distribution_interval: Timestamp,
+    pub rewards: Vec<(AssetInfo, Uint128)>,
+    pub emission: Emission,
+    pub emission_start: Timestamp,
+}
+
+impl Period {
+    pub fn new(
+        start_time: Timestamp,
+        end_time: Timestamp,
+        distribution_interval: Timestamp,
+        rewards: Vec<(AssetInfo, Uint128)>,
+        emission: Emission,
+    ) -> Self {
+        Self {
+            start_time,
+            end_time,
+            distribution_interval,
+            rewards,
+            emission,
+            emission_start: start_time,
+        }
+    }
+
+    /// Emission weight between `from` and `to`, both within the period
+    pub fn weight_between(&self, from: Timestamp, to: Timestamp) -> StdResult<Decimal256> {
+        let interval = to_decimal256(self.distribution_interval.seconds())?;
+
+        // Flat emissions only depend on the elapsed time, avoid rounding positions
+        if self.emission == Emission::Flat {
+            return checked_div(to_decimal256(to.seconds() - from.seconds())?, interval);
+        }
+
+        let position = |time: Timestamp| -> StdResult<Decimal256> {
+            checked_div(
+                to_decimal256(time.seconds() - self.emission_start.seconds())?,
+                interval,
+            )
+        };
+
+        Ok(self
+            .emission
+            .cumulative(position(to)?)?
+            .checked_sub(self.emission.cumulative(position(from)?)?)?)
+    }
+
+    /// Emission weight of the whole period
+    pub fn weight(&self) -> StdResult<Decimal256> {
+        self.weight_between(self.start_time, self.end_time)
+    }
+
+    /// Rewards emitted during the interval containing `time`, limited to the part
+    /// of the interval within the period
+    pub fn emission_at(&self, time: Timestamp) -> StdResult<Vec<(AssetInfo, Uint128)>> {
+        let interval_seconds = self.distribution_interval.seconds();
+        let interval = (time.seconds() - self.emission_start.seconds()) / interval_seconds;
+        let interval_start = self
+            .emission_start
+            .plus_seconds(interval.saturating_mul(interval_seconds));
+        let interval_end = interval_start.plus_seconds(interval_seconds);
+
+        let from = interval_start.max(self.start_time);
+        let to = interval_end.min(self.end_time);
+        let weight = match from < to {
+            true => self.weight_between(from, to)?,
+            false => Decimal256::zero(),
+        };
+        let period_weight = self.weight()?;
+
+        self.rewards
+            .iter()
+            .map(|(asset, total)| Ok((asset.clone(), share_of(*total, weight, period_weight)?)))
+            .collect()
+    }
+
+    /// Truncate the period at `time`, keeping its emission rate, and return the
+    /// rewards left to distribute after that point
+    pub fn split_at(&mut self, time: Timestamp) -> StdResult<Vec<(AssetInfo, Uint128)>> {
+        let weight = self.weight_between(self.start_time, time)?;
+        let period_weight = self.weight()?;
+
+        let remaining = self
+            .rewards
+            .iter_mut()
+            .map(|(asset, total)| {
+                let distributed = share_of(*total, weight, period_weight)?;
+                let remaining = total.checked_sub(distributed)?;
+                *total = distributed;
+                Ok((asset.clone(), remaining))
+            })
+            .collect::<StdResult<Vec<(AssetInfo, Uint128)>>>()?;
+        self.end_time = time;
+
+        Ok(remaining)
+    }
+}
+
+/// `amount * numerator / denominator`, rounded down
+fn share_of(amount: Uint128, numerator: Decimal256, denominator: Decimal256) -> StdResult<Uint128> {
+    let share = checked_mul_ratio(to_decimal256(amount)?, numerator, denominator)?;
+    Ok(Uint128::try_from(share.to_uint_floor())?)
+}
+
+/// Convert to `u64`, saturating at `u64::MAX`
+fn saturating_u64(value: Uint256) -> u64 {
+    match Uint128::try_from(value) {
+        Ok(value) => u64::try_from(value.u128()).unwrap_or(u64::MAX),
+        Err(_) => u64::MAX,
+    }
+}
+
+/// `0.5^epochs`, rounding down to zero past the precision of `Decimal256`
+fn halving_factor(epochs: u64) -> Decimal256 {
+    match u32::try_from(epochs) {
+        Ok(epochs) if epochs < 64 => Decimal256::percent(50)
+            .checked_pow(epochs)
+            .unwrap_or(Decimal256::zero()),
+        _ => Decimal256::zero(),
+    }
+}
+
+/// Highest protocol fee that can be configured, in basis points
+pub use lockup_common::MAX_FEE_BPS;
+
+/// Start and end of a program
+#[cw_serde]
+pub struct ProgramTime {
+    pub start_time: Timestamp,
+    pub end_time: Timestamp,
+}
+
+/// Optional settings of a program, which defaults to flat emission paid out per
+/// interval, without vesting or protocol fee
+#[cw_serde]
+#[derive(Default)]
+pub struct InstantiateOptions {
+    pub emission: Option<Emission>,
+    pub streaming: Option<bool>,
+    pub vesting: Option<VestingConfig>,
+    pub fee: Option<FeeConfigInput>,
+}
+
+/// Unvalidated protocol fee
+#[cw_serde]
+pub struct FeeConfigInput {
+    pub fee_bps: u16,
+    pub collector: String,
+}
+
+impl FeeConfigInput {
+    pub fn check(&self, api: &dyn Api) -> StdResult<FeeConfig> {
+        ensure!(
+            self.fee_bps <= MAX_FEE_BPS,
+            StdError::generic_err(format!("Fee must not exceed {} basis points", MAX_FEE_BPS))
+        );
+
+        Ok(FeeConfig {
+            fee_bps: self.fee_bps,
+            collector: api.addr_validate(&self.collector)?,
+        })
+    }
+}
+
+/// Protocol fee taken on each claim and sent to `collector`
+#[cw_serde]
+pub struct FeeConfig {
+    pub fee_bps: u16,
+    pub collector: Addr,
+}
+
+impl FeeConfig {
+    /// Split `amount` into the part paid to the claimer and the fee
+    pub fn split(&self, amount: Uint128) -> (Uint128, Uint128) {
+        let fee = amount.multiply_ratio(self.fee_bps, 10_000u128);
+        (amount - fee, fee)
+    }
+}
+
+/// Vesting applied to claimed rewards, released linearly over `duration`
+/// once `cliff` has passed
+#[cw_serde]
+pub struct VestingConfig {
+    pub duration: Timestamp,
+    pub cliff: Timestamp,
+}
+
+/// Number of buckets the vesting duration is divided into, claims vesting from
+/// the same bucket sharing a tranche
+pub const VESTING_BUCKETS: u64 = 24;
+
+impl VestingConfig {
+    /// Add the tranche of a claim to `tranches`, keeping their number bounded:
+    /// empty tranches are skipped, fully vested tranches are folded into one, and
+    /// a tranche opened within a bucket of the last one is merged into it, vesting
+    /// from the last tranche's start time
+    pub fn add_tranche(
+        &self,
+        tranches: Vec<VestingTranche>,
+        tranche: VestingTranche,
+    ) -> Vec<VestingTranche> {
+        if tranche.amounts.iter().all(|(_, amount)| amount.is_zero()) {
+            return tranches;
+        }
+
+        let time = tranche.start_time;
+        let (matured, mut pending): (Vec<_>, Vec<_>) = tranches
+            .into_iter()
+            .partition(|tranche| tranche.start_time.plus_seconds(self.duration.seconds()) <= time);
+
+        let mut tranches: Vec<VestingTranche> = matured
+            .into_iter()
+            .reduce(|mut folded, tranche| {
+                folded.merge(tranche);
+                folded
+            })
+            .into_iter()
+            .collect();
+        tranches.append(&mut pending);
+
+        let bucket = (self.duration.seconds() / VESTING_BUCKETS).max(1);
+        match tranches.last_mut() {
+            Some(last) if time < last.start_time.plus_seconds(bucket) => last.merge(tranche),
+            _ => tranches.push(tranche),
+        }
+
+        tranches
+    }
+
+    pub fn validate(&self) -> StdResult<()> {
+        ensure!(
+            self.duration.seconds() > 0,
+            StdError::generic_err("Vesting duration must be greater than 0")
+        );
+        ensure!(
+            self.cliff <= self.duration,
+            StdError::generic_err("Vesting cliff must not exceed its duration")
+        );
+        Ok(())
+    }
+}
+
+/// Rewards of a single claim, vesting from `start_time`
+#[cw_serde]
+pub struct VestingTranche {
+    pub start_time: Timestamp,
+    pub amounts: Vec<(AssetInfo, Uint128)>,
+    pub released: Vec<(AssetInfo, Uint128)>,
+}
+
+impl VestingTranche {
+    pub fn new(start_time: Timestamp, amounts: Vec<(AssetInfo, Uint128)>) -> Self {
+        let released = amounts
+            .iter()
+            .map(|(asset, _)| (asset.clone(), Uint128::zero()))
+            .collect();
+
+        Self {
+            start_time,
+            amounts,
+            released,
+        }
+    }
+
+    /// Amounts vested by `time` but not released yet
+    pub fn releasable(&self, config: &VestingConfig, time: Timestamp) -> Vec<(AssetInfo, Uint128)> {
+        let elapsed = time.seconds().saturating_sub(self.start_time.seconds());
+        let elapsed = match elapsed < config.cliff.seconds() {
+            true => 0,
+            false => elapsed.min(config.duration.seconds()),
+        };
+
+        self.amounts
+            .iter()
+            .zip(self.released.iter())
+            .map(|((asset, amount), (_, released))| {
+                let vested = amount.multiply_ratio(elapsed, config.duration.seconds());
+                (asset.clone(), vested - released)
+            })
+            .collect()
+    }
+
+    /// Whether every amount of the tranche has been released
+    pub fn is_released(&self) -> bool {
+        self.amounts == self.released
+    }
+
+    /// Add the amounts and released amounts of `other` to the tranche
+    pub fn merge(&mut self, other: VestingTranche) {
+        let add = |ledger: &mut Vec<(AssetInfo, Uint128)>, entries: Vec<(AssetInfo, Uint128)>| {
+            for (asset, amount) in entries {
+                match ledger.iter_mut().find(|(entry, _)| entry == &asset) {
+                    Some((_, total)) => *total += amount,
+                    None => ledger.push((asset, amount)),
+                }
+            }
+        };
+        add(&mut self.amounts, other.amounts);
+        add(&mut self.released, other.released);
+    }
+}
+
+/// Unvalidated reward asset, as passed in messages
+#[cw_serde]
+pub enum AssetInfoInput {
+    Native(String),
+    Cw20(String),
+}
+
+impl AssetInfoInput {
+    pub fn check(&self, api: &dyn Api) -> StdResult<AssetInfo> {
+        Ok(match self {
+            AssetInfoInput::Native(denom) => AssetInfo::Native(denom.clone()),
+            AssetInfoInput::Cw20(address) => AssetInfo::Cw20(api.addr_validate(address)?),
+        })
+    }
+}
+
+/// Reward asset, either a native denomination or a CW20 token
+#[cw_serde]
+pub enum AssetInfo {
+    Native(String),
+    Cw20(Addr),
+}
+
+impl AssetInfo {
+    /// Build the message sending `amount` of this asset to `recipient`
+    pub fn transfer_msg(&self, recipient: &Addr, amount: Uint128) -> StdResult<CosmosMsg> {
+        Ok(match self {
+            AssetInfo::Native(denom) => BankMsg::Send {
+                to_address: recipient.to_string(),
+                amount: vec![coin(amount.u128(), denom)],
+            }
+            .into(),
+            AssetInfo::Cw20(address) => WasmMsg::Execute {
+                contract_addr: address.to_string(),
+                msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: recipient.to_string(),
+                    amount,
+                })?,
+                funds: vec![],
+            }
+            .into(),
+        })
+    }
+
+    /// Query the balance of this asset held by `address`
+    pub fn query_balance(&self, querier: &QuerierWrapper, address: &Addr) -> StdResult<Uint128> {
+        match self {
+            AssetInfo::Native(denom) => Ok(querier.query_balance(address, denom)?.amount),
+            AssetInfo::Cw20(token) => {
+                let res: BalanceResponse = querier.query_wasm_smart(
+                    token,
+                    &Cw20QueryMsg::Balance {
+                        address: address.to_string(),
+                    },
+                )?;
+                Ok(res.balance)
+            }
+        }
+    }
+}
+
+impl fmt::Display for AssetInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AssetInfo::Native(denom) => write!(f, "native:{}", denom),
+            AssetInfo::Cw20(address) => write!(f, "cw20:{}", address),
+        }
+    }
+}
@@ -1,19 +1,29 @@
 use cosmwasm_schema::cw_serde;
 use cosmwasm_std::{
-    ensure, ensure_eq, to_json_binary, Addr, BankMsg, Coin, Response, StdError, StdResult, SubMsg,
-    Timestamp, Uint128, WasmMsg,
+    ensure, ensure_eq, from_json, to_json_binary, Addr, BankMsg, Binary, Coin, CosmosMsg, Deps,
+    Env, Response, StdError, StdResult, SubMsg, Timestamp, Uint128, WasmMsg,
 };
 use cw2::ContractVersion;
-use cw_storage_plus::{IndexedMap, Item, MultiIndex};
+use cw_storage_plus::{IndexedMap, Item, Map, MultiIndex};
 
 use sylvia::types::{ExecCtx, InstantiateCtx, QueryCtx};
 use sylvia::{contract, entry_points};
 
 use cw721::{Cw721ExecuteMsg, Cw721QueryMsg, OwnerOfResponse as Cw721OwnerOfResponse};
 
-use crate::helpers::{burn, mint_to};
-use crate::msg::{ConfigResponse, CountResponse, LockupsReponse};
-use crate::storage::{Collection, CollectionInput, Lockup, LockupIndexes};
+use crate::helpers::{
+    burn, change_denom_admin, create_denom, mint_to, random_index, set_denom_metadata,
+};
+use crate::msg::{
+    BackingResponse, CollectionBacking, ConfigResponse, CountResponse, DenomBacking,
+    DepositOptions, DepositQuoteResponse, Health, LockupsReponse, NativeLockupConfigResponse,
+    NativeLockupExecuteMsg, NativeLockupQueryMsg, NoisCallback, NoisProxyExecuteMsg, ReceiveNftMsg,
+    RedeemQuoteResponse,
+};
+use crate::storage::{
+    lockup_key, Collection, CollectionInput, DenomInput, Lockup, LockupIndexes, PriorityWindow,
+    RandomRedemption, RANDOM_REDEMPTION_TIMEOUT,
+};
 use crate::{ACTOR_ID, VERSION};
 
 pub struct FracLockupContract {
@@ -21,6 +31,20 @@ pub struct FracLockupContract {
     pub(crate) denom: Item<'static, String>,
     pub(crate) collections: Item<'static, Vec<Collection>>,
     pub(crate) lockup: IndexedMap<'static, &'static str, Lockup, LockupIndexes<'static>>,
+    /// Randomness proxy used for random redemptions, if enabled
+    pub(crate) nois_proxy: Item<'static, Option<Addr>>,
+    /// Extra amount required to redeem a specific token rather than a random one
+    pub(crate) redeem_premium: Item<'static, Uint128>,
+    /// Random redemptions waiting for randomness (keyed by job ID)
+    pub(crate) random_redemptions: Map<'static, &'static str, RandomRedemption>,
+    /// Number of random redemptions requested so far, used for job IDs
+    pub(crate) redemption_count: Item<'static, u64>,
+    /// Address receiving deposit and redemption fees
+    pub(crate) treasury: Item<'static, Option<Addr>>,
+    /// Tokens paid in advance for swaps, by owner and collection
+    pub(crate) swap_credits: Map<'static, (&'static Addr, &'static Addr), Uint128>,
+    /// Window after deposits during which tokens are reserved for their depositor, if enabled
+    pub(crate) priority_window: Item<'static, Option<PriorityWindow>>,
 }
 
 #[entry_points]
@@ -46,6 +70,13 @@ impl FracLockupContract {
             denom: Item::new("denom"),
             collections: Item::new("collections"),
             lockup: IndexedMap::new("lockup", indexes),
+            nois_proxy: Item::new("nois_proxy"),
+            redeem_premium: Item::new("redeem_premium"),
+            random_redemptions: Map::new("random_redemptions"),
+            redemption_count: Item::new("redemption_count"),
+            treasury: Item::new("treasury"),
+            swap_credits: Map::new("swap_credits"),
+            priority_window: Item::new("priority_window"),
         }
     }
 
@@ -53,28 +84,73 @@ impl FracLockupContract {
     fn instantiate(
         &self,
         ctx: InstantiateCtx,
-        denom: String,
+        denom: DenomInput,
         collections: Vec<CollectionInput>,
+        nois_proxy: Option<String>,
+        redeem_premium: Option<Uint128>,
+        treasury: Option<String>,
     ) -> StdResult<Response> {
-        let collections: Vec<Collection> = collections
-            .into_iter()
-            .map(|collection| Collection {
-                address: ctx.deps.api.addr_validate(&collection.address).unwrap(),
-                tokens: Uint128::from(collection.tokens),
-            })
-            .collect();
+        let (collections, create_denom_msgs): (Vec<Collection>, Vec<Option<CosmosMsg>>) =
+            collections
+                .iter()
+                .map(|collection| self.new_collection(ctx.deps.as_ref(), &ctx.env, collection))
+                .collect::<StdResult<Vec<_>>>()?
+                .into_iter()
+                .unzip();
 
-        // Verify that the denom begins with `factory/`
+        // Fees are paid to the treasury, which must be set to charge any
+        let treasury = treasury
+            .map(|treasury| ctx.deps.api.addr_validate(&treasury))
+            .transpose()?;
         ensure!(
-            denom.starts_with("factory/"),
-            StdError::generic_err("Denom must be a token factory token")
+            treasury.is_some() || !collections.iter().any(|collection| collection.has_fees()),
+            StdError::generic_err("Treasury must be set to charge fees")
         );
+        // The redemption premium is paid to the treasury as well
+        let redeem_premium = redeem_premium.unwrap_or_default();
+        ensure!(
+            treasury.is_some() || redeem_premium.is_zero(),
+            StdError::generic_err("Treasury must be set to charge a redemption premium")
+        );
+        self.treasury.save(ctx.deps.storage, &treasury)?;
+
+        // Either create the denom, or verify that the given one begins with `factory/`
+        let (denom, denom_msgs) = match denom {
+            DenomInput::Existing(denom) => {
+                ensure!(
+                    denom.starts_with("factory/"),
+                    StdError::generic_err("Denom must be a token factory token")
+                );
+                (denom, vec![])
+            }
+            DenomInput::Create(metadata) => {
+                ensure!(
+                    !metadata.subdenom.is_empty() && !metadata.symbol.is_empty(),
+                    StdError::generic_err("Denom subdenom and symbol must not be empty")
+                );
+                let denom = format!("factory/{}/{}", ctx.env.contract.address, metadata.subdenom);
+                let msgs = vec![
+                    create_denom(ctx.env.clone(), metadata.subdenom.clone()),
+                    set_denom_metadata(ctx.env.clone(), denom.clone(), metadata),
+                ];
+                (denom, msgs)
+            }
+        };
 
         self.denom.save(ctx.deps.storage, &denom)?;
         self.collections.save(ctx.deps.storage, &collections)?;
         self.admin.save(ctx.deps.storage, &ctx.info.sender)?;
 
-        Ok(Response::new())
+        let nois_proxy = nois_proxy
+            .map(|nois_proxy| ctx.deps.api.addr_validate(&nois_proxy))
+            .transpose()?;
+        self.nois_proxy.save(ctx.deps.storage, &nois_proxy)?;
+        self.redeem_premium
+            .save(ctx.deps.storage, &redeem_premium)?;
+
+        Ok(Response::new()
+            .add_messages(denom_msgs)
+            .add_messages(create_denom_msgs.into_iter().flatten()))
     }
 
     #[msg(exec)]
@@ -98,6 +174,46 @@ impl FracLockupContract {
             .add_attribute("new_admin", admin.to_string()))
     }
 
+    /// Hand over the tokenfactory admin of a denom of the contract, e.g. to a new contract
+    #[msg(exec)]
+    fn transfer_denom_admin(
+        &self,
+        ctx: ExecCtx,
+        denom: String,
+        new_admin: String,
+    ) -> StdResult<Response> {
+        // Admin only
+        let admin = self.admin.load(ctx.deps.storage)?;
+        ensure_eq!(
+            admin,
+            ctx.info.sender,
+            StdError::generic_err("Unauthorized")
+        );
+
+        // Verify that the denom is the contract's or one of its collections'
+        let collections = self.collections.load(ctx.deps.storage)?;
+        ensure!(
+            self.denom.load(ctx.deps.storage)? == denom
+                || collections
+                    .iter()
+                    .any(|collection| collection.denom.as_ref() == Some(&denom)),
+            StdError::generic_err("Denom is not managed by the contract")
+        );
+
+        let new_admin = ctx.deps.api.addr_validate(&new_admin)?;
+
+        Ok(Response::new()
+            .add_message(change_denom_admin(
+                ctx.env.clone(),
+                denom.clone(),
+                new_admin.to_string(),
+            ))
+            .add_attribute("method", "transfer_denom_admin")
+            .add_attribute("contract_address", ctx.env.contract.address.to_string())
+            .add_attribute("denom", denom)
+            .add_attribute("new_admin", new_admin.to_string()))
+    }
+
     #[msg(exec)]
     fn append_collection(&self, ctx: ExecCtx, collection: CollectionInput) -> StdResult<Response> {
         // Admin only
@@ -108,23 +224,27 @@ impl FracLockupContract {
             StdError::generic_err("Unauthorized")
         );
 
-        // Verify collections addresses
-        let address = ctx.deps.api.addr_validate(&collection.address)?;
-        let tokens = Uint128::from(collection.tokens);
+        // Verify collections addresses, creating the collection's own denom if requested
+        let (new_collection, create_denom_msg) =
+            self.new_collection(ctx.deps.as_ref(), &ctx.env, &collection)?;
+        ensure!(
+            !new_collection.has_fees() || self.treasury.load(ctx.deps.storage)?.is_some(),
+            StdError::generic_err("Treasury must be set to charge fees")
+        );
 
         // Verify that there is not already a collection with this address
         let collections = self.collections.load(ctx.deps.storage)?;
         ensure!(
             !collections
                 .iter()
-                .any(|collection| collection.address == address),
+                .any(|collection| collection.address == new_collection.address),
             StdError::generic_err("Collection already exists")
         );
 
         // Save the new collection
         let new_collections: Vec<Collection> = collections
             .iter()
-            .chain(vec![Collection { address, tokens }].iter())
+            .chain(vec![new_collection].iter())
             .cloned()
             .collect();
 
@@ -132,6 +252,7 @@ impl FracLockupContract {
         self.collections.save(ctx.deps.storage, &new_collections)?;
 
         Ok(Response::new()
+            .add_messages(create_denom_msg)
             .add_attribute("method", "append_collection")
             .add_attribute("contract_address", ctx.env.contract.address.to_string())
             .add_attribute(
@@ -144,9 +265,182 @@ impl FracLockupContract {
             ))
     }
 
+    /// Reserve deposited tokens for their depositor for a while, or stop doing so with `None`
+    #[msg(exec)]
+    fn update_priority_window(
+        &self,
+        ctx: ExecCtx,
+        priority_window: Option<PriorityWindow>,
+    ) -> StdResult<Response> {
+        // Admin only
+        let admin = self.admin.load(ctx.deps.storage)?;
+        ensure_eq!(
+            admin,
+            ctx.info.sender,
+            StdError::generic_err("Unauthorized")
+        );
+
+        if let Some(priority_window) = &priority_window {
+            priority_window.validate()?;
+        }
+        self.priority_window
+            .save(ctx.deps.storage, &priority_window)?;
+
+        Ok(Response::new()
+            .add_attribute("method", "update_priority_window")
+            .add_attribute("contract_address", ctx.env.contract.address.to_string())
+            .add_attribute(
+                "hours",
+                priority_window
+                    .map(|priority_window| priority_window.hours.to_string())
+                    .unwrap_or_else(|| "none".to_string()),
+            ))
+    }
+
+    /// Stop taking deposits of a collection, while still allowing its tokens to be redeemed
+    #[msg(exec)]
+    fn retire_collection(&self, ctx: ExecCtx, address: String) -> StdResult<Response> {
+        // Admin only
+        let admin = self.admin.load(ctx.deps.storage)?;
+        ensure_eq!(
+            admin,
+            ctx.info.sender,
+            StdError::generic_err("Unauthorized")
+        );
+
+        let collection_address = ctx.deps.api.addr_validate(&address)?;
+        let mut collections = self.collections.load(ctx.deps.storage)?;
+        let collection = collections
+            .iter_mut()
+            .find(|collection| collection.address == collection_address)
+            .ok_or_else(|| StdError::generic_err("Collection is not supported"))?;
+        ensure!(
+            !collection.retired,
+            StdError::generic_err("Collection is already retired")
+        );
+
+        collection.retired = true;
+        self.collections.save(ctx.deps.storage, &collections)?;
+
+        Ok(Response::new()
+            .add_attribute("method", "retire_collection")
+            .add_attribute("contract_address", ctx.env.contract.address.to_string())
+            .add_attribute("collection_address", collection_address))
+    }
+
+    /// Delist a collection, once none of its tokens are held by the contract
+    #[msg(exec)]
+    fn remove_collection(&self, ctx: ExecCtx, address: String) -> StdResult<Response> {
+        // Admin only
+        let admin = self.admin.load(ctx.deps.storage)?;
+        ensure_eq!(
+            admin,
+            ctx.info.sender,
+            StdError::generic_err("Unauthorized")
+        );
+
+        let collection_address = ctx.deps.api.addr_validate(&address)?;
+        let collections = self.collections.load(ctx.deps.storage)?;
+        ensure!(
+            collections
+                .iter()
+                .any(|collection| collection.address == collection_address),
+            StdError::generic_err("Collection is not supported")
+        );
+
+        // Verify that nothing references the collection anymore
+        ensure!(
+            self.collection_lockups(ctx.deps.as_ref(), &collection_address)?
+                .is_empty(),
+            StdError::generic_err("Collection still has lockups")
+        );
+        let referenced = self
+            .random_redemptions
+            .range(ctx.deps.storage, None, None, cosmwasm_std::Order::Ascending)
+            .any(|res| {
+                res.map(|(_, redemption)| redemption.collection_address == collection_address)
+                    .unwrap_or(true)
+            })
+            || self
+                .swap_credits
+                .keys(ctx.deps.storage, None, None, cosmwasm_std::Order::Ascending)
+                .any(|res| {
+                    res.map(|(_, credit_collection)| credit_collection == collection_address)
+                        .unwrap_or(true)
+                });
+        ensure!(
+            !referenced,
+            StdError::generic_err("Collection still has pending redemptions or swap credits")
+        );
+
+        let collections: Vec<Collection> = collections
+            .into_iter()
+            .filter(|collection| collection.address != collection_address)
+            .collect();
+        self.collections.save(ctx.deps.storage, &collections)?;
+
+        Ok(Response::new()
+            .add_attribute("method", "remove_collection")
+            .add_attribute("contract_address", ctx.env.contract.address.to_string())
+            .add_attribute("collection_address", collection_address))
+    }
+
+    /// Replace the deposit and redemption fees of a collection, and its swap fee
+    /// when given
+    #[msg(exec)]
+    fn update_collection_fees(
+        &self,
+        ctx: ExecCtx,
+        collection_address: String,
+        deposit_fee_bps: u16,
+        redeem_fee_bps: u16,
+        swap_fee_bps: Option<u16>,
+    ) -> StdResult<Response> {
+        // Admin only
+        let admin = self.admin.load(ctx.deps.storage)?;
+        ensure_eq!(
+            admin,
+            ctx.info.sender,
+            StdError::generic_err("Unauthorized")
+        );
+
+        let collection_address = ctx.deps.api.addr_validate(&collection_address)?;
+        let mut collections = self.collections.load(ctx.deps.storage)?;
+        let collection = collections
+            .iter_mut()
+            .find(|collection| collection.address == collection_address)
+            .ok_or_else(|| StdError::generic_err("Collection is not supported"))?;
+
+        collection.deposit_fee_bps = deposit_fee_bps;
+        collection.redeem_fee_bps = redeem_fee_bps;
+        let swap_fee_bps = swap_fee_bps.unwrap_or(collection.swap_fee_bps);
+        collection.swap_fee_bps = swap_fee_bps;
+        collection.validate()?;
+        ensure!(
+            !collection.has_fees() || self.treasury.load(ctx.deps.storage)?.is_some(),
+            StdError::generic_err("Treasury must be set to charge fees")
+        );
+
+        self.collections.save(ctx.deps.storage, &collections)?;
+
+        Ok(Response::new()
+            .add_attribute("method", "update_collection_fees")
+            .add_attribute("contract_address", ctx.env.contract.address.to_string())
+            .add_attribute("collection_address", collection_address)
+            .add_attribute("deposit_fee_bps", deposit_fee_bps.to_string())
+            .add_attribute("redeem_fee_bps", redeem_fee_bps.to_string())
+            .add_attribute("swap_fee_bps", swap_fee_bps.to_string()))
+    }
+
     #[msg(exec)]
-    fn receive_nft(&self, ctx: ExecCtx, sender: String, token_id: String) -> StdResult<Response> {
-        let collection_address = ctx.info.sender;
+    fn receive_nft(
+        &self,
+        ctx: ExecCtx,
+        sender: String,
+        token_id: String,
+        msg: Binary,
+    ) -> StdResult<Response> {
+        let collection_address = ctx.info.sender.clone();
 
         // Verify that the collection is supported
         let collections = self.collections.load(ctx.deps.storage)?;
@@ -161,6 +455,10 @@ impl FracLockupContract {
             .iter()
             .find(|collection| collection.address == collection_address)
             .unwrap();
+        ensure!(
+            !collection.retired,
+            StdError::generic_err("Collection is retired")
+        );
 
         // Query the owner of the NFT
         let cw721_owner_response: Cw721OwnerOfResponse = ctx
@@ -182,9 +480,21 @@ impl FracLockupContract {
             StdError::generic_err("Token was not transferred to contract")
         );
 
-        // Save a new lockup entry
+        // Swap the token for another one of the pool if requested, or deposit it
         let depositor = ctx.deps.api.addr_validate(&sender)?;
+        let action: Option<ReceiveNftMsg> = match msg.as_slice() {
+            b"" | b"{}" => None,
+            _ => Some(from_json(&msg)?),
+        };
+        let options = match action {
+            Some(ReceiveNftMsg::SwapNft { target_token_id }) => {
+                return self.swap_nft(ctx, collection, depositor, token_id, target_token_id);
+            }
+            Some(ReceiveNftMsg::Deposit { options }) => Some(options),
+            None => None,
+        };
 
+        // Save a new lockup entry
         let lockup = Lockup::new(
             depositor.clone(),
             collection_address.clone(),
@@ -192,21 +502,70 @@ impl FracLockupContract {
             ctx.env.block.time,
         );
 
-        self.lockup
-            .save(ctx.deps.storage, "lockup__depositor", &lockup)?;
+        self.lockup.save(
+            ctx.deps.storage,
+            &lockup_key(&collection_address, &token_id),
+            &lockup,
+        )?;
 
-        // Mint tokens to depositor
-        let mint_msg = mint_to(
-            ctx.env.clone(),
-            depositor.to_string(),
-            Coin {
-                denom: self.denom.load(ctx.deps.storage)?,
-                amount: collection.tokens.clone(),
-            },
-        );
+        // Mint tokens to depositor, or stake them on their behalf,
+        // minus the deposit fee minted to the treasury
+        let denom = self.collection_denom(ctx.deps.as_ref(), collection)?;
+        let fee = collection.deposit_fee();
+        let minted = Coin {
+            denom: denom.clone(),
+            amount: collection.tokens - fee,
+        };
 
-        Ok(Response::new()
-            .add_submessage(SubMsg::new(mint_msg))
+        let mut res = match options {
+            None => Response::new().add_submessage(SubMsg::new(mint_to(
+                ctx.env.clone(),
+                depositor.to_string(),
+                minted,
+            ))),
+            Some(DepositOptions::StakeInto { lockup }) => {
+                let lockup_address = ctx.deps.api.addr_validate(&lockup)?;
+
+                // Verify that the lockup holds the collection's tokens
+                let config: NativeLockupConfigResponse = ctx
+                    .deps
+                    .querier
+                    .query_wasm_smart(&lockup_address, &NativeLockupQueryMsg::Config {})?;
+                ensure_eq!(
+                    config.token,
+                    denom,
+                    StdError::generic_err("Lockup does not hold the collection's tokens")
+                );
+
+                let stake_msg = WasmMsg::Execute {
+                    contract_addr: lockup_address.to_string(),
+                    msg: to_json_binary(&NativeLockupExecuteMsg::DepositFor {
+                        recipient: depositor.to_string(),
+                    })?,
+                    funds: vec![minted.clone()],
+                };
+
+                Response::new()
+                    .add_submessage(SubMsg::new(mint_to(
+                        ctx.env.clone(),
+                        ctx.env.contract.address.to_string(),
+                        minted,
+                    )))
+                    .add_submessage(SubMsg::new(stake_msg))
+                    .add_attribute("staked_into", lockup_address.to_string())
+            }
+        };
+        if let (false, Some(treasury)) = (fee.is_zero(), self.treasury.load(ctx.deps.storage)?) {
+            res = res
+                .add_submessage(SubMsg::new(mint_to(
+                    ctx.env.clone(),
+                    treasury.to_string(),
+                    Coin { denom, amount: fee },
+                )))
+                .add_attribute("fee", fee.to_string());
+        }
+
+        Ok(res
             .add_attribute("method", "deposit")
             .add_attribute("contract_address", ctx.env.contract.address.to_string())
             .add_attribute("collection_address", collection_address.to_string())
@@ -238,14 +597,6 @@ impl FracLockupContract {
             StdError::generic_err("Only one token type can be sent")
         );
 
-        // Verify that the funds sent are in the correct token
-        let denom = self.denom.load(ctx.deps.storage)?;
-        ensure_eq!(
-            ctx.info.funds[0].denom.as_str(),
-            denom.as_str(),
-            StdError::generic_err("Unsupported token sent")
-        );
-
         let sender = ctx.info.sender;
         let collection_address = ctx.deps.api.addr_validate(&collection_address)?;
 
@@ -268,12 +619,29 @@ impl FracLockupContract {
         };
 
         // Verify that the appropriate amount of funds was sent
+        // Picking a specific token costs the redeem premium on top of its tokens
         let collections = self.collections.load(ctx.deps.storage)?;
         let collection = collections
             .iter()
             .find(|collection| collection.address == collection_address)
             .unwrap();
-        let amount = collection.tokens.clone();
+
+        // Verify that the funds sent are in the collection's token
+        let denom = self.collection_denom(ctx.deps.as_ref(), collection)?;
+        ensure_eq!(
+            ctx.info.funds[0].denom.as_str(),
+            denom.as_str(),
+            StdError::generic_err("Unsupported token sent")
+        );
+
+        let (fee, premium) = self.redemption_charges(
+            ctx.deps.as_ref(),
+            &ctx.env,
+            collection,
+            &lockup.1,
+            Some(&sender),
+        )?;
+        let amount = collection.tokens + fee + premium;
         ensure_eq!(
             ctx.info.funds[0].amount,
             amount,
@@ -283,8 +651,21 @@ impl FracLockupContract {
         // Delete the lockup entry
         self.lockup.remove(ctx.deps.storage, &lockup.0)?;
 
-        // Burn the tokens
-        let burn_msg = burn(ctx.env.clone(), ctx.info.funds[0].clone());
+        // Burn the tokens minted against the NFT, paying the fee and premium to the treasury
+        let burn_msg = burn(
+            ctx.env.clone(),
+            Coin {
+                denom: denom.clone(),
+                amount: collection.tokens,
+            },
+        );
+        let fee_msgs = self.fee_msgs(
+            ctx.deps.as_ref(),
+            Coin {
+                denom,
+                amount: fee + premium,
+            },
+        )?;
 
         // Send the NFT to the message sender
         let msg = Cw721ExecuteMsg::TransferNft {
@@ -300,6 +681,7 @@ impl FracLockupContract {
         Ok(Response::new()
             .add_submessage(SubMsg::new(cw721_msg))
             .add_submessage(SubMsg::new(burn_msg))
+            .add_submessages(fee_msgs)
             .add_attribute("method", "withdraw")
             .add_attribute("contract_address", ctx.env.contract.address.to_string())
             .add_attribute("collection_address", collection_address)
@@ -307,6 +689,454 @@ impl FracLockupContract {
             .add_attribute("sent_to", sender.to_string()))
     }
 
+    /// Pay tokens in advance for the fee and premium of swaps within a collection
+    #[msg(exec)]
+    fn fund_swap(&self, ctx: ExecCtx, collection_address: String) -> StdResult<Response> {
+        let collection = self.collection(ctx.deps.as_ref(), &collection_address)?;
+        let denom = self.collection_denom(ctx.deps.as_ref(), &collection)?;
+
+        // Verify that only the collection's tokens were sent
+        ensure!(
+            !ctx.info.funds.is_empty(),
+            StdError::generic_err("No funds sent")
+        );
+        ensure!(
+            ctx.info.funds.len() == 1 && ctx.info.funds[0].denom == denom,
+            StdError::generic_err("Unsupported token sent")
+        );
+
+        let credit = self.swap_credits.update(
+            ctx.deps.storage,
+            (&ctx.info.sender, &collection.address),
+            |credit| -> StdResult<_> { Ok(credit.unwrap_or_default() + ctx.info.funds[0].amount) },
+        )?;
+
+        Ok(Response::new()
+            .add_attribute("method", "fund_swap")
+            .add_attribute("contract_address", ctx.env.contract.address.to_string())
+            .add_attribute("collection_address", collection.address.to_string())
+            .add_attribute("credit", credit.to_string()))
+    }
+
+    /// Refund the tokens paid in advance for swaps within a collection
+    #[msg(exec)]
+    fn reclaim_swap_credit(&self, ctx: ExecCtx, collection_address: String) -> StdResult<Response> {
+        let collection = self.collection(ctx.deps.as_ref(), &collection_address)?;
+        let key = (&ctx.info.sender, &collection.address);
+        let credit = self
+            .swap_credits
+            .may_load(ctx.deps.storage, key)?
+            .unwrap_or_default();
+        ensure!(
+            !credit.is_zero(),
+            StdError::generic_err("No swap credit to reclaim")
+        );
+        self.swap_credits.remove(ctx.deps.storage, key);
+
+        Ok(Response::new()
+            .add_message(BankMsg::Send {
+                to_address: ctx.info.sender.to_string(),
+                amount: vec![Coin {
+                    denom: self.collection_denom(ctx.deps.as_ref(), &collection)?,
+                    amount: credit,
+                }],
+            })
+            .add_attribute("method", "reclaim_swap_credit")
+            .add_attribute("contract_address", ctx.env.contract.address.to_string())
+            .add_attribute("collection_address", collection.address.to_string())
+            .add_attribute("amount", credit.to_string()))
+    }
+
+    /// Redeem a random token of `collection`, picked once the proxy delivers randomness
+    /// Funds other than the fractional tokens are forwarded to the proxy as its fee
+    #[msg(exec)]
+    fn redeem_random(&self, ctx: ExecCtx, collection: String) -> StdResult<Response> {
+        let nois_proxy = self
+            .nois_proxy
+            .may_load(ctx.deps.storage)?
+            .flatten()
+            .ok_or_else(|| StdError::generic_err("Random redemption is not enabled"))?;
+
+        // Verify that the collection is supported
+        let collection_address = ctx.deps.api.addr_validate(&collection)?;
+        let collections = self.collections.load(ctx.deps.storage)?;
+        let collection = collections
+            .iter()
+            .find(|collection| collection.address == collection_address)
+            .ok_or_else(|| StdError::generic_err("Collection is not supported"))?;
+
+        // Verify that the appropriate amount of funds was sent
+        let denom = self.collection_denom(ctx.deps.as_ref(), collection)?;
+        let (tokens, proxy_fee): (Vec<Coin>, Vec<Coin>) = ctx
+            .info
+            .funds
+            .iter()
+            .cloned()
+            .partition(|coin| coin.denom == denom);
+        ensure!(!tokens.is_empty(), StdError::generic_err("No funds sent"));
+        let fee = collection.redeem_fee();
+        ensure_eq!(
+            tokens[0].amount,
+            collection.tokens + fee,
+            StdError::generic_err("Incorrect amount of funds sent")
+        );
+
+        // Verify that a token is left for every pending redemption
+        let pending = self
+            .random_redemptions
+            .range(ctx.deps.storage, None, None, cosmwasm_std::Order::Ascending)
+            .filter(|res| {
+                res.as_ref()
+                    .map(|(_, redemption)| redemption.collection_address == collection_address)
+                    .unwrap_or(true)
+            })
+            .count();
+        let available =
+            self.redeemable_lockups(ctx.deps.as_ref(), &ctx.env, &collection_address)?;
+        ensure!(
+            available.len() > pending,
+            StdError::generic_err("No tokens available for redemption")
+        );
+
+        // Hold the tokens until the randomness is delivered
+        let count = self
+            .redemption_count
+            .may_load(ctx.deps.storage)?
+            .unwrap_or_default()
+            + 1;
+        self.redemption_count.save(ctx.deps.storage, &count)?;
+
+        let job_id = format!("redeem-{}", count);
+        self.random_redemptions.save(
+            ctx.deps.storage,
+            &job_id,
+            &RandomRedemption {
+                redeemer: ctx.info.sender.clone(),
+                collection_address: collection_address.clone(),
+                denom,
+                amount: collection.tokens + fee,
+                fee,
+                requested_at: ctx.env.block.time,
+            },
+        )?;
+
+        let proxy_msg = WasmMsg::Execute {
+            contract_addr: nois_proxy.to_string(),
+            msg: to_json_binary(&NoisProxyExecuteMsg::GetNextRandomness {
+                job_id: job_id.clone(),
+            })?,
+            funds: proxy_fee,
+        };
+
+        Ok(Response::new()
+            .add_submessage(SubMsg::new(proxy_msg))
+            .add_attribute("method", "redeem_random")
+            .add_attribute("contract_address", ctx.env.contract.address.to_string())
+            .add_attribute("collection_address", collection_address)
+            .add_attribute("job_id", job_id)
+            .add_attribute("sender", ctx.info.sender.to_string()))
+    }
+
+    /// Randomness callback of the proxy, fulfilling a random redemption
+    #[msg(exec)]
+    fn nois_receive(&self, ctx: ExecCtx, callback: NoisCallback) -> StdResult<Response> {
+        // Proxy only
+        let nois_proxy = self.nois_proxy.may_load(ctx.deps.storage)?.flatten();
+        ensure!(
+            nois_proxy == Some(ctx.info.sender.clone()),
+            StdError::generic_err("Unauthorized")
+        );
+
+        let redemption = self
+            .random_redemptions
+            .may_load(ctx.deps.storage, &callback.job_id)?
+            .ok_or_else(|| StdError::generic_err("Redemption not found"))?;
+        self.random_redemptions
+            .remove(ctx.deps.storage, &callback.job_id);
+
+        let escrow = Coin {
+            denom: redemption.denom.clone(),
+            amount: redemption.amount,
+        };
+
+        // Refund the redeemer if the pool was emptied in the meantime
+        let lockups =
+            self.redeemable_lockups(ctx.deps.as_ref(), &ctx.env, &redemption.collection_address)?;
+        if lockups.is_empty() {
+            let refund_msg = BankMsg::Send {
+                to_address: redemption.redeemer.to_string(),
+                amount: vec![escrow],
+            };
+
+            return Ok(Response::new()
+                .add_submessage(SubMsg::new(refund_msg))
+                .add_attribute("method", "nois_receive")
+                .add_attribute("contract_address", ctx.env.contract.address.to_string())
+                .add_attribute("job_id", callback.job_id)
+                .add_attribute("refunded", redemption.redeemer.to_string()));
+        }
+
+        let (key, lockup) = &lockups[random_index(&callback.randomness, lockups.len())?];
+
+        // Delete the lockup entry
+        self.lockup.remove(ctx.deps.storage, key)?;
+
+        // Burn the tokens, except for the redemption fee
+        let fee_msgs = self.fee_msgs(
+            ctx.deps.as_ref(),
+            Coin {
+                denom: escrow.denom.clone(),
+                amount: redemption.fee,
+            },
+        )?;
+        let burn_msg = burn(
+            ctx.env.clone(),
+            Coin {
+                denom: escrow.denom,
+                amount: redemption.amount - redemption.fee,
+            },
+        );
+
+        // Send the NFT to the redeemer
+        let msg = Cw721ExecuteMsg::TransferNft {
+            recipient: redemption.redeemer.to_string(),
+            token_id: lockup.token_id.to_string(),
+        };
+        let cw721_msg = WasmMsg::Execute {
+            contract_addr: lockup.collection_address.to_string(),
+            msg: to_json_binary(&msg)?,
+            funds: vec![],
+        };
+
+        Ok(Response::new()
+            .add_submessage(SubMsg::new(cw721_msg))
+            .add_submessage(SubMsg::new(burn_msg))
+            .add_submessages(fee_msgs)
+            .add_attribute("method", "nois_receive")
+            .add_attribute("contract_address", ctx.env.contract.address.to_string())
+            .add_attribute("job_id", callback.job_id)
+            .add_attribute("collection_address", lockup.collection_address.to_string())
+            .add_attribute("token_id", lockup.token_id.to_string())
+            .add_attribute("sent_to", redemption.redeemer.to_string()))
+    }
+
+    /// Cancel a random redemption the proxy has not fulfilled in time, refunding
+    /// the tokens held for it
+    #[msg(exec)]
+    fn cancel_random_redemption(&self, ctx: ExecCtx, job_id: String) -> StdResult<Response> {
+        let redemption = self
+            .random_redemptions
+            .may_load(ctx.deps.storage, &job_id)?
+            .ok_or_else(|| StdError::generic_err("Redemption not found"))?;
+
+        // Redeemer only
+        ensure_eq!(
+            redemption.redeemer,
+            ctx.info.sender,
+            StdError::generic_err("Unauthorized")
+        );
+        ensure!(
+            ctx.env.block.time
+                >= redemption
+                    .requested_at
+                    .plus_seconds(RANDOM_REDEMPTION_TIMEOUT),
+            StdError::generic_err("Redemption has not timed out")
+        );
+
+        self.random_redemptions.remove(ctx.deps.storage, &job_id);
+
+        let refund_msg = BankMsg::Send {
+            to_address: redemption.redeemer.to_string(),
+            amount: vec![Coin {
+                denom: redemption.denom,
+                amount: redemption.amount,
+            }],
+        };
+
+        Ok(Response::new()
+            .add_submessage(SubMsg::new(refund_msg))
+            .add_attribute("method", "cancel_random_redemption")
+            .add_attribute("contract_address", ctx.env.contract.address.to_string())
+            .add_attribute("job_id", job_id)
+            .add_attribute("refunded", redemption.redeemer.to_string()))
+    }
+
+    #[msg(query)]
+    fn deposit_quote(
+        &self,
+        ctx: QueryCtx,
+        collection_address: String,
+    ) -> StdResult<DepositQuoteResponse> {
+        let collection = self.collection(ctx.deps, &collection_address)?;
+        let fee = collection.deposit_fee();
+
+        Ok(DepositQuoteResponse {
+            denom: self.collection_denom(ctx.deps, &collection)?,
+            minted: collection.tokens - fee,
+            fee,
+        })
+    }
+
+    /// Amount to send to redeem a token of a collection, either a specific or a random one.
+    /// Quoting a specific `token_id` for a `redeemer` applies its priority window
+    #[msg(query)]
+    fn redeem_quote(
+        &self,
+        ctx: QueryCtx,
+        collection_address: String,
+        random: bool,
+        token_id: Option<String>,
+        redeemer: Option<String>,
+    ) -> StdResult<RedeemQuoteResponse> {
+        let collection = self.collection(ctx.deps, &collection_address)?;
+        let (fee, premium) = match (random, token_id) {
+            (true, _) => (collection.redeem_fee(), Uint128::zero()),
+            (false, Some(token_id)) => {
+                let lockup = self
+                    .lockup
+                    .may_load(
+                        ctx.deps.storage,
+                        &lockup_key(&collection.address, &token_id),
+                    )?
+                    .ok_or_else(|| StdError::generic_err("Lockup entry not found"))?;
+                let redeemer = redeemer
+                    .map(|redeemer| ctx.deps.api.addr_validate(&redeemer))
+                    .transpose()?;
+                self.redemption_charges(
+                    ctx.deps,
+                    &ctx.env,
+                    &collection,
+                    &lockup,
+                    redeemer.as_ref(),
+                )?
+            }
+            (false, None) => (
+                collection.redeem_fee(),
+                self.redeem_premium.load(ctx.deps.storage)?,
+            ),
+        };
+
+        Ok(RedeemQuoteResponse {
+            denom: self.collection_denom(ctx.deps, &collection)?,
+            price: collection.tokens + fee + premium,
+            fee,
+            premium,
+        })
+    }
+
+    /// Amount to pay in advance with `FundSwap` for a swap within a collection
+    #[msg(query)]
+    fn swap_quote(
+        &self,
+        ctx: QueryCtx,
+        collection_address: String,
+    ) -> StdResult<RedeemQuoteResponse> {
+        let collection = self.collection(ctx.deps, &collection_address)?;
+        let fee = collection.swap_fee();
+        let premium = self.redeem_premium.load(ctx.deps.storage)?;
+
+        Ok(RedeemQuoteResponse {
+            denom: self.collection_denom(ctx.deps, &collection)?,
+            price: fee + premium,
+            fee,
+            premium,
+        })
+    }
+
+    #[msg(query)]
+    fn swap_credit(
+        &self,
+        ctx: QueryCtx,
+        address: String,
+        collection_address: String,
+    ) -> StdResult<Coin> {
+        let address = ctx.deps.api.addr_validate(&address)?;
+        let collection = self.collection(ctx.deps, &collection_address)?;
+
+        Ok(Coin {
+            denom: self.collection_denom(ctx.deps, &collection)?,
+            amount: self
+                .swap_credits
+                .may_load(ctx.deps.storage, (&address, &collection.address))?
+                .unwrap_or_default(),
+        })
+    }
+
+    /// Tokens minted against the NFTs held by the contract, compared with the supply of each denom
+    #[msg(query)]
+    fn backing(&self, ctx: QueryCtx) -> StdResult<BackingResponse> {
+        // The contract's denom is always accounted for, even if no collection uses it
+        let mut denoms = vec![DenomBacking {
+            denom: self.denom.load(ctx.deps.storage)?,
+            expected_supply: Uint128::zero(),
+            supply: Uint128::zero(),
+            health: Health::Backed,
+        }];
+
+        let collections = self
+            .collections
+            .load(ctx.deps.storage)?
+            .iter()
+            .map(|collection| {
+                let count = self
+                    .lockup
+                    .idx
+                    .collection
+                    .prefix(collection.address.clone())
+                    .keys(ctx.deps.storage, None, None, cosmwasm_std::Order::Ascending)
+                    .count() as u64;
+                let backing = collection.tokens * Uint128::from(count);
+
+                let denom = self.collection_denom(ctx.deps, collection)?;
+                match denoms.iter_mut().find(|entry| entry.denom == denom) {
+                    Some(entry) => entry.expected_supply += backing,
+                    None => denoms.push(DenomBacking {
+                        denom: denom.clone(),
+                        expected_supply: backing,
+                        supply: Uint128::zero(),
+                        health: Health::Backed,
+                    }),
+                }
+
+                Ok(CollectionBacking {
+                    collection_address: collection.address.clone(),
+                    denom,
+                    count,
+                    backing,
+                })
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+
+        for entry in denoms.iter_mut() {
+            entry.supply = ctx.deps.querier.query_supply(&entry.denom)?.amount;
+            entry.health = Health::new(entry.supply, entry.expected_supply);
+        }
+
+        let health = match (
+            denoms
+                .iter()
+                .any(|entry| entry.health == Health::Undercollateralized),
+            denoms
+                .iter()
+                .any(|entry| entry.health == Health::Overcollateralized),
+        ) {
+            (true, _) => Health::Undercollateralized,
+            (false, true) => Health::Overcollateralized,
+            (false, false) => Health::Backed,
+        };
+
+        Ok(BackingResponse {
+            collections,
+            denoms,
+            health,
+        })
+    }
+
+    #[msg(query)]
+    fn random_redemption(&self, ctx: QueryCtx, job_id: String) -> StdResult<RandomRedemption> {
+        self.random_redemptions.load(ctx.deps.storage, &job_id)
+    }
+
     #[msg(query)]
     fn count(&self, ctx: QueryCtx) -> StdResult<CountResponse> {
         let count = self
@@ -390,6 +1220,226 @@ impl FracLockupContract {
             admin,
             denom,
             collections,
+            nois_proxy: self.nois_proxy.may_load(ctx.deps.storage)?.flatten(),
+            redeem_premium: self
+                .redeem_premium
+                .may_load(ctx.deps.storage)?
+                .unwrap_or_default(),
+            treasury: self.treasury.may_load(ctx.deps.storage)?.flatten(),
+            priority_window: self.priority_window.may_load(ctx.deps.storage)?.flatten(),
         })
     }
 }
+
+impl FracLockupContract {
+    /// Supported collection with the given address
+    fn collection(&self, deps: Deps, collection_address: &str) -> StdResult<Collection> {
+        let collection_address = deps.api.addr_validate(collection_address)?;
+        self.collections
+            .load(deps.storage)?
+            .into_iter()
+            .find(|collection| collection.address == collection_address)
+            .ok_or_else(|| StdError::generic_err("Collection is not supported"))
+    }
+
+    /// Validate a new collection, along with the message creating its own denom if requested
+    fn new_collection(
+        &self,
+        deps: Deps,
+        env: &Env,
+        collection: &CollectionInput,
+    ) -> StdResult<(Collection, Option<CosmosMsg>)> {
+        let mut new_collection = collection.check(deps.api)?;
+
+        let create_denom_msg = collection.subdenom.clone().map(|subdenom| {
+            new_collection.denom = Some(format!("factory/{}/{}", env.contract.address, subdenom));
+            create_denom(env.clone(), subdenom)
+        });
+
+        Ok((new_collection, create_denom_msg))
+    }
+
+    /// Denom minted for the tokens of a collection
+    fn collection_denom(&self, deps: Deps, collection: &Collection) -> StdResult<String> {
+        match &collection.denom {
+            Some(denom) => Ok(denom.clone()),
+            None => self.denom.load(deps.storage),
+        }
+    }
+
+    /// Send a fee to the treasury
+    fn fee_msgs(&self, deps: Deps, fee: Coin) -> StdResult<Vec<SubMsg>> {
+        let treasury = self.treasury.may_load(deps.storage)?.flatten();
+        match (fee.amount.is_zero(), treasury) {
+            (false, Some(treasury)) => Ok(vec![SubMsg::new(BankMsg::Send {
+                to_address: treasury.to_string(),
+                amount: vec![fee],
+            })]),
+            _ => Ok(vec![]),
+        }
+    }
+
+    /// Swap a token just sent to the contract for a token of the pool from the same collection
+    /// The swap fee and redeem premium are paid out of the owner's swap credit
+    fn swap_nft(
+        &self,
+        ctx: ExecCtx,
+        collection: &Collection,
+        owner: Addr,
+        token_id: String,
+        target_token_id: String,
+    ) -> StdResult<Response> {
+        let target_key = lockup_key(&collection.address, &target_token_id);
+        let target = self
+            .lockup
+            .may_load(ctx.deps.storage, &target_key)?
+            .ok_or_else(|| StdError::generic_err("Lockup entry not found"))?;
+        if self
+            .priority_window(ctx.deps.as_ref(), &ctx.env, &target)?
+            .is_some()
+        {
+            ensure_eq!(
+                target.depositor,
+                owner,
+                StdError::generic_err("Token is reserved for its depositor")
+            );
+        }
+
+        // Pay for the swap out of the owner's credit
+        let fee = collection.swap_fee();
+        let premium = self.redeem_premium.load(ctx.deps.storage)?;
+        let credit_key = (&owner, &collection.address);
+        let credit = self
+            .swap_credits
+            .may_load(ctx.deps.storage, credit_key)?
+            .unwrap_or_default();
+        ensure!(
+            credit >= fee + premium,
+            StdError::generic_err("Insufficient swap credit")
+        );
+        match credit - fee - premium {
+            left if left.is_zero() => self.swap_credits.remove(ctx.deps.storage, credit_key),
+            left => self
+                .swap_credits
+                .save(ctx.deps.storage, credit_key, &left)?,
+        }
+
+        // Replace the target's lockup with one of the token sent
+        self.lockup.remove(ctx.deps.storage, &target_key)?;
+        self.lockup.save(
+            ctx.deps.storage,
+            &lockup_key(&collection.address, &token_id),
+            &Lockup::new(
+                owner.clone(),
+                collection.address.clone(),
+                token_id.clone(),
+                ctx.env.block.time,
+            ),
+        )?;
+
+        // Send the target token to the owner, paying the fee and premium to the treasury
+        let cw721_msg = WasmMsg::Execute {
+            contract_addr: collection.address.to_string(),
+            msg: to_json_binary(&Cw721ExecuteMsg::TransferNft {
+                recipient: owner.to_string(),
+                token_id: target_token_id.clone(),
+            })?,
+            funds: vec![],
+        };
+        let denom = self.collection_denom(ctx.deps.as_ref(), collection)?;
+        let fee_msgs = self.fee_msgs(
+            ctx.deps.as_ref(),
+            Coin {
+                denom,
+                amount: fee + premium,
+            },
+        )?;
+
+        Ok(Response::new()
+            .add_submessage(SubMsg::new(cw721_msg))
+            .add_submessages(fee_msgs)
+            .add_attribute("method", "swap_nft")
+            .add_attribute("contract_address", ctx.env.contract.address.to_string())
+            .add_attribute("collection_address", collection.address.to_string())
+            .add_attribute("token_id", token_id)
+            .add_attribute("target_token_id", target_token_id)
+            .add_attribute("sent_to", owner.to_string()))
+    }
+
+    /// Priority window the token of a lockup is still within, if any
+    fn priority_window(
+        &self,
+        deps: Deps,
+        env: &Env,
+        lockup: &Lockup,
+    ) -> StdResult<Option<PriorityWindow>> {
+        Ok(self
+            .priority_window
+            .may_load(deps.storage)?
+            .flatten()
+            .filter(|priority_window| priority_window.reserves(lockup, env.block.time)))
+    }
+
+    /// Fee and premium paid by `redeemer`, if known, to redeem the token of a lockup.
+    /// Tokens within their priority window can only be redeemed by their depositor, who
+    /// pays no premium and a discounted fee
+    fn redemption_charges(
+        &self,
+        deps: Deps,
+        env: &Env,
+        collection: &Collection,
+        lockup: &Lockup,
+        redeemer: Option<&Addr>,
+    ) -> StdResult<(Uint128, Uint128)> {
+        match self.priority_window(deps, env, lockup)? {
+            Some(priority_window) => {
+                ensure_eq!(
+                    Some(&lockup.depositor),
+                    redeemer,
+                    StdError::generic_err("Token is reserved for its depositor")
+                );
+                Ok((
+                    priority_window.discounted_fee(collection.redeem_fee()),
+                    Uint128::zero(),
+                ))
+            }
+            None => Ok((
+                collection.redeem_fee(),
+                self.redeem_premium.load(deps.storage)?,
+            )),
+        }
+    }
+
+    /// Lockups of a collection that anyone can redeem, i.e. outside of their priority window
+    fn redeemable_lockups(
+        &self,
+        deps: Deps,
+        env: &Env,
+        collection_address: &Addr,
+    ) -> StdResult<Vec<(String, Lockup)>> {
+        let priority_window = self.priority_window.may_load(deps.storage)?.flatten();
+        Ok(self
+            .collection_lockups(deps, collection_address)?
+            .into_iter()
+            .filter(|(_, lockup)| {
+                !priority_window
+                    .as_ref()
+                    .is_some_and(|window| window.reserves(lockup, env.block.time))
+            })
+            .collect())
+    }
+
+    /// Lockups of a collection along with their keys
+    fn collection_lockups(
+        &self,
+        deps: Deps,
+        collection_address: &Addr,
+    ) -> StdResult<Vec<(String, Lockup)>> {
+        self.lockup
+            .idx
+            .collection
+            .prefix(collection_address.clone())
+            .range(deps.storage, None, None, cosmwasm_std::Order::Ascending)
+            .collect()
+    }
+}
@@ -1,5 +1,55 @@
-use cosmwasm_std::{Coin, CosmosMsg, Env};
-use osmosis_std::types::osmosis::tokenfactory::v1beta1::{MsgBurn, MsgMint};
+use cosmwasm_std::{Coin, CosmosMsg, Env, StdError, StdResult};
+use osmosis_std::types::cosmos::bank::v1beta1::{DenomUnit, Metadata};
+use osmosis_std::types::osmosis::tokenfactory::v1beta1::{
+    MsgBurn, MsgChangeAdmin, MsgCreateDenom, MsgMint, MsgSetDenomMetadata,
+};
+
+use crate::storage::DenomMetadata;
+
+pub fn create_denom(env: Env, subdenom: String) -> CosmosMsg {
+    MsgCreateDenom {
+        sender: env.contract.address.into(),
+        subdenom,
+    }
+    .into()
+}
+
+pub fn set_denom_metadata(env: Env, denom: String, metadata: DenomMetadata) -> CosmosMsg {
+    MsgSetDenomMetadata {
+        sender: env.contract.address.into(),
+        metadata: Some(Metadata {
+            description: metadata.description.unwrap_or_default(),
+            denom_units: vec![
+                DenomUnit {
+                    denom: denom.clone(),
+                    exponent: 0,
+                    aliases: vec![],
+                },
+                DenomUnit {
+                    denom: metadata.symbol.clone(),
+                    exponent: metadata.exponent,
+                    aliases: vec![],
+                },
+            ],
+            base: denom,
+            display: metadata.symbol.clone(),
+            name: metadata.name,
+            symbol: metadata.symbol,
+            uri: String::new(),
+            uri_hash: String::new(),
+        }),
+    }
+    .into()
+}
+
+pub fn change_denom_admin(env: Env, denom: String, new_admin: String) -> CosmosMsg {
+    MsgChangeAdmin {
+        sender: env.contract.address.into(),
+        denom,
+        new_admin,
+    }
+    .into()
+}
 
 pub fn mint_to(env: Env, address: String, amount: Coin) -> CosmosMsg {
     let account: String = env.contract.address.into();
@@ -32,3 +82,13 @@ pub fn burn(env: Env, amount: Coin) -> CosmosMsg {
 
     msg_burn
 }
+
+/// Pick an index below `len` from the first 8 bytes of `randomness`
+pub fn random_index(randomness: &[u8], len: usize) -> StdResult<usize> {
+    let bytes: [u8; 8] = randomness
+        .get(..8)
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| StdError::generic_err("Invalid randomness"))?;
+
+    Ok((u64::from_be_bytes(bytes) % len as u64) as usize)
+}
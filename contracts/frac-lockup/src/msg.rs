@@ -1,7 +1,10 @@
+use std::cmp::Ordering;
+
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::Addr;
+use cosmwasm_std::{Addr, HexBinary, Timestamp, Uint128};
+use serde::Deserialize;
 
-use crate::storage::{Collection, Lockup};
+use crate::storage::{Collection, Lockup, PriorityWindow};
 
 #[cw_serde]
 pub struct CountResponse {
@@ -18,4 +21,124 @@ pub struct ConfigResponse {
     pub admin: Addr,
     pub denom: String,
     pub collections: Vec<Collection>,
+    pub nois_proxy: Option<Addr>,
+    pub redeem_premium: Uint128,
+    pub treasury: Option<Addr>,
+    pub priority_window: Option<PriorityWindow>,
+}
+
+#[cw_serde]
+pub struct DepositQuoteResponse {
+    pub denom: String,
+    /// Tokens minted to the depositor
+    pub minted: Uint128,
+    /// Tokens minted to the treasury
+    pub fee: Uint128,
+}
+
+#[cw_serde]
+pub struct RedeemQuoteResponse {
+    pub denom: String,
+    /// Total amount of tokens to send
+    pub price: Uint128,
+    /// Part of the price paid to the treasury
+    pub fee: Uint128,
+    /// Part of the price charged for picking a specific token
+    pub premium: Uint128,
+}
+
+/// Whether the supply of a denom is covered by the tokens locked against it
+#[cw_serde]
+pub enum Health {
+    /// Supply matches the locked tokens exactly
+    Backed,
+    /// Supply is below the locked tokens
+    Overcollateralized,
+    /// Supply exceeds the locked tokens
+    Undercollateralized,
+}
+
+impl Health {
+    pub fn new(supply: Uint128, expected_supply: Uint128) -> Self {
+        match supply.cmp(&expected_supply) {
+            Ordering::Equal => Health::Backed,
+            Ordering::Less => Health::Overcollateralized,
+            Ordering::Greater => Health::Undercollateralized,
+        }
+    }
+}
+
+#[cw_serde]
+pub struct CollectionBacking {
+    pub collection_address: Addr,
+    pub denom: String,
+    /// Number of NFTs of the collection held by the contract
+    pub count: u64,
+    /// Tokens minted against these NFTs (`count × tokens`)
+    pub backing: Uint128,
+}
+
+#[cw_serde]
+pub struct DenomBacking {
+    pub denom: String,
+    /// Tokens minted against the NFTs of the collections using the denom
+    pub expected_supply: Uint128,
+    /// Actual supply of the denom
+    pub supply: Uint128,
+    pub health: Health,
+}
+
+#[cw_serde]
+pub struct BackingResponse {
+    pub collections: Vec<CollectionBacking>,
+    pub denoms: Vec<DenomBacking>,
+    /// Worst health of all denoms
+    pub health: Health,
+}
+
+/// Payload of a `SendNft` to the contract, which deposits the token when empty
+#[cw_serde]
+pub enum ReceiveNftMsg {
+    /// Deposit the token, doing something else with the minted tokens than sending them
+    Deposit { options: DepositOptions },
+    /// Swap the token sent for another token of the pool from the same collection
+    SwapNft { target_token_id: String },
+}
+
+#[cw_serde]
+pub enum DepositOptions {
+    /// Lock the minted tokens into a native-lockup contract on behalf of the depositor
+    StakeInto { lockup: String },
+}
+
+/// Messages sent to native-lockup contracts
+#[cw_serde]
+pub enum NativeLockupExecuteMsg {
+    DepositFor { recipient: String },
+}
+
+/// Queries sent to native-lockup contracts
+#[cw_serde]
+pub enum NativeLockupQueryMsg {
+    Config {},
+}
+
+/// Part of the configuration of native-lockup contracts, other fields are ignored
+#[derive(Deserialize)]
+pub struct NativeLockupConfigResponse {
+    pub token: String,
+}
+
+/// Messages sent to the randomness proxy (Nois proxy interface)
+#[cw_serde]
+pub enum NoisProxyExecuteMsg {
+    GetNextRandomness { job_id: String },
+}
+
+/// Randomness delivered by the proxy for a job
+#[cw_serde]
+pub struct NoisCallback {
+    pub job_id: String,
+    pub published: Timestamp,
+    pub randomness: HexBinary,
 }
@@ -1,19 +1,142 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Addr, Timestamp, Uint128};
+use cosmwasm_std::{ensure, Addr, Api, StdError, StdResult, Timestamp, Uint128};
 use cw_storage_plus::{Index, IndexList, MultiIndex};
 
 #[cw_serde]
 pub struct CollectionInput {
     pub address: String,
     pub tokens: u128,
+    /// Fee on deposits, in basis points of `tokens`
+    pub deposit_fee_bps: Option<u16>,
+    /// Fee on redemptions, in basis points of `tokens`
+    pub redeem_fee_bps: Option<u16>,
+    /// Fee on swaps of a token for another, in basis points of `tokens`
+    pub swap_fee_bps: Option<u16>,
+    /// Subdenom of a tokenfactory denom created for the collection alone
+    pub subdenom: Option<String>,
+}
+
+impl CollectionInput {
+    pub fn check(&self, api: &dyn Api) -> StdResult<Collection> {
+        let collection = Collection {
+            address: api.addr_validate(&self.address)?,
+            tokens: Uint128::from(self.tokens),
+            deposit_fee_bps: self.deposit_fee_bps.unwrap_or_default(),
+            redeem_fee_bps: self.redeem_fee_bps.unwrap_or_default(),
+            swap_fee_bps: self.swap_fee_bps.unwrap_or_default(),
+            denom: None,
+            retired: false,
+        };
+        collection.validate()?;
+
+        Ok(collection)
+    }
 }
 
 #[cw_serde]
 pub struct Collection {
     pub address: Addr,
     pub tokens: Uint128,
+    #[serde(default)]
+    pub deposit_fee_bps: u16,
+    #[serde(default)]
+    pub redeem_fee_bps: u16,
+    #[serde(default)]
+    pub swap_fee_bps: u16,
+    /// Denom of the collection, if it does not use the contract's denom
+    #[serde(default)]
+    pub denom: Option<String>,
+    /// Retired collections take no more deposits, but their tokens can still be redeemed
+    #[serde(default)]
+    pub retired: bool,
+}
+
+impl Collection {
+    pub fn validate(&self) -> StdResult<()> {
+        ensure!(
+            self.deposit_fee_bps <= MAX_FEE_BPS
+                && self.redeem_fee_bps <= MAX_FEE_BPS
+                && self.swap_fee_bps <= MAX_FEE_BPS,
+            StdError::generic_err(format!("Fee must not exceed {} basis points", MAX_FEE_BPS))
+        );
+        Ok(())
+    }
+
+    /// Fee kept out of the tokens minted for a deposit
+    pub fn deposit_fee(&self) -> Uint128 {
+        self.tokens.multiply_ratio(self.deposit_fee_bps, 10_000u128)
+    }
+
+    /// Fee paid on top of the tokens burned for a redemption
+    pub fn redeem_fee(&self) -> Uint128 {
+        self.tokens.multiply_ratio(self.redeem_fee_bps, 10_000u128)
+    }
+
+    /// Fee paid for swapping a token for another
+    pub fn swap_fee(&self) -> Uint128 {
+        self.tokens.multiply_ratio(self.swap_fee_bps, 10_000u128)
+    }
+
+    pub fn has_fees(&self) -> bool {
+        self.deposit_fee_bps > 0 || self.redeem_fee_bps > 0 || self.swap_fee_bps > 0
+    }
 }
 
+/// Window after a deposit during which only the depositor can redeem the token
+#[cw_serde]
+pub struct PriorityWindow {
+    pub hours: u64,
+    /// Discount on the redemption fee for the depositor, in basis points
+    pub fee_discount_bps: u16,
+}
+
+impl PriorityWindow {
+    pub fn validate(&self) -> StdResult<()> {
+        ensure!(
+            self.hours > 0,
+            StdError::generic_err("Priority window must be greater than 0")
+        );
+        ensure!(
+            self.fee_discount_bps <= 10_000,
+            StdError::generic_err("Fee discount must not exceed 10000 basis points")
+        );
+        Ok(())
+    }
+
+    /// Whether the token of a lockup is still reserved for its depositor
+    pub fn reserves(&self, lockup: &Lockup, time: Timestamp) -> bool {
+        time < lockup.locked_since.plus_hours(self.hours)
+    }
+
+    /// Redemption fee paid by the depositor within the window
+    pub fn discounted_fee(&self, fee: Uint128) -> Uint128 {
+        fee - fee.multiply_ratio(self.fee_discount_bps, 10_000u128)
+    }
+}
+
+/// Denom of the fractional tokens of the pool
+#[cw_serde]
+pub enum DenomInput {
+    /// Existing tokenfactory denom, whose admin must be handed over to the contract
+    Existing(String),
+    /// Tokenfactory denom created by the contract on instantiation
+    Create(DenomMetadata),
+}
+
+/// Bank metadata of a denom created by the contract
+#[cw_serde]
+pub struct DenomMetadata {
+    pub subdenom: String,
+    pub name: String,
+    pub symbol: String,
+    /// Decimals of the display unit, which is named after the symbol
+    pub exponent: u32,
+    pub description: Option<String>,
+}
+
+/// Highest deposit, redemption or swap fee, in basis points
+pub use lockup_common::MAX_FEE_BPS;
+
 #[cw_serde]
 pub struct Lockup {
     pub depositor: Addr,
@@ -38,6 +161,28 @@ impl Lockup {
     }
 }
 
+/// Primary key of the lockup of a token
+pub fn lockup_key(collection_address: &Addr, token_id: &str) -> String {
+    format!("{}/{}", collection_address, token_id)
+}
+
+/// Seconds after which a random redemption the proxy has not fulfilled can be cancelled
+pub const RANDOM_REDEMPTION_TIMEOUT: u64 = 24 * 60 * 60;
+
+/// Redemption of a random token, waiting for randomness from the proxy
+#[cw_serde]
+pub struct RandomRedemption {
+    pub redeemer: Addr,
+    pub collection_address: Addr,
+    /// Fractional tokens held until the redemption is fulfilled
+    pub denom: String,
+    pub amount: Uint128,
+    /// Part of the held tokens paid to the treasury once fulfilled
+    #[serde(default)]
+    pub fee: Uint128,
+    pub requested_at: Timestamp,
+}
+
 type Token = (Addr, String);
 
 pub struct LockupIndexes<'a> {
@@ -48,7 +193,7 @@ pub struct LockupIndexes<'a> {
 
 impl<'a> IndexList<Lockup> for LockupIndexes<'a> {
     fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<Lockup>> + '_> {
-        let v: Vec<&dyn Index<Lockup>> = vec![&self.token, &self.depositor];
+        let v: Vec<&dyn Index<Lockup>> = vec![&self.token, &self.collection, &self.depositor];
         Box::new(v.into_iter())
     }
 }
@@ -1,33 +1,38 @@
 use cosmwasm_std::{
-    coin, ensure, ensure_eq, Addr, BankMsg, Response, StdError, StdResult, SubMsg, Timestamp,
-    Uint128,
+    coin, ensure, ensure_eq, Addr, BankMsg, Binary, DepsMut, Env, Order, Response, StdError,
+    StdResult, SubMsg, Timestamp, Uint128,
 };
 use cw2::ContractVersion;
 use cw_storage_plus::{Item, Map};
 
-use sylvia::types::{ExecCtx, InstantiateCtx, QueryCtx};
+use lockup_common::fungible::FungibleLockups;
+use lockup_common::receipts::Receipts;
+
+use sylvia::types::{ExecCtx, InstantiateCtx, MigrateCtx, QueryCtx};
 use sylvia::{contract, entry_points};
 
 use crate::msg::{ConfigResponse, CountResponse};
-use crate::storage::Lockup;
+use crate::storage::{LegacyLockup, Lockup};
 use crate::{ACTOR_ID, VERSION};
 
 pub struct NativeLockupContract {
     pub(crate) admin: Item<'static, Addr>,
     pub(crate) token: Item<'static, String>,
-    pub(crate) lockup_interval: Item<'static, Timestamp>,
-    pub(crate) lockup: Map<'static, Addr, Lockup>,
+    pub(crate) lockups: FungibleLockups<'static>,
+    /// Contracts allowed to deposit on behalf of other addresses
+    pub(crate) depositors: Item<'static, Vec<Addr>>,
 }
 
 #[entry_points]
 #[contract]
+#[messages(lockup_common::receipts as Receipts)]
 impl NativeLockupContract {
     pub const fn new() -> Self {
         Self {
             admin: Item::new("admin"),
             token: Item::new("token"),
-            lockup_interval: Item::new("lockup_interval"),
-            lockup: Map::new("lockup"),
+            lockups: FungibleLockups::new(),
+            depositors: Item::new("depositors"),
         }
     }
 
@@ -39,7 +44,7 @@ impl NativeLockupContract {
         lockup_interval: Option<Timestamp>,
     ) -> StdResult<Response> {
         self.token.save(ctx.deps.storage, &token)?;
-        self.lockup_interval.save(
+        self.lockups.lockup_interval.save(
             ctx.deps.storage,
             &lockup_interval
                 .or(Some(Timestamp::from_seconds(0)))
@@ -47,6 +52,11 @@ impl NativeLockupContract {
         )?;
         self.admin.save(ctx.deps.storage, &ctx.info.sender)?;
 
+        // Lockup positions are represented by receipt NFTs issued by this contract
+        self.lockups
+            .receipts
+            .save_info(ctx.deps.storage, format!("{} Lockup Receipt", token))?;
+
         Ok(Response::new())
     }
 
@@ -85,7 +95,7 @@ impl NativeLockupContract {
         );
 
         // Save the new config
-        self.lockup_interval.save(
+        self.lockups.lockup_interval.save(
             ctx.deps.storage,
             &lockup_interval
                 .or(Some(Timestamp::from_seconds(0)))
@@ -105,121 +115,112 @@ impl NativeLockupContract {
     }
 
     #[msg(exec)]
-    fn deposit(&self, ctx: ExecCtx) -> StdResult<Response> {
-        // Verify that the amount of funds sent is over 0
-        ensure!(
-            !ctx.info.funds.is_empty(),
-            StdError::generic_err("No funds sent")
-        );
-        ensure!(
-            ctx.info.funds[0].amount > Uint128::zero(),
-            StdError::generic_err("Funds sent must be greater than 0")
-        );
-
-        // Verify that only one token type was sent
+    fn update_stake_contracts(
+        &self,
+        ctx: ExecCtx,
+        stake_contracts: Vec<String>,
+    ) -> StdResult<Response> {
+        // Admin only
+        let admin = self.admin.load(ctx.deps.storage).unwrap();
         ensure_eq!(
-            ctx.info.funds.len(),
-            1,
-            StdError::generic_err("Only one token type can be sent")
+            admin,
+            ctx.info.sender,
+            StdError::generic_err("Unauthorized")
         );
 
-        // Verify that the funds sent are in the correct token
-        let token = self.token.load(ctx.deps.storage)?;
+        // Verify stake contract addresses
+        let stake_contracts = stake_contracts
+            .into_iter()
+            .map(|addr| ctx.deps.api.addr_validate(&addr))
+            .collect::<StdResult<Vec<Addr>>>()?;
+
+        self.lockups
+            .stake_contracts
+            .save(ctx.deps.storage, &stake_contracts)?;
+
+        Ok(Response::new()
+            .add_attribute("method", "update_stake_contracts")
+            .add_attribute("contract_address", ctx.env.contract.address.to_string())
+            .add_attribute(
+                "stake_contracts",
+                stake_contracts
+                    .iter()
+                    .map(|addr| addr.to_string())
+                    .collect::<Vec<String>>()
+                    .join(","),
+            ))
+    }
+
+    /// Replace the contracts allowed to deposit on behalf of other addresses
+    #[msg(exec)]
+    fn update_depositors(&self, ctx: ExecCtx, depositors: Vec<String>) -> StdResult<Response> {
+        // Admin only
+        let admin = self.admin.load(ctx.deps.storage)?;
         ensure_eq!(
-            ctx.info.funds[0].denom.as_str(),
-            token.as_str(),
-            StdError::generic_err("Unsupported token sent")
+            admin,
+            ctx.info.sender,
+            StdError::generic_err("Unauthorized")
         );
 
-        // Check if there is already a lockup for this user
-        let existing_lockup = self
-            .lockup
-            .may_load(ctx.deps.storage, ctx.info.sender.clone())?;
-        let lockup_interval = self.lockup_interval.load(ctx.deps.storage)?;
-
-        // If there is no lockup, create a new one
-        // If there is one, append the funds to the existing lockup
-        match existing_lockup {
-            Some(lockup) => {
-                let mut new_lockup = lockup;
-                new_lockup.amount += ctx.info.funds[0].amount;
-                new_lockup.locked_until =
-                    ctx.env.block.time.plus_seconds(lockup_interval.seconds());
-                self.lockup
-                    .save(ctx.deps.storage, ctx.info.sender.clone(), &new_lockup)?;
-            }
-            None => {
-                let lockup = Lockup::new(
-                    ctx.info.funds[0].amount,
-                    ctx.env.block.time,
-                    ctx.env.block.time.plus_seconds(lockup_interval.seconds()),
-                );
-
-                self.lockup
-                    .save(ctx.deps.storage, ctx.info.sender.clone(), &lockup)?;
-            }
-        }
+        // Verify depositor addresses
+        let depositors = depositors
+            .into_iter()
+            .map(|addr| ctx.deps.api.addr_validate(&addr))
+            .collect::<StdResult<Vec<Addr>>>()?;
 
-        let new_lockup = self
-            .lockup
-            .load(ctx.deps.storage, ctx.info.sender.clone())?;
+        self.depositors.save(ctx.deps.storage, &depositors)?;
 
         Ok(Response::new()
-            .add_attribute("method", "deposit")
+            .add_attribute("method", "update_depositors")
             .add_attribute("contract_address", ctx.env.contract.address.to_string())
-            .add_attribute("sender", ctx.info.sender.to_string())
-            .add_attribute("amount", new_lockup.amount.to_string())
-            .add_attribute("locked_until", new_lockup.locked_until.to_string()))
+            .add_attribute(
+                "depositors",
+                depositors
+                    .iter()
+                    .map(|addr| addr.to_string())
+                    .collect::<Vec<String>>()
+                    .join(","),
+            ))
     }
 
     #[msg(exec)]
-    fn withdraw(&self, ctx: ExecCtx, amount: Option<Uint128>) -> StdResult<Response> {
-        let lockup = self
-            .lockup
-            .load(ctx.deps.storage, ctx.info.sender.clone())?;
+    fn deposit(&self, ctx: ExecCtx) -> StdResult<Response> {
+        let owner = ctx.info.sender.clone();
+        self.lock_funds(ctx, owner, "deposit")
+    }
 
-        // If the lockup has not expired, return an error
+    /// Lock the funds sent into the lockup of `recipient`, e.g. for contracts depositing for users
+    /// Deposits for others do not extend the time an existing lockup is locked for
+    #[msg(exec)]
+    fn deposit_for(&self, ctx: ExecCtx, recipient: String) -> StdResult<Response> {
+        // Approved depositors only, since an address holding a lockup cannot receive
+        // lockup transfers
+        let depositors = self
+            .depositors
+            .may_load(ctx.deps.storage)?
+            .unwrap_or_default();
         ensure!(
-            ctx.env.block.time > lockup.locked_until,
-            StdError::generic_err("Lockup period has not passed")
+            depositors.contains(&ctx.info.sender),
+            StdError::generic_err("Unauthorized")
         );
 
-        // If the amount requested is higher than the value of the lockup, return an error
-        ensure!(
-            amount.is_none() || amount.unwrap() <= lockup.amount,
-            StdError::generic_err("Amount requested is higher than the lockup value")
-        );
+        let recipient = ctx.deps.api.addr_validate(&recipient)?;
+        self.lock_funds(ctx, recipient, "deposit_for")
+    }
 
-        let token = self.token.load(ctx.deps.storage)?;
+    #[msg(exec)]
+    fn withdraw(&self, ctx: ExecCtx, amount: Option<Uint128>) -> StdResult<Response> {
+        let (lockup, amount) =
+            self.lockups
+                .unlock(ctx.deps.storage, &ctx.env, &ctx.info.sender, amount)?;
 
-        // If there is no amount specified, send the full amount
-        let amount = amount.unwrap_or(lockup.amount);
+        let token = self.token.load(ctx.deps.storage)?;
         let msg = BankMsg::Send {
             to_address: ctx.info.sender.to_string(),
             amount: vec![coin(amount.u128(), token.clone())],
         };
         let send_msg = SubMsg::new(msg);
 
-        // Subtract the amount from the lockup
-        // or remove the lockup if the entire balance was withdrawn
-        if amount == lockup.amount {
-            self.lockup
-                .remove(ctx.deps.storage, ctx.info.sender.clone());
-        } else {
-            self.lockup.update(
-                ctx.deps.storage,
-                ctx.info.sender.clone(),
-                |lockup| match lockup {
-                    Some(lockup) => Ok(Lockup::new(
-                        lockup.amount - amount,
-                        lockup.locked_since,
-                        lockup.locked_until,
-                    )),
-                    None => Err(StdError::generic_err("Lockup not found")),
-                },
-            )?;
-        }
-
         // Send the funds to the user
         let res = Response::new()
             .add_submessage(send_msg)
@@ -232,18 +233,60 @@ impl NativeLockupContract {
         Ok(res)
     }
 
-    #[msg(query)]
-    fn count(&self, ctx: QueryCtx) -> StdResult<CountResponse> {
-        // Get all amounts from `count` Map
-        let lockups = self
-            .lockup
-            .range(ctx.deps.storage, None, None, cosmwasm_std::Order::Ascending)
-            .map(|res| res.map(|item| item.1.amount.u128()))
+    #[msg(exec)]
+    fn transfer_lockup(&self, ctx: ExecCtx, recipient: String) -> StdResult<Response> {
+        self.lockups.transfer_lockup(ctx, recipient)
+    }
+
+    /// Issue receipts for lockups created before lockup positions were represented by receipts
+    #[msg(migrate)]
+    fn migrate(&self, ctx: MigrateCtx) -> StdResult<Response> {
+        let legacy_lockup: Map<'static, Addr, LegacyLockup> = Map::new("lockup");
+
+        // Receipt information is missing if the contract predates receipts
+        if self
+            .lockups
+            .receipts
+            .nfts()
+            .contract_info
+            .may_load(ctx.deps.storage)?
+            .is_none()
+        {
+            let token = self.token.load(ctx.deps.storage)?;
+            self.lockups
+                .receipts
+                .save_info(ctx.deps.storage, format!("{} Lockup Receipt", token))?;
+        }
+
+        let entries = legacy_lockup
+            .range(ctx.deps.storage, None, None, Order::Ascending)
             .collect::<StdResult<Vec<_>>>()?;
+        let mut migrated = 0u64;
+        for (owner, legacy) in entries {
+            if legacy.receipt_id.is_some() {
+                continue;
+            }
 
-        // Get sum of items in lockups
-        let count = lockups.iter().sum::<u128>();
+            let receipt_id = self.lockups.receipts.mint(ctx.deps.storage, &owner)?;
+            let lockup = Lockup::new(
+                receipt_id,
+                legacy.amount,
+                legacy.locked_since,
+                legacy.locked_until,
+            );
+            self.lockups.lockup.save(ctx.deps.storage, owner, &lockup)?;
+            migrated += 1;
+        }
 
+        Ok(Response::new()
+            .add_attribute("method", "migrate")
+            .add_attribute("contract_address", ctx.env.contract.address.to_string())
+            .add_attribute("migrated", migrated.to_string()))
+    }
+
+    #[msg(query)]
+    fn count(&self, ctx: QueryCtx) -> StdResult<CountResponse> {
+        let count = self.lockups.total(ctx.deps.storage)?;
         Ok(CountResponse { count })
     }
 
@@ -258,19 +301,106 @@ impl NativeLockupContract {
     #[msg(query)]
     fn lockup(&self, ctx: QueryCtx, address: String) -> StdResult<Lockup> {
         let address = ctx.deps.api.addr_validate(&address)?;
-        let lockup = self.lockup.load(ctx.deps.storage, address)?;
+        let lockup = self.lockups.lockup.load(ctx.deps.storage, address)?;
         Ok(lockup)
     }
 
     #[msg(query)]
     fn config(&self, ctx: QueryCtx) -> StdResult<ConfigResponse> {
         let admin = self.admin.load(ctx.deps.storage)?;
-        let lockup_interval = self.lockup_interval.load(ctx.deps.storage)?;
+        let lockup_interval = self.lockups.lockup_interval.load(ctx.deps.storage)?;
         let token = self.token.load(ctx.deps.storage)?;
+        let stake_contracts = self
+            .lockups
+            .stake_contracts
+            .may_load(ctx.deps.storage)?
+            .unwrap_or_default();
+        let depositors = self
+            .depositors
+            .may_load(ctx.deps.storage)?
+            .unwrap_or_default();
         Ok(ConfigResponse {
             admin,
             lockup_interval,
             token,
+            stake_contracts,
+            depositors,
         })
     }
 }
+
+impl NativeLockupContract {
+    /// Lock the funds sent into the lockup of `owner`, creating it if needed
+    fn lock_funds(&self, ctx: ExecCtx, owner: Addr, method: &str) -> StdResult<Response> {
+        // Verify that the amount of funds sent is over 0
+        ensure!(
+            !ctx.info.funds.is_empty(),
+            StdError::generic_err("No funds sent")
+        );
+        ensure!(
+            ctx.info.funds[0].amount > Uint128::zero(),
+            StdError::generic_err("Funds sent must be greater than 0")
+        );
+
+        // Verify that only one token type was sent
+        ensure_eq!(
+            ctx.info.funds.len(),
+            1,
+            StdError::generic_err("Only one token type can be sent")
+        );
+
+        // Verify that the funds sent are in the correct token
+        let token = self.token.load(ctx.deps.storage)?;
+        ensure_eq!(
+            ctx.info.funds[0].denom.as_str(),
+            token.as_str(),
+            StdError::generic_err("Unsupported token sent")
+        );
+
+        let new_lockup = self.lockups.lock(
+            ctx.deps.storage,
+            &ctx.env,
+            &ctx.info.sender,
+            &owner,
+            ctx.info.funds[0].amount,
+        )?;
+
+        Ok(Response::new()
+            .add_attribute("method", method)
+            .add_attribute("contract_address", ctx.env.contract.address.to_string())
+            .add_attribute("sender", ctx.info.sender.to_string())
+            .add_attribute("owner", owner.to_string())
+            .add_attribute("receipt_id", new_lockup.receipt_id)
+            .add_attribute("amount", new_lockup.amount.to_string())
+            .add_attribute("locked_until", new_lockup.locked_until.to_string()))
+    }
+}
+
+impl Receipts for NativeLockupContract {
+    type Error = StdError;
+
+    fn transfer_nft(
+        &self,
+        ctx: ExecCtx,
+        recipient: String,
+        token_id: String,
+    ) -> StdResult<Response> {
+        self.lockups.transfer_nft(ctx, recipient, token_id)
+    }
+
+    fn send_nft(
+        &self,
+        ctx: ExecCtx,
+        contract: String,
+        token_id: String,
+        msg: Binary,
+    ) -> StdResult<Response> {
+        self.lockups.send_nft(ctx, contract, token_id, msg)
+    }
+}
+
+/// Migrate entry point, not generated by `#[entry_points]`
+#[cosmwasm_std::entry_point]
+pub fn migrate(deps: DepsMut, env: Env, msg: MigrateMsg) -> StdResult<Response> {
+    msg.dispatch(&NativeLockupContract::new(), (deps, env))
+}
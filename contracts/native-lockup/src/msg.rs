@@ -11,4 +11,6 @@ pub struct ConfigResponse {
     pub admin: Addr,
     pub lockup_interval: Timestamp,
     pub token: String,
+    pub stake_contracts: Vec<Addr>,
+    pub depositors: Vec<Addr>,
 }
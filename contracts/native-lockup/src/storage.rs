@@ -1,19 +1,14 @@
 use cosmwasm_schema::cw_serde;
 use cosmwasm_std::{Timestamp, Uint128};
 
+pub use lockup_common::fungible::Lockup;
+
+/// Lockup entry that may predate receipts, used when migrating
 #[cw_serde]
-pub struct Lockup {
+pub struct LegacyLockup {
+    #[serde(default)]
+    pub receipt_id: Option<String>,
     pub amount: Uint128,
     pub locked_since: Timestamp,
     pub locked_until: Timestamp,
 }
-
-impl Lockup {
-    pub fn new(amount: Uint128, locked_since: Timestamp, locked_until: Timestamp) -> Self {
-        Self {
-            amount,
-            locked_since,
-            locked_until,
-        }
-    }
-}
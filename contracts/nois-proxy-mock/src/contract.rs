@@ -0,0 +1,86 @@
+use cosmwasm_std::{to_json_binary, Addr, HexBinary, Response, StdResult, SubMsg, WasmMsg};
+use cw_storage_plus::Item;
+
+use sylvia::types::{ExecCtx, InstantiateCtx, QueryCtx};
+use sylvia::{contract, entry_points};
+
+use crate::msg::{Job, NoisCallback, ReceiverExecuteMsg};
+
+/// Randomness proxy standing in for a Nois proxy in tests
+/// Jobs are queued until randomness is delivered with `Deliver`
+pub struct NoisProxyMockContract {
+    pub(crate) jobs: Item<'static, Vec<(Addr, String)>>,
+}
+
+#[entry_points]
+#[contract]
+impl NoisProxyMockContract {
+    pub const fn new() -> Self {
+        Self {
+            jobs: Item::new("jobs"),
+        }
+    }
+
+    #[msg(instantiate)]
+    fn instantiate(&self, ctx: InstantiateCtx) -> StdResult<Response> {
+        self.jobs.save(ctx.deps.storage, &vec![])?;
+
+        Ok(Response::new())
+    }
+
+    #[msg(exec)]
+    fn get_next_randomness(&self, ctx: ExecCtx, job_id: String) -> StdResult<Response> {
+        let mut jobs = self.jobs.load(ctx.deps.storage)?;
+        jobs.push((ctx.info.sender.clone(), job_id.clone()));
+        self.jobs.save(ctx.deps.storage, &jobs)?;
+
+        Ok(Response::new()
+            .add_attribute("method", "get_next_randomness")
+            .add_attribute("contract_address", ctx.env.contract.address.to_string())
+            .add_attribute("job_id", job_id)
+            .add_attribute("sender", ctx.info.sender.to_string()))
+    }
+
+    /// Deliver `randomness` to every queued job
+    #[msg(exec)]
+    fn deliver(&self, ctx: ExecCtx, randomness: HexBinary) -> StdResult<Response> {
+        let jobs = self.jobs.load(ctx.deps.storage)?;
+        self.jobs.save(ctx.deps.storage, &vec![])?;
+
+        let callback_msgs = jobs
+            .into_iter()
+            .map(|(requester, job_id)| {
+                let msg = ReceiverExecuteMsg::NoisReceive {
+                    callback: NoisCallback {
+                        job_id,
+                        published: ctx.env.block.time,
+                        randomness: randomness.clone(),
+                    },
+                };
+                Ok(SubMsg::new(WasmMsg::Execute {
+                    contract_addr: requester.to_string(),
+                    msg: to_json_binary(&msg)?,
+                    funds: vec![],
+                }))
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(Response::new()
+            .add_submessages(callback_msgs)
+            .add_attribute("method", "deliver")
+            .add_attribute("contract_address", ctx.env.contract.address.to_string()))
+    }
+
+    #[msg(query)]
+    fn jobs(&self, ctx: QueryCtx) -> StdResult<Vec<Job>> {
+        Ok(self
+            .jobs
+            .load(ctx.deps.storage)?
+            .into_iter()
+            .map(|(requester, job_id)| Job {
+                requester: requester.to_string(),
+                job_id,
+            })
+            .collect())
+    }
+}
@@ -0,0 +1,5 @@
+pub const CONTRACT: &str = "nois_proxy_mock";
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+pub mod contract;
+pub mod msg;
@@ -0,0 +1,22 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{HexBinary, Timestamp};
+
+/// Randomness delivered to a job's requester
+#[cw_serde]
+pub struct NoisCallback {
+    pub job_id: String,
+    pub published: Timestamp,
+    pub randomness: HexBinary,
+}
+
+/// Messages sent to requesters (Nois receiver interface)
+#[cw_serde]
+pub enum ReceiverExecuteMsg {
+    NoisReceive { callback: NoisCallback },
+}
+
+#[cw_serde]
+pub struct Job {
+    pub requester: String,
+    pub job_id: String,
+}
@@ -0,0 +1,305 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{
+    ensure, ensure_eq, to_json_binary, Addr, Binary, DepsMut, Env, MessageInfo, Order, Response,
+    StdError, StdResult, Storage, SubMsg, Timestamp, Uint128, WasmMsg,
+};
+use cw721::Cw721ReceiveMsg;
+use cw_storage_plus::{Item, Map};
+use sylvia::types::ExecCtx;
+
+use crate::receipts::LockupReceipts;
+
+#[cw_serde]
+pub struct Lockup {
+    pub receipt_id: String,
+    pub amount: Uint128,
+    pub locked_since: Timestamp,
+    pub locked_until: Timestamp,
+}
+
+impl Lockup {
+    pub fn new(
+        receipt_id: String,
+        amount: Uint128,
+        locked_since: Timestamp,
+        locked_until: Timestamp,
+    ) -> Self {
+        Self {
+            receipt_id,
+            amount,
+            locked_since,
+            locked_until,
+        }
+    }
+}
+
+/// Messages sent by the lockup contract to its linked stake contracts
+#[cw_serde]
+pub enum StakeExecuteMsg {
+    MigrateClaim {
+        from: (String, String),
+        to: (String, String),
+    },
+}
+
+/// Lockups of a fungible token, one per address, shared by the native and CW20 lockups
+pub struct FungibleLockups<'a> {
+    pub lockup_interval: Item<'a, Timestamp>,
+    pub lockup: Map<'a, Addr, Lockup>,
+    /// Stake contracts notified when a lockup changes owner
+    pub stake_contracts: Item<'a, Vec<Addr>>,
+    /// Receipt NFTs representing lockup positions
+    pub receipts: LockupReceipts<'a>,
+}
+
+impl<'a> FungibleLockups<'a> {
+    pub const fn new() -> Self {
+        Self {
+            lockup_interval: Item::new("lockup_interval"),
+            lockup: Map::new("lockup"),
+            stake_contracts: Item::new("stake_contracts"),
+            receipts: LockupReceipts::new(),
+        }
+    }
+
+    /// Add `amount` to the lockup of `owner`, creating it along with its receipt if needed.
+    /// Deposits made by others do not extend the time an existing lockup is locked for.
+    pub fn lock(
+        &self,
+        storage: &mut dyn Storage,
+        env: &Env,
+        sender: &Addr,
+        owner: &Addr,
+        amount: Uint128,
+    ) -> StdResult<Lockup> {
+        ensure!(
+            amount > Uint128::zero(),
+            StdError::generic_err("Funds sent must be greater than 0")
+        );
+
+        let lockup_interval = self.lockup_interval.load(storage)?;
+        let locked_until = env.block.time.plus_seconds(lockup_interval.seconds());
+
+        // If there is no lockup, create a new one
+        // If there is one, append the funds to the existing lockup
+        let lockup = match self.lockup.may_load(storage, owner.clone())? {
+            Some(mut lockup) => {
+                lockup.amount += amount;
+                if owner == sender {
+                    lockup.locked_until = locked_until;
+                }
+                lockup
+            }
+            None => {
+                let receipt_id = self.receipts.mint(storage, owner)?;
+                Lockup::new(receipt_id, amount, env.block.time, locked_until)
+            }
+        };
+        self.lockup.save(storage, owner.clone(), &lockup)?;
+
+        Ok(lockup)
+    }
+
+    /// Take `amount`, or the whole lockup if unset, out of the expired lockup of `owner`,
+    /// burning its receipt once it is emptied. Returns the lockup and the amount taken.
+    pub fn unlock(
+        &self,
+        storage: &mut dyn Storage,
+        env: &Env,
+        owner: &Addr,
+        amount: Option<Uint128>,
+    ) -> StdResult<(Lockup, Uint128)> {
+        let lockup = self.lockup.load(storage, owner.clone())?;
+
+        // Verify that the owner holds the lockup receipt
+        let receipt_owner = self.receipts.owner(storage, &lockup.receipt_id)?;
+        ensure_eq!(
+            &receipt_owner,
+            owner,
+            StdError::generic_err("Sender is not the owner of the lockup receipt")
+        );
+
+        // If the lockup has not expired, return an error
+        ensure!(
+            env.block.time > lockup.locked_until,
+            StdError::generic_err("Lockup period has not passed")
+        );
+
+        // If the amount requested is higher than the value of the lockup, return an error
+        ensure!(
+            amount.is_none() || amount.unwrap() <= lockup.amount,
+            StdError::generic_err("Amount requested is higher than the lockup value")
+        );
+
+        // Subtract the amount from the lockup
+        // or remove the lockup and burn its receipt if the entire balance was withdrawn
+        let amount = amount.unwrap_or(lockup.amount);
+        if amount == lockup.amount {
+            self.lockup.remove(storage, owner.clone());
+            self.receipts.burn(storage, &lockup.receipt_id)?;
+        } else {
+            self.lockup.save(
+                storage,
+                owner.clone(),
+                &Lockup {
+                    amount: lockup.amount - amount,
+                    ..lockup.clone()
+                },
+            )?;
+        }
+
+        Ok((lockup, amount))
+    }
+
+    /// Transfer a lockup receipt and move the lockup it represents to the new owner.
+    /// Lockups are keyed by the address holding their receipt, so the recipient cannot
+    /// already hold one.
+    pub fn transfer(
+        &self,
+        mut deps: DepsMut,
+        env: &Env,
+        info: &MessageInfo,
+        recipient: &str,
+        receipt_id: &str,
+    ) -> StdResult<(Addr, Addr, Lockup, Vec<SubMsg>)> {
+        let owner = self.receipts.owner(deps.storage, receipt_id)?;
+        let recipient = deps.api.addr_validate(recipient)?;
+
+        ensure!(
+            owner != recipient,
+            StdError::generic_err("Cannot transfer a lockup to its owner")
+        );
+        ensure!(
+            !self.lockup.has(deps.storage, recipient.clone()),
+            StdError::generic_err("Recipient already has a lockup")
+        );
+
+        // Transfer the receipt, verifying that the sender is allowed to do so
+        self.receipts
+            .transfer(deps.branch(), env, info, &recipient, receipt_id)?;
+
+        // Move the lockup, keeping its lock period intact
+        let lockup = self.lockup.load(deps.storage, owner.clone())?;
+        self.lockup.remove(deps.storage, owner.clone());
+        self.lockup.save(deps.storage, recipient.clone(), &lockup)?;
+
+        // Notify linked stake contracts so that claim information follows the lockup
+        let stake_contracts = self
+            .stake_contracts
+            .may_load(deps.storage)?
+            .unwrap_or_default();
+        let notify_msgs = stake_contracts
+            .iter()
+            .map(|stake_contract| {
+                let msg = StakeExecuteMsg::MigrateClaim {
+                    from: (owner.to_string(), String::from("")),
+                    to: (recipient.to_string(), String::from("")),
+                };
+                Ok(SubMsg::new(WasmMsg::Execute {
+                    contract_addr: stake_contract.to_string(),
+                    msg: to_json_binary(&msg)?,
+                    funds: vec![],
+                }))
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok((owner, recipient, lockup, notify_msgs))
+    }
+
+    /// Move the lockup of the sender to `recipient` along with its receipt
+    pub fn transfer_lockup(&self, mut ctx: ExecCtx, recipient: String) -> StdResult<Response> {
+        let lockup = self
+            .lockup
+            .load(ctx.deps.storage, ctx.info.sender.clone())?;
+
+        let (owner, recipient, lockup, notify_msgs) = self.transfer(
+            ctx.deps.branch(),
+            &ctx.env,
+            &ctx.info,
+            &recipient,
+            &lockup.receipt_id,
+        )?;
+
+        Ok(Response::new()
+            .add_submessages(notify_msgs)
+            .add_attribute("method", "transfer_lockup")
+            .add_attribute("contract_address", ctx.env.contract.address.to_string())
+            .add_attribute("sender", owner.to_string())
+            .add_attribute("recipient", recipient.to_string())
+            .add_attribute("receipt_id", lockup.receipt_id)
+            .add_attribute("amount", lockup.amount.to_string())
+            .add_attribute("locked_until", lockup.locked_until.to_string()))
+    }
+
+    /// Transfer a receipt through its CW721 interface, moving the lockup along with it
+    pub fn transfer_nft(
+        &self,
+        mut ctx: ExecCtx,
+        recipient: String,
+        token_id: String,
+    ) -> StdResult<Response> {
+        let (owner, recipient, _, notify_msgs) = self.transfer(
+            ctx.deps.branch(),
+            &ctx.env,
+            &ctx.info,
+            &recipient,
+            &token_id,
+        )?;
+
+        Ok(Response::new()
+            .add_submessages(notify_msgs)
+            .add_attribute("method", "transfer_nft")
+            .add_attribute("contract_address", ctx.env.contract.address.to_string())
+            .add_attribute("sender", ctx.info.sender.to_string())
+            .add_attribute("owner", owner.to_string())
+            .add_attribute("recipient", recipient.to_string())
+            .add_attribute("token_id", token_id))
+    }
+
+    /// Send a receipt to a contract through its CW721 interface, moving the lockup along with it
+    pub fn send_nft(
+        &self,
+        mut ctx: ExecCtx,
+        contract: String,
+        token_id: String,
+        msg: Binary,
+    ) -> StdResult<Response> {
+        let (owner, contract, _, notify_msgs) =
+            self.transfer(ctx.deps.branch(), &ctx.env, &ctx.info, &contract, &token_id)?;
+
+        // Notify the receiving contract
+        let receive_msg = Cw721ReceiveMsg {
+            sender: ctx.info.sender.to_string(),
+            token_id: token_id.clone(),
+            msg,
+        }
+        .into_cosmos_msg(contract.to_string())?;
+
+        Ok(Response::new()
+            .add_submessages(notify_msgs)
+            .add_message(receive_msg)
+            .add_attribute("method", "send_nft")
+            .add_attribute("contract_address", ctx.env.contract.address.to_string())
+            .add_attribute("sender", ctx.info.sender.to_string())
+            .add_attribute("owner", owner.to_string())
+            .add_attribute("recipient", contract.to_string())
+            .add_attribute("token_id", token_id))
+    }
+
+    /// Total amount held in lockups
+    pub fn total(&self, storage: &dyn Storage) -> StdResult<u128> {
+        let lockups = self
+            .lockup
+            .range(storage, None, None, Order::Ascending)
+            .map(|res| res.map(|item| item.1.amount.u128()))
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(lockups.iter().sum::<u128>())
+    }
+}
+
+impl<'a> Default for FungibleLockups<'a> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,5 @@
+pub mod fungible;
+pub mod receipts;
+
+/// Highest fee any contract can be configured to charge, in basis points
+pub const MAX_FEE_BPS: u16 = 1000;
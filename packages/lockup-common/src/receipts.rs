@@ -0,0 +1,327 @@
+use cosmwasm_std::{
+    Addr, Binary, DepsMut, Empty, Env, MessageInfo, Response, StdError, StdResult, Storage,
+};
+use cw_storage_plus::Item;
+
+use cw721::{
+    AllNftInfoResponse, ApprovalResponse, ApprovalsResponse, ContractInfoResponse, Cw721Execute,
+    Cw721Query, Expiration, NftInfoResponse, NumTokensResponse, OperatorResponse,
+    OperatorsResponse, OwnerOfResponse, TokensResponse,
+};
+use cw721_base::state::TokenInfo;
+use cw721_base::{ContractError, Cw721Contract};
+
+use sylvia::interface;
+use sylvia::types::{ExecCtx, QueryCtx};
+
+/// Receipt NFTs representing lockup positions
+pub type ReceiptNfts<'a> = Cw721Contract<'a, Empty, Empty, Empty, Empty>;
+
+/// Receipts issued by a lockup contract, one for each lockup position
+pub struct LockupReceipts<'a> {
+    /// Last token ID issued for a lockup receipt
+    count: Item<'a, u64>,
+}
+
+impl<'a> LockupReceipts<'a> {
+    pub const fn new() -> Self {
+        Self {
+            count: Item::new("receipt_count"),
+        }
+    }
+
+    pub fn nfts(&self) -> ReceiptNfts<'static> {
+        ReceiptNfts::default()
+    }
+
+    /// Save the collection information of the receipts
+    pub fn save_info(&self, storage: &mut dyn Storage, name: String) -> StdResult<()> {
+        self.nfts().contract_info.save(
+            storage,
+            &ContractInfoResponse {
+                name,
+                symbol: String::from("LOCKUP"),
+            },
+        )
+    }
+
+    /// Mint a new lockup receipt to `owner` and return its token ID
+    pub fn mint(&self, storage: &mut dyn Storage, owner: &Addr) -> StdResult<String> {
+        let receipt_id = self.count.may_load(storage)?.unwrap_or_default() + 1;
+        self.count.save(storage, &receipt_id)?;
+
+        let nfts = self.nfts();
+        let receipt_id = receipt_id.to_string();
+        nfts.tokens.save(
+            storage,
+            &receipt_id,
+            &TokenInfo {
+                owner: owner.clone(),
+                approvals: vec![],
+                token_uri: None,
+                extension: Empty {},
+            },
+        )?;
+        nfts.increment_tokens(storage)?;
+
+        Ok(receipt_id)
+    }
+
+    pub fn burn(&self, storage: &mut dyn Storage, receipt_id: &str) -> StdResult<()> {
+        let nfts = self.nfts();
+        nfts.tokens.remove(storage, receipt_id)?;
+        nfts.decrement_tokens(storage)?;
+        Ok(())
+    }
+
+    pub fn owner(&self, storage: &dyn Storage, receipt_id: &str) -> StdResult<Addr> {
+        Ok(self.nfts().tokens.load(storage, receipt_id)?.owner)
+    }
+
+    /// Transfer a receipt to `recipient`, verifying that the sender is allowed to do so
+    pub fn transfer(
+        &self,
+        deps: DepsMut,
+        env: &Env,
+        info: &MessageInfo,
+        recipient: &Addr,
+        receipt_id: &str,
+    ) -> StdResult<()> {
+        self.nfts()
+            ._transfer_nft(deps, env, info, recipient.as_str(), receipt_id)
+            .map(|_| ())
+            .map_err(|err| match err {
+                ContractError::Ownership(_) => {
+                    StdError::generic_err("Sender is not the owner of the lockup receipt")
+                }
+                err => receipt_error(err),
+            })
+    }
+}
+
+impl<'a> Default for LockupReceipts<'a> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn receipt_error(err: ContractError) -> StdError {
+    StdError::generic_err(err.to_string())
+}
+
+/// CW721 interface of the lockup receipts.
+/// Transfers move the lockup position along with its receipt, so they are left to each contract.
+#[interface]
+pub trait Receipts {
+    type Error: From<StdError>;
+
+    #[msg(exec)]
+    fn transfer_nft(
+        &self,
+        ctx: ExecCtx,
+        recipient: String,
+        token_id: String,
+    ) -> Result<Response, Self::Error>;
+
+    #[msg(exec)]
+    fn send_nft(
+        &self,
+        ctx: ExecCtx,
+        contract: String,
+        token_id: String,
+        msg: Binary,
+    ) -> Result<Response, Self::Error>;
+
+    #[msg(exec)]
+    fn approve(
+        &self,
+        ctx: ExecCtx,
+        spender: String,
+        token_id: String,
+        expires: Option<Expiration>,
+    ) -> Result<Response, Self::Error> {
+        Ok(LockupReceipts::new()
+            .nfts()
+            .approve(ctx.deps, ctx.env, ctx.info, spender, token_id, expires)
+            .map_err(receipt_error)?)
+    }
+
+    #[msg(exec)]
+    fn revoke(
+        &self,
+        ctx: ExecCtx,
+        spender: String,
+        token_id: String,
+    ) -> Result<Response, Self::Error> {
+        Ok(LockupReceipts::new()
+            .nfts()
+            .revoke(ctx.deps, ctx.env, ctx.info, spender, token_id)
+            .map_err(receipt_error)?)
+    }
+
+    #[msg(exec)]
+    fn approve_all(
+        &self,
+        ctx: ExecCtx,
+        operator: String,
+        expires: Option<Expiration>,
+    ) -> Result<Response, Self::Error> {
+        Ok(LockupReceipts::new()
+            .nfts()
+            .approve_all(ctx.deps, ctx.env, ctx.info, operator, expires)
+            .map_err(receipt_error)?)
+    }
+
+    #[msg(exec)]
+    fn revoke_all(&self, ctx: ExecCtx, operator: String) -> Result<Response, Self::Error> {
+        Ok(LockupReceipts::new()
+            .nfts()
+            .revoke_all(ctx.deps, ctx.env, ctx.info, operator)
+            .map_err(receipt_error)?)
+    }
+
+    #[msg(query)]
+    fn owner_of(
+        &self,
+        ctx: QueryCtx,
+        token_id: String,
+        include_expired: Option<bool>,
+    ) -> Result<OwnerOfResponse, Self::Error> {
+        Ok(LockupReceipts::new().nfts().owner_of(
+            ctx.deps,
+            ctx.env,
+            token_id,
+            include_expired.unwrap_or(false),
+        )?)
+    }
+
+    #[msg(query)]
+    fn approval(
+        &self,
+        ctx: QueryCtx,
+        token_id: String,
+        spender: String,
+        include_expired: Option<bool>,
+    ) -> Result<ApprovalResponse, Self::Error> {
+        Ok(LockupReceipts::new().nfts().approval(
+            ctx.deps,
+            ctx.env,
+            token_id,
+            spender,
+            include_expired.unwrap_or(false),
+        )?)
+    }
+
+    #[msg(query)]
+    fn approvals(
+        &self,
+        ctx: QueryCtx,
+        token_id: String,
+        include_expired: Option<bool>,
+    ) -> Result<ApprovalsResponse, Self::Error> {
+        Ok(LockupReceipts::new().nfts().approvals(
+            ctx.deps,
+            ctx.env,
+            token_id,
+            include_expired.unwrap_or(false),
+        )?)
+    }
+
+    #[msg(query)]
+    fn operator(
+        &self,
+        ctx: QueryCtx,
+        owner: String,
+        operator: String,
+        include_expired: Option<bool>,
+    ) -> Result<OperatorResponse, Self::Error> {
+        Ok(LockupReceipts::new().nfts().operator(
+            ctx.deps,
+            ctx.env,
+            owner,
+            operator,
+            include_expired.unwrap_or(false),
+        )?)
+    }
+
+    #[msg(query)]
+    fn all_operators(
+        &self,
+        ctx: QueryCtx,
+        owner: String,
+        include_expired: Option<bool>,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> Result<OperatorsResponse, Self::Error> {
+        Ok(LockupReceipts::new().nfts().operators(
+            ctx.deps,
+            ctx.env,
+            owner,
+            include_expired.unwrap_or(false),
+            start_after,
+            limit,
+        )?)
+    }
+
+    #[msg(query)]
+    fn num_tokens(&self, ctx: QueryCtx) -> Result<NumTokensResponse, Self::Error> {
+        Ok(LockupReceipts::new().nfts().num_tokens(ctx.deps)?)
+    }
+
+    #[msg(query)]
+    fn contract_info(&self, ctx: QueryCtx) -> Result<ContractInfoResponse, Self::Error> {
+        Ok(Cw721Query::<Empty>::contract_info(
+            &LockupReceipts::new().nfts(),
+            ctx.deps,
+        )?)
+    }
+
+    #[msg(query)]
+    fn nft_info(
+        &self,
+        ctx: QueryCtx,
+        token_id: String,
+    ) -> Result<NftInfoResponse<Empty>, Self::Error> {
+        Ok(LockupReceipts::new().nfts().nft_info(ctx.deps, token_id)?)
+    }
+
+    #[msg(query)]
+    fn all_nft_info(
+        &self,
+        ctx: QueryCtx,
+        token_id: String,
+        include_expired: Option<bool>,
+    ) -> Result<AllNftInfoResponse<Empty>, Self::Error> {
+        Ok(LockupReceipts::new().nfts().all_nft_info(
+            ctx.deps,
+            ctx.env,
+            token_id,
+            include_expired.unwrap_or(false),
+        )?)
+    }
+
+    #[msg(query)]
+    fn tokens(
+        &self,
+        ctx: QueryCtx,
+        owner: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> Result<TokensResponse, Self::Error> {
+        Ok(LockupReceipts::new()
+            .nfts()
+            .tokens(ctx.deps, owner, start_after, limit)?)
+    }
+
+    #[msg(query)]
+    fn all_tokens(
+        &self,
+        ctx: QueryCtx,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> Result<TokensResponse, Self::Error> {
+        Ok(LockupReceipts::new()
+            .nfts()
+            .all_tokens(ctx.deps, start_after, limit)?)
+    }
+}
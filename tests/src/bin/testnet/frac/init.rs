@@ -1,10 +1,8 @@
 use cw_orch::{daemon::TxSender, prelude::*};
-use frac_lockup::storage::CollectionInput;
+use frac_lockup::storage::{CollectionInput, DenomInput, DenomMetadata};
 use osmosis_test_tube::osmosis_std::types::{
+    cosmos::base::v1beta1::Coin,
     cosmwasm::wasm::v1::{MsgInstantiateContract, MsgInstantiateContractResponse},
-    osmosis::tokenfactory::v1beta1::{
-        MsgChangeAdmin, MsgChangeAdminResponse, MsgCreateDenom, MsgCreateDenomResponse,
-    },
 };
 use prost::Message;
 use prost_types::Any;
@@ -53,28 +51,6 @@ pub fn main() {
     );
     assert!(cw721_init_res.is_ok());
 
-    let create_denom_res = daemon.commit_any::<MsgCreateDenomResponse>(
-        vec![Any {
-            type_url: MsgCreateDenom::TYPE_URL.to_string(),
-            value: MsgCreateDenom {
-                sender: daemon.sender_addr().to_string(),
-                subdenom: subdenom.clone(),
-            }
-            .encode_to_vec(),
-        }],
-        None,
-    );
-    assert!(create_denom_res.is_ok());
-
-    let denom = format!(
-        "factory/{}/{}",
-        daemon.sender_addr().to_string(),
-        subdenom.clone()
-    );
-
-    let set_res = daemon.state().set("custom", "denom", denom.clone());
-    assert!(set_res.is_ok());
-
     let frac_lockup = FracLockup::new(daemon.clone());
 
     let frac_lockup_init_res = frac_lockup.upload();
@@ -88,8 +64,21 @@ pub fn main() {
         collections: vec![CollectionInput {
             address: cw721_base.addr_str().unwrap(),
             tokens: 1000000,
+            deposit_fee_bps: None,
+            redeem_fee_bps: None,
+            swap_fee_bps: None,
+            subdenom: None,
         }],
-        denom: denom.clone(),
+        denom: DenomInput::Create(DenomMetadata {
+            subdenom: subdenom.clone(),
+            name: String::from("WAU Test Collection"),
+            symbol: subdenom.clone(),
+            exponent: 6,
+            description: None,
+        }),
+        nois_proxy: None,
+        redeem_premium: None,
+        treasury: None,
     };
 
     let frac_lockup_init_res = daemon.commit_any::<MsgInstantiateContractResponse>(
@@ -100,7 +89,15 @@ pub fn main() {
                 admin: daemon.sender().address().to_string(),
                 code_id: frac_lockup_code_id,
                 label: "frac-lockup".to_string(),
-                funds: vec![],
+                // Denom creation fee, in ustars, if the chain charges one
+                funds: std::env::var("DENOM_CREATION_FEE")
+                    .map(|amount| {
+                        vec![Coin {
+                            denom: "ustars".to_string(),
+                            amount,
+                        }]
+                    })
+                    .unwrap_or_default(),
                 msg: cosmwasm_std::to_json_binary(&frac_lockup_instantiate_msg)
                     .unwrap()
                     .into(),
@@ -119,19 +116,11 @@ pub fn main() {
 
     frac_lockup.set_address(&frac_lockup_address);
 
-    let change_admin_res = daemon.commit_any::<MsgChangeAdminResponse>(
-        vec![Any {
-            type_url: MsgChangeAdmin::TYPE_URL.to_string(),
-            value: MsgChangeAdmin {
-                sender: daemon.sender().address().to_string(),
-                denom: denom.clone(),
-                new_admin: frac_lockup_address.to_string(),
-            }
-            .encode_to_vec(),
-        }],
-        None,
-    );
-    assert!(change_admin_res.is_ok());
+    // The contract creates its own denom
+    let denom = format!("factory/{}/{}", frac_lockup_address, subdenom.clone());
+
+    let set_res = daemon.state().set("custom", "denom", denom.clone());
+    assert!(set_res.is_ok());
 
     let mint_msg = Cw721ExecuteMsg::Mint {
         token_id: "1".to_string(),
@@ -0,0 +1,20 @@
+use cw_orch::{interface, prelude::*};
+
+use cw20_lockup::contract::entry_points::{execute, instantiate, query};
+pub use cw20_lockup::contract::{ExecMsg as ExecuteMsg, InstantiateMsg, QueryMsg};
+
+#[interface(InstantiateMsg, ExecuteMsg, QueryMsg, cosmwasm_std::Empty,  id = cw20_lockup::CONTRACT)]
+pub struct Cw20Lockup;
+
+impl<Chain> Uploadable for Cw20Lockup<Chain> {
+    /// Return the path to the wasm file corresponding to the contract
+    fn wasm(_chain: &ChainInfoOwned) -> WasmPath {
+        artifacts_dir_from_workspace!()
+            .find_wasm_path(cw20_lockup::CONTRACT)
+            .unwrap()
+    }
+    /// Returns a CosmWasm contract wrapper
+    fn wrapper() -> Box<dyn MockContract<Empty>> {
+        Box::new(ContractWrapper::new_with_empty(execute, instantiate, query))
+    }
+}
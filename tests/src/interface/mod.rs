@@ -1,3 +1,4 @@
+pub mod cw20_lockup;
 pub mod cw721_lockup;
 pub mod frac_lockup;
 pub mod native_lockup;
@@ -5,3 +6,4 @@ pub mod native_lockup;
 pub mod fixed_stake;
 
 pub mod cw721_base;
+pub mod nois_proxy_mock;
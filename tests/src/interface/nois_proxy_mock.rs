@@ -0,0 +1,20 @@
+use cw_orch::{interface, prelude::*};
+
+use nois_proxy_mock::contract::entry_points::{execute, instantiate, query};
+pub use nois_proxy_mock::contract::{ExecMsg as ExecuteMsg, InstantiateMsg, QueryMsg};
+
+#[interface(InstantiateMsg, ExecuteMsg, QueryMsg, cosmwasm_std::Empty,  id = nois_proxy_mock::CONTRACT)]
+pub struct NoisProxyMock;
+
+impl<Chain> Uploadable for NoisProxyMock<Chain> {
+    /// Return the path to the wasm file corresponding to the contract
+    fn wasm(_chain: &ChainInfoOwned) -> WasmPath {
+        artifacts_dir_from_workspace!()
+            .find_wasm_path(nois_proxy_mock::CONTRACT)
+            .unwrap()
+    }
+    /// Returns a CosmWasm contract wrapper
+    fn wrapper() -> Box<dyn MockContract<Empty>> {
+        Box::new(ContractWrapper::new_with_empty(execute, instantiate, query))
+    }
+}
@@ -0,0 +1,198 @@
+#![cfg(test)]
+
+use cosmwasm_std::{Addr, Binary, Empty, StdError, Timestamp, Uint128};
+use cw20::{BalanceResponse, Cw20Coin, Cw20ExecuteMsg, Cw20QueryMsg};
+use cw_multi_test::{App, Contract, ContractWrapper, Executor};
+
+pub fn contract_cw20() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        cw20_base::contract::execute,
+        cw20_base::contract::instantiate,
+        cw20_base::contract::query,
+    );
+    Box::new(contract)
+}
+
+pub fn contract_lockup() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        cw20_lockup::contract::entry_points::execute,
+        cw20_lockup::contract::entry_points::instantiate,
+        cw20_lockup::contract::entry_points::query,
+    );
+    Box::new(contract)
+}
+
+const CW20: &str = "contract0";
+const LOCKUP: &str = "contract1";
+
+const ADMIN: &str = "admin";
+const USER: &str = "user";
+
+// Initial contract setup
+fn setup_contracts() -> App {
+    let admin = Addr::unchecked(ADMIN);
+
+    let mut router = App::default();
+
+    // Set up CW20 contract
+    let cw20_id = router.store_code(contract_cw20());
+    let msg = cw20_base::msg::InstantiateMsg {
+        name: String::from("Stardust"),
+        symbol: String::from("STRDST"),
+        decimals: 6,
+        initial_balances: vec![Cw20Coin {
+            address: USER.to_string(),
+            amount: Uint128::from(2000u128),
+        }],
+        mint: None,
+        marketing: None,
+    };
+
+    let cw20_addr = router
+        .instantiate_contract(cw20_id, admin.clone(), &msg, &[], "CW20", None)
+        .unwrap();
+
+    // Set up Cw20Lockup contract
+    let lockup_id = router.store_code(contract_lockup());
+    let msg = cw20_lockup::contract::InstantiateMsg {
+        lockup_interval: Some(Timestamp::from_seconds(3600)),
+        token: cw20_addr.to_string(),
+    };
+
+    router
+        .instantiate_contract(lockup_id, admin.clone(), &msg, &[], "LOCKUP", None)
+        .unwrap();
+
+    router
+}
+
+// Update block time
+fn add_block_time(router: &mut App, seconds: u64) {
+    let mut block = router.block_info();
+    block.time = block.time.plus_seconds(seconds);
+    router.set_block(block);
+}
+
+// Send CW20 tokens to a contract
+fn send_cw20(router: &mut App, sender: Addr, recipient: Addr, amount: u128) {
+    let msg = Cw20ExecuteMsg::Send {
+        contract: recipient.to_string(),
+        amount: Uint128::from(amount),
+        msg: Binary::default(),
+    };
+
+    router
+        .execute_contract(sender, Addr::unchecked(CW20), &msg, &[])
+        .unwrap();
+}
+
+#[test]
+fn proper_initialization() {
+    setup_contracts();
+}
+
+#[test]
+fn try_query_config() {
+    let router = setup_contracts();
+    let msg = cw20_lockup::contract::QueryMsg::Config {};
+    let res: cw20_lockup::msg::ConfigResponse =
+        router.wrap().query_wasm_smart(LOCKUP, &msg).unwrap();
+    assert_eq!(res.admin, ADMIN);
+    assert_eq!(res.lockup_interval, Timestamp::from_seconds(3600));
+    assert_eq!(res.token, Addr::unchecked(CW20));
+}
+
+#[test]
+fn try_deposit() {
+    let mut router = setup_contracts();
+    let user = Addr::unchecked(USER);
+
+    // User calls `Receive` directly without sending tokens
+    let msg = cw20_lockup::contract::ExecMsg::Receive {
+        sender: user.to_string(),
+        amount: Uint128::from(500u128),
+    };
+    let err = router
+        .execute_contract(user.clone(), Addr::unchecked(LOCKUP), &msg, &[])
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<StdError>().unwrap(),
+        StdError::generic_err("Unsupported token sent")
+    );
+
+    // User deposits 500 tokens
+    send_cw20(&mut router, user.clone(), Addr::unchecked(LOCKUP), 500);
+
+    // Query the lockup
+    let query_msg = cw20_lockup::contract::QueryMsg::Lockup {
+        address: user.to_string(),
+    };
+    let res: cw20_lockup::storage::Lockup =
+        router.wrap().query_wasm_smart(LOCKUP, &query_msg).unwrap();
+    assert_eq!(res.amount, Uint128::from(500u128));
+
+    // Deposit 500 more tokens
+    send_cw20(&mut router, user.clone(), Addr::unchecked(LOCKUP), 500);
+
+    // Query the lockup
+    let res: cw20_lockup::storage::Lockup =
+        router.wrap().query_wasm_smart(LOCKUP, &query_msg).unwrap();
+    assert_eq!(res.amount, Uint128::from(1000u128));
+
+    let query_msg = cw20_lockup::contract::QueryMsg::Count {};
+    let res: cw20_lockup::msg::CountResponse =
+        router.wrap().query_wasm_smart(LOCKUP, &query_msg).unwrap();
+    assert_eq!(res.count, 1000);
+}
+
+#[test]
+fn try_withdraw() {
+    let mut router = setup_contracts();
+    let user = Addr::unchecked(USER);
+
+    let withdraw_amount = Uint128::from(500u128);
+
+    // User deposits 1000 tokens
+    send_cw20(&mut router, user.clone(), Addr::unchecked(LOCKUP), 1000);
+
+    // Withdraw before lockup period has passed
+    let msg = cw20_lockup::contract::ExecMsg::Withdraw {
+        amount: Some(withdraw_amount),
+    };
+    let err = router
+        .execute_contract(user.clone(), Addr::unchecked(LOCKUP), &msg, &[])
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<StdError>().unwrap(),
+        StdError::generic_err("Lockup period has not passed")
+    );
+
+    // Update block time to pass lockup period
+    add_block_time(&mut router, 3700);
+
+    // Withdraw after lockup period has passed
+    router
+        .execute_contract(user.clone(), Addr::unchecked(LOCKUP), &msg, &[])
+        .unwrap();
+
+    // Query the lockup
+    let query_msg = cw20_lockup::contract::QueryMsg::Lockup {
+        address: user.to_string(),
+    };
+    let res: cw20_lockup::storage::Lockup =
+        router.wrap().query_wasm_smart(LOCKUP, &query_msg).unwrap();
+    assert_eq!(res.amount, Uint128::from(500u128));
+
+    // Withdraw the remaining amount
+    let msg = cw20_lockup::contract::ExecMsg::Withdraw { amount: None };
+    router
+        .execute_contract(user.clone(), Addr::unchecked(LOCKUP), &msg, &[])
+        .unwrap();
+
+    // Verify that the user got all tokens back
+    let query_msg = Cw20QueryMsg::Balance {
+        address: user.to_string(),
+    };
+    let res: BalanceResponse = router.wrap().query_wasm_smart(CW20, &query_msg).unwrap();
+    assert_eq!(res.balance, Uint128::from(2000u128));
+}
@@ -1,8 +1,13 @@
 #![cfg(test)]
 
-use cosmwasm_std::{coins, Addr, Empty, StdError, Timestamp};
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{
+    coins, Addr, DepsMut, Empty, Env, MessageInfo, Response, StdError, StdResult, Timestamp,
+};
 use cw721_base::msg::ExecuteMsg as Cw721ExecuteMsg;
+use cw721_lockup::storage::{CollectionRule, CollectionRuleInput};
 use cw_multi_test::{App, Contract, ContractWrapper, Executor};
+use cw_storage_plus::{Index, IndexList, IndexedMap, MultiIndex};
 
 pub fn contract_cw721() -> Box<dyn Contract<Empty>> {
     let contract = ContractWrapper::new(
@@ -22,6 +27,77 @@ pub fn contract_lockup() -> Box<dyn Contract<Empty>> {
     Box::new(contract)
 }
 
+pub fn contract_legacy_lockup() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        cw721_lockup::contract::entry_points::execute,
+        legacy_lockup_instantiate,
+        cw721_lockup::contract::entry_points::query,
+    )
+    .with_migrate(cw721_lockup::contract::migrate);
+    Box::new(contract)
+}
+
+// Lockup entry as stored before lockup positions were represented by receipts
+#[cw_serde]
+struct BaselineLockup {
+    owner: Addr,
+    collection_address: Addr,
+    token_id: String,
+    locked_since: Timestamp,
+    locked_until: Timestamp,
+}
+
+struct BaselineLockupIndexes<'a> {
+    token: MultiIndex<'a, (Addr, String), BaselineLockup, String>,
+    owner: MultiIndex<'a, Addr, BaselineLockup, String>,
+}
+
+impl<'a> IndexList<BaselineLockup> for BaselineLockupIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<BaselineLockup>> + '_> {
+        let v: Vec<&dyn Index<BaselineLockup>> = vec![&self.token, &self.owner];
+        Box::new(v.into_iter())
+    }
+}
+
+// Cw721 lockup instantiation leaving the storage of a contract that predates receipts,
+// holding a lockup of token "1" for the user under the key it used
+fn legacy_lockup_instantiate(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: cw721_lockup::contract::InstantiateMsg,
+) -> StdResult<Response> {
+    let res = cw721_lockup::contract::entry_points::instantiate(deps.branch(), env, info, msg)?;
+
+    lockup_common::receipts::ReceiptNfts::default()
+        .contract_info
+        .remove(deps.storage);
+
+    let indexes = BaselineLockupIndexes {
+        token: MultiIndex::new(
+            |_, d| (d.collection_address.clone(), d.token_id.clone()),
+            "lockup",
+            "lockup__token",
+        ),
+        owner: MultiIndex::new(|_, d| d.owner.clone(), "lockup", "lockup__owner"),
+    };
+    let lockup: IndexedMap<&str, BaselineLockup, BaselineLockupIndexes> =
+        IndexedMap::new("lockup", indexes);
+    lockup.save(
+        deps.storage,
+        "lockup__depositor",
+        &BaselineLockup {
+            owner: Addr::unchecked(USER),
+            collection_address: Addr::unchecked(CW721),
+            token_id: "1".to_string(),
+            locked_since: Timestamp::from_seconds(1),
+            locked_until: Timestamp::from_seconds(3601),
+        },
+    )?;
+
+    Ok(res)
+}
+
 const CW721: &str = "contract0";
 const LOCKUP: &str = "contract1";
 
@@ -58,6 +134,7 @@ fn setup_contracts() -> App {
     let msg = cw721_lockup::contract::InstantiateMsg {
         lockup_interval: Some(Timestamp::from_seconds(3600)),
         collections: vec![cw721_addr.to_string()],
+        collection_rules: None,
     };
 
     router
@@ -198,3 +275,362 @@ fn try_withdraw_cw721() {
         router.wrap().query_wasm_smart(LOCKUP, &msg).unwrap();
     assert_eq!(res.lockups.len(), 0);
 }
+
+#[test]
+fn try_transfer_lockup() {
+    let mut router = setup_contracts();
+
+    let user = Addr::unchecked(USER);
+    let admin = Addr::unchecked(ADMIN);
+    let recipient = Addr::unchecked("recipient");
+    let contract = Addr::unchecked(LOCKUP);
+    let token_id = "1";
+
+    mint_cw721(&mut router, user.clone(), "1");
+    send_cw721(&mut router, user.clone(), contract.clone(), token_id);
+
+    let msg = cw721_lockup::contract::ExecMsg::TransferLockup {
+        recipient: recipient.to_string(),
+        collection_address: CW721.to_string(),
+        token_id: token_id.to_string(),
+    };
+
+    // Only the owner can transfer the lockup
+    let err = router
+        .execute_contract(admin.clone(), contract.clone(), &msg, &[])
+        .unwrap_err();
+
+    assert_eq!(
+        err.downcast::<StdError>().unwrap(),
+        StdError::generic_err("Sender is not the owner of the lockup receipt")
+    );
+
+    router
+        .execute_contract(user.clone(), contract.clone(), &msg, &[])
+        .unwrap();
+
+    // The owner index follows the transfer
+    let msg = cw721_lockup::contract::QueryMsg::LockupsByOwner {
+        owner: user.to_string(),
+    };
+    let res: cw721_lockup::msg::LockupsReponse =
+        router.wrap().query_wasm_smart(LOCKUP, &msg).unwrap();
+    assert_eq!(res.lockups.len(), 0);
+
+    let msg = cw721_lockup::contract::QueryMsg::LockupsByOwner {
+        owner: recipient.to_string(),
+    };
+    let res: cw721_lockup::msg::LockupsReponse =
+        router.wrap().query_wasm_smart(LOCKUP, &msg).unwrap();
+    assert_eq!(res.lockups.len(), 1);
+    assert_eq!(res.lockups[0].owner, recipient);
+    assert_eq!(res.lockups[0].token_id, token_id);
+
+    // The lockup period is preserved
+    let msg = cw721_lockup::contract::ExecMsg::Withdraw {
+        collection_address: CW721.to_string(),
+        token_id: token_id.to_string(),
+    };
+    let err = router
+        .execute_contract(recipient.clone(), contract.clone(), &msg, &[])
+        .unwrap_err();
+
+    assert_eq!(
+        err.downcast::<StdError>().unwrap(),
+        StdError::generic_err("Lockup period has not passed")
+    );
+
+    add_block_time(&mut router, 3700);
+
+    router
+        .execute_contract(recipient.clone(), contract, &msg, &[])
+        .unwrap();
+}
+
+#[test]
+fn try_transfer_receipt() {
+    let mut router = setup_contracts();
+
+    let user = Addr::unchecked(USER);
+    let admin = Addr::unchecked(ADMIN);
+    let recipient = Addr::unchecked("recipient");
+    let contract = Addr::unchecked(LOCKUP);
+
+    mint_cw721(&mut router, user.clone(), "1");
+    mint_cw721(&mut router, user.clone(), "2");
+    send_cw721(&mut router, user.clone(), contract.clone(), "1");
+    send_cw721(&mut router, user.clone(), contract.clone(), "2");
+
+    // Each lockup is represented by its own receipt
+    let msg = lockup_common::receipts::QueryMsg::Tokens {
+        owner: user.to_string(),
+        start_after: None,
+        limit: None,
+    };
+    let res: cw721::TokensResponse = router.wrap().query_wasm_smart(LOCKUP, &msg).unwrap();
+    assert_eq!(res.tokens.len(), 2);
+
+    let msg = cw721_lockup::contract::QueryMsg::LockupByToken {
+        collection_address: CW721.to_string(),
+        token_id: "1".to_string(),
+    };
+    let lockup: cw721_lockup::storage::Lockup =
+        router.wrap().query_wasm_smart(LOCKUP, &msg).unwrap();
+
+    // User approves admin, who transfers the receipt to recipient
+    let msg = lockup_common::receipts::ExecMsg::Approve {
+        spender: admin.to_string(),
+        token_id: lockup.receipt_id.clone(),
+        expires: None,
+    };
+    router
+        .execute_contract(user.clone(), contract.clone(), &msg, &[])
+        .unwrap();
+
+    let msg = lockup_common::receipts::ExecMsg::TransferNft {
+        recipient: recipient.to_string(),
+        token_id: lockup.receipt_id.clone(),
+    };
+    router
+        .execute_contract(admin.clone(), contract.clone(), &msg, &[])
+        .unwrap();
+
+    let msg = cw721_lockup::contract::QueryMsg::LockupsByOwner {
+        owner: recipient.to_string(),
+    };
+    let res: cw721_lockup::msg::LockupsReponse =
+        router.wrap().query_wasm_smart(LOCKUP, &msg).unwrap();
+    assert_eq!(res.lockups.len(), 1);
+    assert_eq!(res.lockups[0].token_id, "1");
+
+    add_block_time(&mut router, 3700);
+
+    // The original depositor can no longer withdraw
+    let msg = cw721_lockup::contract::ExecMsg::Withdraw {
+        collection_address: CW721.to_string(),
+        token_id: "1".to_string(),
+    };
+    let err = router
+        .execute_contract(user.clone(), contract.clone(), &msg, &[])
+        .unwrap_err();
+
+    assert_eq!(
+        err.downcast::<StdError>().unwrap(),
+        StdError::generic_err("Sender is not the owner of the NFT")
+    );
+
+    // The receipt holder withdraws the NFT
+    router
+        .execute_contract(recipient.clone(), contract, &msg, &[])
+        .unwrap();
+
+    let msg = cw721::Cw721QueryMsg::OwnerOf {
+        token_id: "1".to_string(),
+        include_expired: None,
+    };
+    let res: cw721::OwnerOfResponse = router.wrap().query_wasm_smart(CW721, &msg).unwrap();
+    assert_eq!(res.owner, recipient.to_string());
+
+    let msg = lockup_common::receipts::QueryMsg::NumTokens {};
+    let res: cw721::NumTokensResponse = router.wrap().query_wasm_smart(LOCKUP, &msg).unwrap();
+    assert_eq!(res.count, 1);
+}
+
+#[test]
+fn try_collection_rules() {
+    let mut router = setup_contracts();
+    let admin = Addr::unchecked(ADMIN);
+    let user = Addr::unchecked(USER);
+
+    // Set up a Cw721Lockup contract listing no collection
+    let msg = cw721_lockup::contract::InstantiateMsg {
+        lockup_interval: None,
+        collections: vec![],
+        collection_rules: None,
+    };
+    let lockup = router
+        .instantiate_contract(2, admin.clone(), &msg, &[], "LOCKUP", None)
+        .unwrap();
+
+    mint_cw721(&mut router, user.clone(), "1");
+    let msg: Cw721ExecuteMsg<Empty, Empty> = Cw721ExecuteMsg::SendNft {
+        contract: lockup.to_string(),
+        token_id: "1".to_string(),
+        msg: b"{}".to_vec().into(),
+    };
+    let err = router
+        .execute_contract(user.clone(), Addr::unchecked(CW721), &msg, &[])
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<StdError>().unwrap(),
+        StdError::generic_err("Collection is not supported")
+    );
+
+    // Collections of another factory are not supported either
+    let rules_msg = cw721_lockup::contract::ExecMsg::UpdateCollectionRules {
+        collection_rules: vec![CollectionRuleInput::Factory("factory".to_string())],
+    };
+    router
+        .execute_contract(admin.clone(), lockup.clone(), &rules_msg, &[])
+        .unwrap();
+    router
+        .execute_contract(user.clone(), Addr::unchecked(CW721), &msg, &[])
+        .unwrap_err();
+
+    // Collections instantiated from the allowed code ID are supported
+    let rules_msg = cw721_lockup::contract::ExecMsg::UpdateCollectionRules {
+        collection_rules: vec![CollectionRuleInput::CodeId(1)],
+    };
+    let err = router
+        .execute_contract(user.clone(), lockup.clone(), &rules_msg, &[])
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<StdError>().unwrap(),
+        StdError::generic_err("Unauthorized")
+    );
+    router
+        .execute_contract(admin.clone(), lockup.clone(), &rules_msg, &[])
+        .unwrap();
+    router
+        .execute_contract(user.clone(), Addr::unchecked(CW721), &msg, &[])
+        .unwrap();
+
+    // Collections instantiated by the allowed factory are supported
+    let factory_collection = router
+        .instantiate_contract(
+            1,
+            Addr::unchecked("factory"),
+            &cw721_base::msg::InstantiateMsg {
+                name: String::from("Factory Kids"),
+                symbol: String::from("FAC"),
+                minter: admin.to_string(),
+            },
+            &[],
+            "CW721",
+            None,
+        )
+        .unwrap();
+    let rules_msg = cw721_lockup::contract::ExecMsg::UpdateCollectionRules {
+        collection_rules: vec![CollectionRuleInput::Factory("factory".to_string())],
+    };
+    router
+        .execute_contract(admin.clone(), lockup.clone(), &rules_msg, &[])
+        .unwrap();
+
+    let mint_msg: Cw721ExecuteMsg<Empty, Empty> = Cw721ExecuteMsg::Mint {
+        token_id: "1".to_string(),
+        owner: user.to_string(),
+        token_uri: None,
+        extension: Empty {},
+    };
+    router
+        .execute_contract(admin, factory_collection.clone(), &mint_msg, &[])
+        .unwrap();
+    router
+        .execute_contract(user, factory_collection, &msg, &[])
+        .unwrap();
+
+    let query_msg = cw721_lockup::contract::QueryMsg::Config {};
+    let res: cw721_lockup::msg::ConfigResponse =
+        router.wrap().query_wasm_smart(lockup, &query_msg).unwrap();
+    assert!(res.collections.is_empty());
+    assert_eq!(
+        res.collection_rules,
+        vec![CollectionRule::Factory(Addr::unchecked("factory"))]
+    );
+}
+
+#[test]
+fn migrate_legacy_lockups() {
+    let mut router = setup_contracts();
+    let admin = Addr::unchecked(ADMIN);
+    let user = Addr::unchecked(USER);
+
+    // Set up a Cw721Lockup contract holding a lockup without a receipt
+    let legacy_id = router.store_code(contract_legacy_lockup());
+    let msg = cw721_lockup::contract::InstantiateMsg {
+        lockup_interval: Some(Timestamp::from_seconds(3600)),
+        collections: vec![CW721.to_string()],
+        collection_rules: None,
+    };
+    let lockup = router
+        .instantiate_contract(
+            legacy_id,
+            admin.clone(),
+            &msg,
+            &[],
+            "LOCKUP",
+            Some(ADMIN.to_string()),
+        )
+        .unwrap();
+
+    mint_cw721(&mut router, user.clone(), "1");
+    let msg: Cw721ExecuteMsg<Empty, Empty> = Cw721ExecuteMsg::TransferNft {
+        recipient: lockup.to_string(),
+        token_id: "1".to_string(),
+    };
+    router
+        .execute_contract(user.clone(), Addr::unchecked(CW721), &msg, &[])
+        .unwrap();
+
+    // Migrating issues a receipt for the lockup and keys it by the receipt
+    router
+        .migrate_contract(
+            admin,
+            lockup.clone(),
+            &cw721_lockup::contract::MigrateMsg {},
+            legacy_id,
+        )
+        .unwrap();
+
+    let msg = cw721_lockup::contract::QueryMsg::LockupByToken {
+        collection_address: CW721.to_string(),
+        token_id: "1".to_string(),
+    };
+    let res: cw721_lockup::storage::Lockup = router
+        .wrap()
+        .query_wasm_smart(lockup.clone(), &msg)
+        .unwrap();
+    assert_eq!(res.receipt_id, "1");
+    assert_eq!(res.owner, user);
+
+    let msg = cw721_lockup::contract::QueryMsg::LockupsByOwner {
+        owner: user.to_string(),
+    };
+    let res: cw721_lockup::msg::LockupsReponse = router
+        .wrap()
+        .query_wasm_smart(lockup.clone(), &msg)
+        .unwrap();
+    assert_eq!(res.lockups.len(), 1);
+
+    let msg = lockup_common::receipts::QueryMsg::OwnerOf {
+        token_id: "1".to_string(),
+        include_expired: None,
+    };
+    let res: cw721::OwnerOfResponse = router
+        .wrap()
+        .query_wasm_smart(lockup.clone(), &msg)
+        .unwrap();
+    assert_eq!(res.owner, USER);
+
+    // The user withdraws the NFT through the receipt
+    let msg = cw721_lockup::contract::ExecMsg::Withdraw {
+        collection_address: CW721.to_string(),
+        token_id: "1".to_string(),
+    };
+    router
+        .execute_contract(user.clone(), lockup.clone(), &msg, &[])
+        .unwrap();
+
+    let msg = cw721::Cw721QueryMsg::OwnerOf {
+        token_id: "1".to_string(),
+        include_expired: None,
+    };
+    let res: cw721::OwnerOfResponse = router.wrap().query_wasm_smart(CW721, &msg).unwrap();
+    assert_eq!(res.owner, USER);
+
+    let msg = cw721_lockup::contract::QueryMsg::Count {};
+    let res: cw721_lockup::msg::CountResponse =
+        router.wrap().query_wasm_smart(lockup, &msg).unwrap();
+    assert_eq!(res.count, 0);
+}
@@ -1,7 +1,13 @@
 #![cfg(test)]
 
-use cosmwasm_std::{coin, coins, Addr, BankMsg, Empty, StdError, Timestamp, Uint128};
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{
+    coin, coins, Addr, BankMsg, Binary, Decimal, DepsMut, Empty, Env, MessageInfo, Response,
+    StdError, StdResult, Timestamp, Uint128,
+};
+use cw20::{BalanceResponse, Cw20Coin, Cw20ExecuteMsg, Cw20QueryMsg};
 use cw_multi_test::{App, Contract, ContractWrapper, Executor};
+use cw_storage_plus::{Item, Map};
 
 pub fn contract_native_lockup() -> Box<dyn Contract<Empty>> {
     let contract = ContractWrapper::new(
@@ -30,11 +36,76 @@ pub fn contract_cw721_lockup() -> Box<dyn Contract<Empty>> {
     Box::new(contract)
 }
 
+pub fn contract_cw20() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        cw20_base::contract::execute,
+        cw20_base::contract::instantiate,
+        cw20_base::contract::query,
+    );
+    Box::new(contract)
+}
+
+pub fn contract_cw20_lockup() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        cw20_lockup::contract::entry_points::execute,
+        cw20_lockup::contract::entry_points::instantiate,
+        cw20_lockup::contract::entry_points::query,
+    );
+    Box::new(contract)
+}
+
 pub fn contract_stake() -> Box<dyn Contract<Empty>> {
     let contract = ContractWrapper::new(
         fixed_stake::contract::entry_points::execute,
         fixed_stake::contract::entry_points::instantiate,
         fixed_stake::contract::entry_points::query,
+    )
+    .with_migrate(fixed_stake::contract::migrate);
+    Box::new(contract)
+}
+
+// Fixed stake instantiation message before multiple rewards were supported
+#[cw_serde]
+struct BaselineInstantiateMsg {
+    lockup_contract: String,
+    distribution_interval: Timestamp,
+    reward_denom: String,
+    total_rewards: Uint128,
+    start_time: Timestamp,
+    end_time: Timestamp,
+}
+
+// Fixed stake instantiation leaving the storage of a contract that predates multiple
+// rewards, holding a claim under the legacy `(address, token_id)` key
+fn legacy_stake_instantiate(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    msg: BaselineInstantiateMsg,
+) -> StdResult<Response> {
+    Item::new("admin").save(deps.storage, &info.sender)?;
+    Item::new("lockup_contract").save(deps.storage, &Addr::unchecked(msg.lockup_contract))?;
+    Item::new("distribution_interval").save(deps.storage, &msg.distribution_interval)?;
+    Item::new("reward_denom").save(deps.storage, &msg.reward_denom)?;
+    Item::new("total_rewards").save(deps.storage, &msg.total_rewards)?;
+    Item::new("start_time").save(deps.storage, &msg.start_time)?;
+    Item::new("end_time").save(deps.storage, &msg.end_time)?;
+
+    let legacy_staking: Map<(Addr, String), fixed_stake::storage::Stake> = Map::new("staking");
+    legacy_staking.save(
+        deps.storage,
+        (Addr::unchecked(USER), String::from("")),
+        &fixed_stake::storage::Stake::new(Timestamp::from_seconds(3601)),
+    )?;
+
+    Ok(Response::new())
+}
+
+pub fn contract_legacy_stake() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        fixed_stake::contract::entry_points::execute,
+        legacy_stake_instantiate,
+        fixed_stake::contract::entry_points::query,
     );
     Box::new(contract)
 }
@@ -44,6 +115,12 @@ const CW721: &str = "contract0";
 const CW721_LOCKUP: &str = "contract1";
 const NATIVE_STAKE: &str = "contract1";
 const CW721_STAKE: &str = "contract2";
+const CW20: &str = "contract0";
+const CW20_LOCKUP: &str = "contract1";
+const CW20_STAKE: &str = "contract2";
+const CW20_REWARD: &str = "contract0";
+const CW20_REWARD_LOCKUP: &str = "contract1";
+const CW20_REWARD_STAKE: &str = "contract2";
 
 const ADMIN: &str = "admin";
 const USER: &str = "user";
@@ -51,7 +128,7 @@ const USER: &str = "user";
 fn setup_native_contracts() -> App {
     let admin = Addr::unchecked(ADMIN);
 
-    let init_funds = coins(200, "ustars");
+    let init_funds = coins(300, "ustars");
 
     let mut router = App::new(|router, _, storage| {
         router
@@ -76,24 +153,29 @@ fn setup_native_contracts() -> App {
     let msg = fixed_stake::contract::InstantiateMsg {
         lockup_contract: NATIVE_LOCKUP.to_string(),
         distribution_interval: Timestamp::from_seconds(3600),
-        reward_denom: "ustars".to_string(),
-        total_rewards: Uint128::from(100u128),
-        start_time: Timestamp::from_seconds(1),
-        end_time: Timestamp::from_seconds(36001),
+        rewards: vec![(
+            fixed_stake::storage::AssetInfoInput::Native("ustars".to_string()),
+            Uint128::from(100u128),
+        )],
+        time: fixed_stake::storage::ProgramTime {
+            start_time: Timestamp::from_seconds(1),
+            end_time: Timestamp::from_seconds(36001),
+        },
+        options: None,
     };
 
     router
         .instantiate_contract(stake_id, admin.clone(), &msg, &[], "LOCKUP", None)
         .unwrap();
 
-    // Admin send 100 ustars to contract
+    // Admin funds the contract with 100 ustars
+    let msg = fixed_stake::contract::ExecMsg::Fund {};
     router
-        .execute(
+        .execute_contract(
             admin.clone(),
-            cosmwasm_std::CosmosMsg::Bank(BankMsg::Send {
-                to_address: NATIVE_STAKE.to_string(),
-                amount: vec![coin(100, "ustars")],
-            }),
+            Addr::unchecked(NATIVE_STAKE),
+            &msg,
+            &coins(100, "ustars"),
         )
         .unwrap();
 
@@ -130,6 +212,7 @@ fn setup_cw721_contracts() -> App {
     let msg = cw721_lockup::contract::InstantiateMsg {
         lockup_interval: Some(Timestamp::from_seconds(3600)),
         collections: vec![cw721_addr.to_string()],
+        collection_rules: None,
     };
 
     router
@@ -141,24 +224,105 @@ fn setup_cw721_contracts() -> App {
     let msg = fixed_stake::contract::InstantiateMsg {
         lockup_contract: CW721_LOCKUP.to_string(),
         distribution_interval: Timestamp::from_seconds(3600),
-        reward_denom: "ustars".to_string(),
-        total_rewards: Uint128::from(100u128),
-        start_time: Timestamp::from_seconds(1),
-        end_time: Timestamp::from_seconds(36001),
+        rewards: vec![(
+            fixed_stake::storage::AssetInfoInput::Native("ustars".to_string()),
+            Uint128::from(100u128),
+        )],
+        time: fixed_stake::storage::ProgramTime {
+            start_time: Timestamp::from_seconds(1),
+            end_time: Timestamp::from_seconds(36001),
+        },
+        options: None,
     };
 
     router
         .instantiate_contract(stake_id, admin.clone(), &msg, &[], "STAKE", None)
         .unwrap();
 
-    // Admin send 100 ustars to contract
+    // Admin funds the contract with 100 ustars
+    let msg = fixed_stake::contract::ExecMsg::Fund {};
     router
-        .execute(
+        .execute_contract(
             admin.clone(),
-            cosmwasm_std::CosmosMsg::Bank(BankMsg::Send {
-                to_address: CW721_STAKE.to_string(),
-                amount: vec![coin(100, "ustars")],
-            }),
+            Addr::unchecked(CW721_STAKE),
+            &msg,
+            &coins(100, "ustars"),
+        )
+        .unwrap();
+
+    let mut block = router.block_info();
+    block.time = Timestamp::from_seconds(1);
+    router.set_block(block);
+
+    router
+}
+
+fn setup_cw20_contracts() -> App {
+    let admin = Addr::unchecked(ADMIN);
+
+    let init_funds = coins(100, "ustars");
+
+    let mut router = App::new(|router, _, storage| {
+        router
+            .bank
+            .init_balance(storage, &admin, init_funds)
+            .unwrap();
+    });
+
+    // Set up Cw20Lockup contract
+    let cw20_id = router.store_code(contract_cw20());
+    let msg = cw20_base::msg::InstantiateMsg {
+        name: String::from("Stardust"),
+        symbol: String::from("STRDST"),
+        decimals: 6,
+        initial_balances: vec![Cw20Coin {
+            address: USER.to_string(),
+            amount: Uint128::from(100u128),
+        }],
+        mint: None,
+        marketing: None,
+    };
+    let cw20_addr = router
+        .instantiate_contract(cw20_id, admin.clone(), &msg, &[], "CW20", None)
+        .unwrap();
+    let lockup_id = router.store_code(contract_cw20_lockup());
+    let msg = cw20_lockup::contract::InstantiateMsg {
+        lockup_interval: Some(Timestamp::from_seconds(3600)),
+        token: cw20_addr.to_string(),
+    };
+
+    router
+        .instantiate_contract(lockup_id, admin.clone(), &msg, &[], "LOCKUP", None)
+        .unwrap();
+
+    // Set up FixedStake contract
+    let stake_id = router.store_code(contract_stake());
+    let msg = fixed_stake::contract::InstantiateMsg {
+        lockup_contract: CW20_LOCKUP.to_string(),
+        distribution_interval: Timestamp::from_seconds(3600),
+        rewards: vec![(
+            fixed_stake::storage::AssetInfoInput::Native("ustars".to_string()),
+            Uint128::from(100u128),
+        )],
+        time: fixed_stake::storage::ProgramTime {
+            start_time: Timestamp::from_seconds(1),
+            end_time: Timestamp::from_seconds(36001),
+        },
+        options: None,
+    };
+
+    router
+        .instantiate_contract(stake_id, admin.clone(), &msg, &[], "STAKE", None)
+        .unwrap();
+
+    // Admin funds the contract with 100 ustars
+    let msg = fixed_stake::contract::ExecMsg::Fund {};
+    router
+        .execute_contract(
+            admin.clone(),
+            Addr::unchecked(CW20_STAKE),
+            &msg,
+            &coins(100, "ustars"),
         )
         .unwrap();
 
@@ -169,6 +333,84 @@ fn setup_cw721_contracts() -> App {
     router
 }
 
+// Native lockup paying out CW20 rewards
+fn setup_cw20_reward_contracts() -> App {
+    let admin = Addr::unchecked(ADMIN);
+
+    let init_funds = coins(100, "ustars");
+
+    let mut router = App::new(|router, _, storage| {
+        router
+            .bank
+            .init_balance(storage, &admin, init_funds)
+            .unwrap();
+    });
+
+    // Set up CW20 reward token
+    let cw20_id = router.store_code(contract_cw20());
+    let msg = cw20_base::msg::InstantiateMsg {
+        name: String::from("Stardust"),
+        symbol: String::from("STRDST"),
+        decimals: 6,
+        initial_balances: vec![Cw20Coin {
+            address: ADMIN.to_string(),
+            amount: Uint128::from(200u128),
+        }],
+        mint: None,
+        marketing: None,
+    };
+    router
+        .instantiate_contract(cw20_id, admin.clone(), &msg, &[], "CW20", None)
+        .unwrap();
+
+    // Set up NativeLockup contract
+    let lockup_id = router.store_code(contract_native_lockup());
+    let msg = native_lockup::contract::InstantiateMsg {
+        lockup_interval: Some(Timestamp::from_seconds(3600)),
+        token: "ustars".to_string(),
+    };
+
+    router
+        .instantiate_contract(lockup_id, admin.clone(), &msg, &[], "NATIVE_LOCKUP", None)
+        .unwrap();
+
+    // Set up FixedStake contract
+    let stake_id = router.store_code(contract_stake());
+    let msg = fixed_stake::contract::InstantiateMsg {
+        lockup_contract: CW20_REWARD_LOCKUP.to_string(),
+        distribution_interval: Timestamp::from_seconds(3600),
+        rewards: vec![(
+            fixed_stake::storage::AssetInfoInput::Cw20(CW20_REWARD.to_string()),
+            Uint128::from(100u128),
+        )],
+        time: fixed_stake::storage::ProgramTime {
+            start_time: Timestamp::from_seconds(1),
+            end_time: Timestamp::from_seconds(36001),
+        },
+        options: None,
+    };
+
+    router
+        .instantiate_contract(stake_id, admin.clone(), &msg, &[], "STAKE", None)
+        .unwrap();
+
+    // Admin funds the contract with 100 tokens
+    let msg = Cw20ExecuteMsg::Send {
+        contract: CW20_REWARD_STAKE.to_string(),
+        amount: Uint128::from(100u128),
+        msg: Binary::default(),
+    };
+    router
+        .execute_contract(admin.clone(), Addr::unchecked(CW20_REWARD), &msg, &[])
+        .unwrap();
+
+    let mut block = router.block_info();
+    block.time = Timestamp::from_seconds(1);
+    router.set_block(block);
+
+    router
+}
+
 // Update block time
 fn add_block_time(router: &mut App, seconds: u64) {
     let mut block = router.block_info();
@@ -186,6 +428,29 @@ fn proper_cw721_initialization() {
     setup_cw721_contracts();
 }
 
+#[test]
+fn proper_cw20_initialization() {
+    setup_cw20_contracts();
+}
+
+#[test]
+fn proper_cw20_reward_initialization() {
+    let router = setup_cw20_reward_contracts();
+
+    let msg = fixed_stake::contract::QueryMsg::Config {};
+    let res: fixed_stake::msg::ConfigResponse = router
+        .wrap()
+        .query_wasm_smart(CW20_REWARD_STAKE, &msg)
+        .unwrap();
+    assert_eq!(
+        res.rewards,
+        vec![(
+            fixed_stake::storage::AssetInfo::Cw20(Addr::unchecked(CW20_REWARD)),
+            Uint128::from(100u128)
+        )]
+    );
+}
+
 // Mint a CW721 NFT to an address
 fn mint_cw721(router: &mut App, addr: Addr, token_id: &str) {
     let msg: cw721_base::ExecuteMsg<Empty, Empty> = cw721_base::ExecuteMsg::Mint {
@@ -245,7 +510,9 @@ fn native_deposit_and_claim() {
 
     // User claims rewards before they are available
     let msg = fixed_stake::contract::ExecMsg::ClaimRewards {
-        of: (USER.to_string(), String::from("")),
+        of: fixed_stake::storage::Position::Native {
+            address: USER.to_string(),
+        },
     };
     let err = router
         .execute_contract(user.clone(), Addr::unchecked(NATIVE_STAKE), &msg, &[])
@@ -286,7 +553,10 @@ fn cw721_deposit_and_claim() {
 
     // User claims rewards before they are available
     let msg = fixed_stake::contract::ExecMsg::ClaimRewards {
-        of: (CW721.to_string(), token_id.to_string()),
+        of: fixed_stake::storage::Position::Nft {
+            collection: CW721.to_string(),
+            token_id: token_id.to_string(),
+        },
     };
     let err = router
         .execute_contract(user.clone(), Addr::unchecked(CW721_STAKE), &msg, &[])
@@ -309,3 +579,1613 @@ fn cw721_deposit_and_claim() {
     let balance = router.wrap().query_balance(USER, "ustars").unwrap();
     assert_eq!(balance, coin(10u128, "ustars"));
 }
+
+#[test]
+fn native_transfer_lockup_migrates_claim() {
+    let mut router = setup_native_contracts();
+    let admin = Addr::unchecked(ADMIN);
+    let user = Addr::unchecked(USER);
+    let recipient = Addr::unchecked("recipient");
+
+    // Link the stake contract to the lockup contract
+    let msg = native_lockup::contract::ExecMsg::UpdateStakeContracts {
+        stake_contracts: vec![NATIVE_STAKE.to_string()],
+    };
+    router
+        .execute_contract(admin.clone(), Addr::unchecked(NATIVE_LOCKUP), &msg, &[])
+        .unwrap();
+
+    // Only the lockup contract can migrate claims
+    let msg = fixed_stake::contract::ExecMsg::MigrateClaim {
+        from: (USER.to_string(), String::from("")),
+        to: (ADMIN.to_string(), String::from("")),
+    };
+    let err = router
+        .execute_contract(admin.clone(), Addr::unchecked(NATIVE_STAKE), &msg, &[])
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<StdError>().unwrap(),
+        StdError::generic_err("Unauthorized")
+    );
+
+    // Admin sends 100 ustars to user
+    router
+        .execute(
+            admin.clone(),
+            cosmwasm_std::CosmosMsg::Bank(BankMsg::Send {
+                to_address: user.to_string(),
+                amount: vec![coin(100, "ustars")],
+            }),
+        )
+        .unwrap();
+
+    // User deposits 100 ustars
+    let msg = native_lockup::contract::ExecMsg::Deposit {};
+    router
+        .execute_contract(
+            user.clone(),
+            Addr::unchecked(NATIVE_LOCKUP),
+            &msg,
+            &coins(100, "ustars"),
+        )
+        .unwrap();
+
+    // Time advances by 3700 seconds, user claims rewards
+    add_block_time(&mut router, 3700);
+    let msg = fixed_stake::contract::ExecMsg::ClaimRewards {
+        of: fixed_stake::storage::Position::Native {
+            address: USER.to_string(),
+        },
+    };
+    router
+        .execute_contract(user.clone(), Addr::unchecked(NATIVE_STAKE), &msg, &[])
+        .unwrap();
+
+    let query_msg = fixed_stake::contract::QueryMsg::QueryLastClaim {
+        of: fixed_stake::storage::Position::Native {
+            address: USER.to_string(),
+        },
+    };
+    let last_claim: Timestamp = router
+        .wrap()
+        .query_wasm_smart(NATIVE_STAKE, &query_msg)
+        .unwrap();
+
+    // User transfers the lockup to recipient
+    let msg = native_lockup::contract::ExecMsg::TransferLockup {
+        recipient: recipient.to_string(),
+    };
+    router
+        .execute_contract(user.clone(), Addr::unchecked(NATIVE_LOCKUP), &msg, &[])
+        .unwrap();
+
+    // The last claim moved along with the lockup
+    let query_msg = fixed_stake::contract::QueryMsg::QueryLastClaim {
+        of: fixed_stake::storage::Position::Native {
+            address: recipient.to_string(),
+        },
+    };
+    let res: Timestamp = router
+        .wrap()
+        .query_wasm_smart(NATIVE_STAKE, &query_msg)
+        .unwrap();
+    assert_eq!(res, last_claim);
+
+    // Recipient cannot claim again within the same interval
+    let msg = fixed_stake::contract::ExecMsg::ClaimRewards {
+        of: fixed_stake::storage::Position::Native {
+            address: recipient.to_string(),
+        },
+    };
+    let err = router
+        .execute_contract(recipient.clone(), Addr::unchecked(NATIVE_STAKE), &msg, &[])
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<StdError>().unwrap(),
+        StdError::generic_err("Distribution interval not reached")
+    );
+
+    // Time advances by 3600 seconds, recipient claims rewards
+    add_block_time(&mut router, 3600);
+    router
+        .execute_contract(recipient.clone(), Addr::unchecked(NATIVE_STAKE), &msg, &[])
+        .unwrap();
+
+    let balance = router.wrap().query_balance(&recipient, "ustars").unwrap();
+    assert_eq!(balance, coin(10u128, "ustars"));
+}
+
+#[test]
+fn cw721_claim_follows_receipt() {
+    let mut router = setup_cw721_contracts();
+    let user = Addr::unchecked(USER);
+    let recipient = Addr::unchecked("recipient");
+
+    let token_id = "1";
+
+    mint_cw721(&mut router, user.clone(), "1");
+    send_cw721(
+        &mut router,
+        user.clone(),
+        Addr::unchecked(CW721_LOCKUP),
+        token_id,
+    );
+
+    let query_msg = cw721_lockup::contract::QueryMsg::LockupByToken {
+        collection_address: CW721.to_string(),
+        token_id: token_id.to_string(),
+    };
+    let lockup: cw721_lockup::storage::Lockup = router
+        .wrap()
+        .query_wasm_smart(CW721_LOCKUP, &query_msg)
+        .unwrap();
+
+    // User transfers the lockup receipt to recipient
+    let msg = lockup_common::receipts::ExecMsg::TransferNft {
+        recipient: recipient.to_string(),
+        token_id: lockup.receipt_id,
+    };
+    router
+        .execute_contract(user.clone(), Addr::unchecked(CW721_LOCKUP), &msg, &[])
+        .unwrap();
+
+    add_block_time(&mut router, 3700);
+
+    // User no longer holds the receipt and cannot claim
+    let msg = fixed_stake::contract::ExecMsg::ClaimRewards {
+        of: fixed_stake::storage::Position::Nft {
+            collection: CW721.to_string(),
+            token_id: token_id.to_string(),
+        },
+    };
+    let err = router
+        .execute_contract(user.clone(), Addr::unchecked(CW721_STAKE), &msg, &[])
+        .unwrap_err();
+
+    assert_eq!(
+        err.downcast::<StdError>().unwrap(),
+        StdError::generic_err("Unauthorized")
+    );
+
+    // Recipient claims rewards
+    router
+        .execute_contract(recipient.clone(), Addr::unchecked(CW721_STAKE), &msg, &[])
+        .unwrap();
+
+    let balance = router.wrap().query_balance(&recipient, "ustars").unwrap();
+    assert_eq!(balance, coin(10u128, "ustars"));
+}
+
+#[test]
+fn cw20_deposit_and_claim() {
+    let mut router = setup_cw20_contracts();
+    let user = Addr::unchecked(USER);
+
+    // User deposits 100 tokens
+    let msg = Cw20ExecuteMsg::Send {
+        contract: CW20_LOCKUP.to_string(),
+        amount: Uint128::from(100u128),
+        msg: Binary::default(),
+    };
+    router
+        .execute_contract(user.clone(), Addr::unchecked(CW20), &msg, &[])
+        .unwrap();
+
+    // User claims rewards before they are available
+    let msg = fixed_stake::contract::ExecMsg::ClaimRewards {
+        of: fixed_stake::storage::Position::Cw20 {
+            address: USER.to_string(),
+        },
+    };
+    let err = router
+        .execute_contract(user.clone(), Addr::unchecked(CW20_STAKE), &msg, &[])
+        .unwrap_err();
+
+    assert_eq!(
+        err.downcast::<StdError>().unwrap(),
+        StdError::generic_err("Reward distribution period has not started")
+    );
+
+    // Time advances by 3700 seconds
+    add_block_time(&mut router, 3700);
+
+    // User claims rewards
+    router
+        .execute_contract(user.clone(), Addr::unchecked(CW20_STAKE), &msg, &[])
+        .unwrap();
+
+    // Verify that the user has 10 stars
+    let balance = router.wrap().query_balance(USER, "ustars").unwrap();
+    assert_eq!(balance, coin(10u128, "ustars"));
+}
+
+#[test]
+fn cw20_reward_claim_and_withdraw() {
+    let mut router = setup_cw20_reward_contracts();
+    let admin = Addr::unchecked(ADMIN);
+    let user = Addr::unchecked(USER);
+
+    // User calls `Receive` directly without sending tokens
+    let msg = fixed_stake::contract::ExecMsg::Receive {
+        sender: user.to_string(),
+        amount: Uint128::from(100u128),
+    };
+    let err = router
+        .execute_contract(user.clone(), Addr::unchecked(CW20_REWARD_STAKE), &msg, &[])
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<StdError>().unwrap(),
+        StdError::generic_err("Unsupported token sent")
+    );
+
+    // Admin sends 100 ustars to user, who deposits them
+    router
+        .execute(
+            admin.clone(),
+            cosmwasm_std::CosmosMsg::Bank(BankMsg::Send {
+                to_address: user.to_string(),
+                amount: vec![coin(100, "ustars")],
+            }),
+        )
+        .unwrap();
+
+    let msg = native_lockup::contract::ExecMsg::Deposit {};
+    router
+        .execute_contract(
+            user.clone(),
+            Addr::unchecked(CW20_REWARD_LOCKUP),
+            &msg,
+            &coins(100, "ustars"),
+        )
+        .unwrap();
+
+    // Time advances by 3700 seconds
+    add_block_time(&mut router, 3700);
+
+    // User claims rewards
+    let msg = fixed_stake::contract::ExecMsg::ClaimRewards {
+        of: fixed_stake::storage::Position::Native {
+            address: USER.to_string(),
+        },
+    };
+    router
+        .execute_contract(user.clone(), Addr::unchecked(CW20_REWARD_STAKE), &msg, &[])
+        .unwrap();
+
+    // Verify that the user has 10 tokens
+    let query_msg = Cw20QueryMsg::Balance {
+        address: user.to_string(),
+    };
+    let res: BalanceResponse = router
+        .wrap()
+        .query_wasm_smart(CW20_REWARD, &query_msg)
+        .unwrap();
+    assert_eq!(res.balance, Uint128::from(10u128));
+
+    // Admin withdraws the remaining rewards after the end time
+    add_block_time(&mut router, 36001);
+
+    let msg = fixed_stake::contract::ExecMsg::WithdrawExcessBalance {};
+    router
+        .execute_contract(admin.clone(), Addr::unchecked(CW20_REWARD_STAKE), &msg, &[])
+        .unwrap();
+
+    let query_msg = Cw20QueryMsg::Balance {
+        address: admin.to_string(),
+    };
+    let res: BalanceResponse = router
+        .wrap()
+        .query_wasm_smart(CW20_REWARD, &query_msg)
+        .unwrap();
+    assert_eq!(res.balance, Uint128::from(190u128));
+}
+
+#[test]
+fn native_multi_denom_claim() {
+    let admin = Addr::unchecked(ADMIN);
+    let user = Addr::unchecked(USER);
+
+    let init_funds = vec![coin(200, "uatom"), coin(200, "ustars")];
+
+    let mut router = App::new(|router, _, storage| {
+        router
+            .bank
+            .init_balance(storage, &admin, init_funds)
+            .unwrap();
+    });
+
+    // Set up NativeLockup contract
+    let lockup_id = router.store_code(contract_native_lockup());
+    let msg = native_lockup::contract::InstantiateMsg {
+        lockup_interval: Some(Timestamp::from_seconds(3600)),
+        token: "ustars".to_string(),
+    };
+    router
+        .instantiate_contract(lockup_id, admin.clone(), &msg, &[], "NATIVE_LOCKUP", None)
+        .unwrap();
+
+    // Duplicate reward assets are rejected
+    let stake_id = router.store_code(contract_stake());
+    let mut msg = fixed_stake::contract::InstantiateMsg {
+        lockup_contract: NATIVE_LOCKUP.to_string(),
+        distribution_interval: Timestamp::from_seconds(3600),
+        rewards: vec![
+            (
+                fixed_stake::storage::AssetInfoInput::Native("ustars".to_string()),
+                Uint128::from(100u128),
+            ),
+            (
+                fixed_stake::storage::AssetInfoInput::Native("ustars".to_string()),
+                Uint128::from(50u128),
+            ),
+        ],
+        time: fixed_stake::storage::ProgramTime {
+            start_time: Timestamp::from_seconds(1),
+            end_time: Timestamp::from_seconds(36001),
+        },
+        options: None,
+    };
+    let err = router
+        .instantiate_contract(stake_id, admin.clone(), &msg, &[], "STAKE", None)
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<StdError>().unwrap(),
+        StdError::generic_err("Duplicate reward asset")
+    );
+
+    // Set up FixedStake contract paying both $STARS and $ATOM
+    msg.rewards[1].0 = fixed_stake::storage::AssetInfoInput::Native("uatom".to_string());
+    router
+        .instantiate_contract(
+            stake_id,
+            admin.clone(),
+            &msg,
+            &[coin(50, "uatom"), coin(100, "ustars")],
+            "STAKE",
+            None,
+        )
+        .unwrap();
+
+    let mut block = router.block_info();
+    block.time = Timestamp::from_seconds(1);
+    router.set_block(block);
+
+    // User deposits 100 ustars
+    router
+        .execute(
+            admin.clone(),
+            cosmwasm_std::CosmosMsg::Bank(BankMsg::Send {
+                to_address: user.to_string(),
+                amount: vec![coin(100, "ustars")],
+            }),
+        )
+        .unwrap();
+    let msg = native_lockup::contract::ExecMsg::Deposit {};
+    router
+        .execute_contract(
+            user.clone(),
+            Addr::unchecked(NATIVE_LOCKUP),
+            &msg,
+            &coins(100, "ustars"),
+        )
+        .unwrap();
+
+    // Time advances by 3700 seconds
+    add_block_time(&mut router, 3700);
+
+    // User claims rewards in both denominations
+    let msg = fixed_stake::contract::ExecMsg::ClaimRewards {
+        of: fixed_stake::storage::Position::Native {
+            address: USER.to_string(),
+        },
+    };
+    router
+        .execute_contract(user.clone(), Addr::unchecked(NATIVE_STAKE), &msg, &[])
+        .unwrap();
+
+    let balance = router.wrap().query_balance(USER, "ustars").unwrap();
+    assert_eq!(balance, coin(10u128, "ustars"));
+    let balance = router.wrap().query_balance(USER, "uatom").unwrap();
+    assert_eq!(balance, coin(5u128, "uatom"));
+
+    // Admin sweeps the remaining balance of each denomination
+    add_block_time(&mut router, 36001);
+
+    let msg = fixed_stake::contract::ExecMsg::WithdrawExcessBalance {};
+    router
+        .execute_contract(admin.clone(), Addr::unchecked(NATIVE_STAKE), &msg, &[])
+        .unwrap();
+
+    let balance = router.wrap().query_balance(ADMIN, "ustars").unwrap();
+    assert_eq!(balance, coin(90u128, "ustars"));
+    let balance = router.wrap().query_balance(ADMIN, "uatom").unwrap();
+    assert_eq!(balance, coin(195u128, "uatom"));
+}
+
+#[test]
+fn claim_requires_full_funding() {
+    let mut router = setup_native_contracts();
+    let admin = Addr::unchecked(ADMIN);
+    let user = Addr::unchecked(USER);
+
+    // Set up a second FixedStake contract, funded with 40 ustars on instantiation
+    let msg = fixed_stake::contract::InstantiateMsg {
+        lockup_contract: NATIVE_LOCKUP.to_string(),
+        distribution_interval: Timestamp::from_seconds(3600),
+        rewards: vec![(
+            fixed_stake::storage::AssetInfoInput::Native("ustars".to_string()),
+            Uint128::from(100u128),
+        )],
+        time: fixed_stake::storage::ProgramTime {
+            start_time: Timestamp::from_seconds(1),
+            end_time: Timestamp::from_seconds(36001),
+        },
+        options: None,
+    };
+    let stake = router
+        .instantiate_contract(2, admin.clone(), &msg, &coins(40, "ustars"), "STAKE", None)
+        .unwrap();
+
+    // Funding requires sending reward tokens
+    let msg = fixed_stake::contract::ExecMsg::Fund {};
+    let err = router
+        .execute_contract(admin.clone(), stake.clone(), &msg, &[])
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<StdError>().unwrap(),
+        StdError::generic_err("No funds sent")
+    );
+
+    // User deposits 50 ustars
+    router
+        .execute(
+            admin.clone(),
+            cosmwasm_std::CosmosMsg::Bank(BankMsg::Send {
+                to_address: user.to_string(),
+                amount: vec![coin(50, "ustars")],
+            }),
+        )
+        .unwrap();
+    let msg = native_lockup::contract::ExecMsg::Deposit {};
+    router
+        .execute_contract(
+            user.clone(),
+            Addr::unchecked(NATIVE_LOCKUP),
+            &msg,
+            &coins(50, "ustars"),
+        )
+        .unwrap();
+
+    add_block_time(&mut router, 3700);
+
+    // Claims are refused while the program is underfunded
+    let msg = fixed_stake::contract::ExecMsg::ClaimRewards {
+        of: fixed_stake::storage::Position::Native {
+            address: USER.to_string(),
+        },
+    };
+    let err = router
+        .execute_contract(user.clone(), stake.clone(), &msg, &[])
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<StdError>().unwrap(),
+        StdError::generic_err("Reward program is not fully funded")
+    );
+
+    // Admin tops up the remaining 60 ustars
+    let fund_msg = fixed_stake::contract::ExecMsg::Fund {};
+    router
+        .execute_contract(
+            admin.clone(),
+            stake.clone(),
+            &fund_msg,
+            &coins(60, "ustars"),
+        )
+        .unwrap();
+
+    router
+        .execute_contract(user.clone(), stake.clone(), &msg, &[])
+        .unwrap();
+
+    let balance = router.wrap().query_balance(USER, "ustars").unwrap();
+    assert_eq!(balance, coin(10u128, "ustars"));
+
+    // Query the funding ledger
+    let query_msg = fixed_stake::contract::QueryMsg::Funding {};
+    let res: fixed_stake::msg::FundingResponse =
+        router.wrap().query_wasm_smart(stake, &query_msg).unwrap();
+    let asset = fixed_stake::storage::AssetInfo::Native("ustars".to_string());
+    assert_eq!(
+        res.funded_amount,
+        vec![(asset.clone(), Uint128::from(100u128))]
+    );
+    assert_eq!(res.distributed_amount, vec![(asset, Uint128::from(10u128))]);
+    assert!(res.fully_funded);
+}
+
+// User locks ustars in the native lockup
+fn native_deposit(router: &mut App, amount: u128) {
+    let user = Addr::unchecked(USER);
+
+    router
+        .execute(
+            Addr::unchecked(ADMIN),
+            cosmwasm_std::CosmosMsg::Bank(BankMsg::Send {
+                to_address: user.to_string(),
+                amount: vec![coin(amount, "ustars")],
+            }),
+        )
+        .unwrap();
+
+    let msg = native_lockup::contract::ExecMsg::Deposit {};
+    router
+        .execute_contract(
+            user,
+            Addr::unchecked(NATIVE_LOCKUP),
+            &msg,
+            &coins(amount, "ustars"),
+        )
+        .unwrap();
+}
+
+#[test]
+fn native_extend_program() {
+    let mut router = setup_native_contracts();
+    let admin = Addr::unchecked(ADMIN);
+    let user = Addr::unchecked(USER);
+
+    native_deposit(&mut router, 100);
+
+    // Time advances by one and a half intervals
+    add_block_time(&mut router, 5400);
+
+    // Only the admin can extend the program
+    let msg = fixed_stake::contract::ExecMsg::ExtendProgram {
+        new_end_time: Timestamp::from_seconds(72001),
+        additional_rewards: vec![(
+            fixed_stake::storage::AssetInfoInput::Native("uatom".to_string()),
+            Uint128::from(100u128),
+        )],
+    };
+    let err = router
+        .execute_contract(user.clone(), Addr::unchecked(NATIVE_STAKE), &msg, &[])
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<StdError>().unwrap(),
+        StdError::generic_err("Unauthorized")
+    );
+
+    // Additional rewards must use an existing reward asset
+    let err = router
+        .execute_contract(admin.clone(), Addr::unchecked(NATIVE_STAKE), &msg, &[])
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<StdError>().unwrap(),
+        StdError::generic_err("Unknown reward asset")
+    );
+
+    // Additional rewards must be sent along with the extension
+    let msg = fixed_stake::contract::ExecMsg::ExtendProgram {
+        new_end_time: Timestamp::from_seconds(72001),
+        additional_rewards: vec![(
+            fixed_stake::storage::AssetInfoInput::Native("ustars".to_string()),
+            Uint128::from(100u128),
+        )],
+    };
+    let err = router
+        .execute_contract(
+            admin.clone(),
+            Addr::unchecked(NATIVE_STAKE),
+            &msg,
+            &coins(50, "ustars"),
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<StdError>().unwrap(),
+        StdError::generic_err("Additional rewards must be funded")
+    );
+
+    // Admin doubles the program length and funds 100 more ustars
+    router
+        .execute_contract(
+            admin.clone(),
+            Addr::unchecked(NATIVE_STAKE),
+            &msg,
+            &coins(100, "ustars"),
+        )
+        .unwrap();
+
+    // The first 5400 seconds are settled at the original rate
+    let query_msg = fixed_stake::contract::QueryMsg::Periods {};
+    let res: Vec<fixed_stake::storage::Period> = router
+        .wrap()
+        .query_wasm_smart(NATIVE_STAKE, &query_msg)
+        .unwrap();
+    let asset = fixed_stake::storage::AssetInfo::Native("ustars".to_string());
+    assert_eq!(res.len(), 2);
+    assert_eq!(res[0].end_time, Timestamp::from_seconds(5401));
+    assert_eq!(res[0].rewards, vec![(asset.clone(), Uint128::from(15u128))]);
+    assert_eq!(res[1].end_time, Timestamp::from_seconds(72001));
+    assert_eq!(res[1].rewards, vec![(asset, Uint128::from(185u128))]);
+
+    // One interval into the extension, the user is owed one and a half
+    // intervals of the original period and one of the extension
+    add_block_time(&mut router, 3600);
+
+    let msg = fixed_stake::contract::ExecMsg::ClaimRewards {
+        of: fixed_stake::storage::Position::Native {
+            address: USER.to_string(),
+        },
+    };
+    router
+        .execute_contract(user.clone(), Addr::unchecked(NATIVE_STAKE), &msg, &[])
+        .unwrap();
+
+    let balance = router.wrap().query_balance(USER, "ustars").unwrap();
+    assert_eq!(balance, coin(25u128, "ustars"));
+}
+
+#[test]
+fn native_update_interval() {
+    let mut router = setup_native_contracts();
+    let admin = Addr::unchecked(ADMIN);
+    let user = Addr::unchecked(USER);
+
+    native_deposit(&mut router, 100);
+
+    // Time advances by two intervals
+    add_block_time(&mut router, 7200);
+
+    // Admin halves the distribution interval
+    let msg = fixed_stake::contract::ExecMsg::UpdateInterval {
+        distribution_interval: Timestamp::from_seconds(1800),
+    };
+    router
+        .execute_contract(admin.clone(), Addr::unchecked(NATIVE_STAKE), &msg, &[])
+        .unwrap();
+
+    // After one shorter interval, the user is owed two intervals at the old rate
+    // and one at the new rate
+    add_block_time(&mut router, 1800);
+
+    let msg = fixed_stake::contract::ExecMsg::ClaimRewards {
+        of: fixed_stake::storage::Position::Native {
+            address: USER.to_string(),
+        },
+    };
+    router
+        .execute_contract(user.clone(), Addr::unchecked(NATIVE_STAKE), &msg, &[])
+        .unwrap();
+
+    let balance = router.wrap().query_balance(USER, "ustars").unwrap();
+    assert_eq!(balance, coin(25u128, "ustars"));
+
+    let query_msg = fixed_stake::contract::QueryMsg::Config {};
+    let res: fixed_stake::msg::ConfigResponse = router
+        .wrap()
+        .query_wasm_smart(NATIVE_STAKE, &query_msg)
+        .unwrap();
+    assert_eq!(res.distribution_interval, Timestamp::from_seconds(1800));
+}
+
+#[test]
+fn native_cancel_program() {
+    let mut router = setup_native_contracts();
+    let admin = Addr::unchecked(ADMIN);
+    let user = Addr::unchecked(USER);
+
+    native_deposit(&mut router, 100);
+
+    // Time advances by one and a half intervals
+    add_block_time(&mut router, 5400);
+
+    // Only the admin can cancel the program
+    let msg = fixed_stake::contract::ExecMsg::CancelProgram {};
+    let err = router
+        .execute_contract(user.clone(), Addr::unchecked(NATIVE_STAKE), &msg, &[])
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<StdError>().unwrap(),
+        StdError::generic_err("Unauthorized")
+    );
+
+    // Admin cancels and is refunded everything but the 15 ustars accrued so far
+    router
+        .execute_contract(admin.clone(), Addr::unchecked(NATIVE_STAKE), &msg, &[])
+        .unwrap();
+
+    let balance = router.wrap().query_balance(ADMIN, "ustars").unwrap();
+    assert_eq!(balance, coin(185u128, "ustars"));
+    let balance = router.wrap().query_balance(NATIVE_STAKE, "ustars").unwrap();
+    assert_eq!(balance, coin(15u128, "ustars"));
+
+    let query_msg = fixed_stake::contract::QueryMsg::Config {};
+    let res: fixed_stake::msg::ConfigResponse = router
+        .wrap()
+        .query_wasm_smart(NATIVE_STAKE, &query_msg)
+        .unwrap();
+    assert!(res.cancelled);
+    assert_eq!(res.end_time, Timestamp::from_seconds(5401));
+
+    // The program can no longer be cancelled or extended
+    let err = router
+        .execute_contract(admin.clone(), Addr::unchecked(NATIVE_STAKE), &msg, &[])
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<StdError>().unwrap(),
+        StdError::generic_err("Reward distribution period has ended")
+    );
+
+    // Reserved rewards cannot be swept by the admin
+    add_block_time(&mut router, 3600);
+
+    let msg = fixed_stake::contract::ExecMsg::WithdrawExcessBalance {};
+    router
+        .execute_contract(admin.clone(), Addr::unchecked(NATIVE_STAKE), &msg, &[])
+        .unwrap();
+    let balance = router.wrap().query_balance(NATIVE_STAKE, "ustars").unwrap();
+    assert_eq!(balance, coin(15u128, "ustars"));
+
+    // User claims the rewards accrued until cancellation
+    let msg = fixed_stake::contract::ExecMsg::ClaimRewards {
+        of: fixed_stake::storage::Position::Native {
+            address: USER.to_string(),
+        },
+    };
+    router
+        .execute_contract(user.clone(), Addr::unchecked(NATIVE_STAKE), &msg, &[])
+        .unwrap();
+
+    let balance = router.wrap().query_balance(USER, "ustars").unwrap();
+    assert_eq!(balance, coin(15u128, "ustars"));
+
+    // Nothing accrues after cancellation
+    add_block_time(&mut router, 3600);
+
+    router
+        .execute_contract(user.clone(), Addr::unchecked(NATIVE_STAKE), &msg, &[])
+        .unwrap();
+
+    let balance = router.wrap().query_balance(USER, "ustars").unwrap();
+    assert_eq!(balance, coin(15u128, "ustars"));
+}
+
+// Instantiate a FixedStake contract on the native lockup with the given emission
+fn instantiate_native_stake(router: &mut App, emission: fixed_stake::storage::Emission) -> Addr {
+    let msg = fixed_stake::contract::InstantiateMsg {
+        lockup_contract: NATIVE_LOCKUP.to_string(),
+        distribution_interval: Timestamp::from_seconds(3600),
+        rewards: vec![(
+            fixed_stake::storage::AssetInfoInput::Native("ustars".to_string()),
+            Uint128::from(100u128),
+        )],
+        time: fixed_stake::storage::ProgramTime {
+            start_time: Timestamp::from_seconds(1),
+            end_time: Timestamp::from_seconds(36001),
+        },
+        options: Some(fixed_stake::storage::InstantiateOptions {
+            emission: Some(emission),
+            ..Default::default()
+        }),
+    };
+
+    router
+        .instantiate_contract(2, Addr::unchecked(ADMIN), &msg, &[], "STAKE", None)
+        .unwrap()
+}
+
+// Query the rewards emitted during the interval containing `time`
+fn query_emission_at(router: &App, stake: &Addr, time: u64) -> Uint128 {
+    let msg = fixed_stake::contract::QueryMsg::EmissionAt {
+        time: Timestamp::from_seconds(time),
+    };
+    let res: Vec<(fixed_stake::storage::AssetInfo, Uint128)> =
+        router.wrap().query_wasm_smart(stake, &msg).unwrap();
+    res[0].1
+}
+
+#[test]
+fn emission_schedules() {
+    let mut router = setup_native_contracts();
+
+    // Flat emission distributes the same amount every interval
+    let query_msg = fixed_stake::contract::QueryMsg::EmissionAt {
+        time: Timestamp::from_seconds(7201),
+    };
+    let res: Vec<(fixed_stake::storage::AssetInfo, Uint128)> = router
+        .wrap()
+        .query_wasm_smart(NATIVE_STAKE, &query_msg)
+        .unwrap();
+    assert_eq!(res[0].1, Uint128::from(10u128));
+
+    // Nothing is emitted after the end time
+    let query_msg = fixed_stake::contract::QueryMsg::EmissionAt {
+        time: Timestamp::from_seconds(36001),
+    };
+    let res: Vec<(fixed_stake::storage::AssetInfo, Uint128)> = router
+        .wrap()
+        .query_wasm_smart(NATIVE_STAKE, &query_msg)
+        .unwrap();
+    assert_eq!(res[0].1, Uint128::zero());
+
+    // Linear decay: weights 10, 9, ..., 1 out of 55
+    let stake = instantiate_native_stake(
+        &mut router,
+        fixed_stake::storage::Emission::LinearDecay { intervals: 10 },
+    );
+    assert_eq!(query_emission_at(&router, &stake, 1), Uint128::from(18u128));
+    assert_eq!(
+        query_emission_at(&router, &stake, 3601),
+        Uint128::from(16u128)
+    );
+    assert_eq!(
+        query_emission_at(&router, &stake, 32401),
+        Uint128::from(1u128)
+    );
+
+    // Halving every two intervals: weights 1, 1, 0.5, 0.5, ... out of 3.875
+    let stake = instantiate_native_stake(
+        &mut router,
+        fixed_stake::storage::Emission::Halving { intervals: 2 },
+    );
+    assert_eq!(
+        query_emission_at(&router, &stake, 3601),
+        Uint128::from(25u128)
+    );
+    assert_eq!(
+        query_emission_at(&router, &stake, 7201),
+        Uint128::from(12u128)
+    );
+
+    // Explicit table: a boosted first interval, then a flat tail
+    let stake = instantiate_native_stake(
+        &mut router,
+        fixed_stake::storage::Emission::Table {
+            weights: vec![Decimal::percent(300), Decimal::one()],
+        },
+    );
+    assert_eq!(query_emission_at(&router, &stake, 1), Uint128::from(25u128));
+    assert_eq!(
+        query_emission_at(&router, &stake, 36000),
+        Uint128::from(8u128)
+    );
+
+    // Empty tables are rejected
+    let msg = fixed_stake::contract::InstantiateMsg {
+        lockup_contract: NATIVE_LOCKUP.to_string(),
+        distribution_interval: Timestamp::from_seconds(3600),
+        rewards: vec![(
+            fixed_stake::storage::AssetInfoInput::Native("ustars".to_string()),
+            Uint128::from(100u128),
+        )],
+        time: fixed_stake::storage::ProgramTime {
+            start_time: Timestamp::from_seconds(1),
+            end_time: Timestamp::from_seconds(36001),
+        },
+        options: Some(fixed_stake::storage::InstantiateOptions {
+            emission: Some(fixed_stake::storage::Emission::Table { weights: vec![] }),
+            ..Default::default()
+        }),
+    };
+    let err = router
+        .instantiate_contract(2, Addr::unchecked(ADMIN), &msg, &[], "STAKE", None)
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<StdError>().unwrap(),
+        StdError::generic_err("Emission table must not be empty")
+    );
+}
+
+#[test]
+fn claim_with_linear_decay() {
+    let mut router = setup_native_contracts();
+    let user = Addr::unchecked(USER);
+
+    let stake = instantiate_native_stake(
+        &mut router,
+        fixed_stake::storage::Emission::LinearDecay { intervals: 10 },
+    );
+
+    let msg = fixed_stake::contract::ExecMsg::Fund {};
+    router
+        .execute_contract(
+            Addr::unchecked(ADMIN),
+            stake.clone(),
+            &msg,
+            &coins(100, "ustars"),
+        )
+        .unwrap();
+
+    native_deposit(&mut router, 100);
+
+    // After two intervals, the user owns the whole emission of 10/55 + 9/55
+    add_block_time(&mut router, 7200);
+
+    let msg = fixed_stake::contract::ExecMsg::ClaimRewards {
+        of: fixed_stake::storage::Position::Native {
+            address: USER.to_string(),
+        },
+    };
+    router
+        .execute_contract(user.clone(), stake, &msg, &[])
+        .unwrap();
+
+    let balance = router.wrap().query_balance(USER, "ustars").unwrap();
+    assert_eq!(balance, coin(34u128, "ustars"));
+}
+
+#[test]
+fn partial_interval_carries_over() {
+    let mut router = setup_native_contracts();
+    let user = Addr::unchecked(USER);
+
+    native_deposit(&mut router, 100);
+
+    // User claims one and a half intervals in, and is paid a single interval
+    add_block_time(&mut router, 5400);
+
+    let msg = fixed_stake::contract::ExecMsg::ClaimRewards {
+        of: fixed_stake::storage::Position::Native {
+            address: USER.to_string(),
+        },
+    };
+    router
+        .execute_contract(user.clone(), Addr::unchecked(NATIVE_STAKE), &msg, &[])
+        .unwrap();
+
+    let balance = router.wrap().query_balance(USER, "ustars").unwrap();
+    assert_eq!(balance, coin(10u128, "ustars"));
+
+    // The unpaid half interval counts towards the next claim
+    let query_msg = fixed_stake::contract::QueryMsg::QueryLastClaim {
+        of: fixed_stake::storage::Position::Native {
+            address: USER.to_string(),
+        },
+    };
+    let res: Timestamp = router
+        .wrap()
+        .query_wasm_smart(NATIVE_STAKE, &query_msg)
+        .unwrap();
+    assert_eq!(res, Timestamp::from_seconds(3601));
+
+    add_block_time(&mut router, 1800);
+
+    router
+        .execute_contract(user.clone(), Addr::unchecked(NATIVE_STAKE), &msg, &[])
+        .unwrap();
+
+    let balance = router.wrap().query_balance(USER, "ustars").unwrap();
+    assert_eq!(balance, coin(20u128, "ustars"));
+}
+
+#[test]
+fn streaming_claims() {
+    let mut router = setup_native_contracts();
+    let admin = Addr::unchecked(ADMIN);
+    let user = Addr::unchecked(USER);
+
+    // Set up a streaming FixedStake contract
+    let msg = fixed_stake::contract::InstantiateMsg {
+        lockup_contract: NATIVE_LOCKUP.to_string(),
+        distribution_interval: Timestamp::from_seconds(3600),
+        rewards: vec![(
+            fixed_stake::storage::AssetInfoInput::Native("ustars".to_string()),
+            Uint128::from(100u128),
+        )],
+        time: fixed_stake::storage::ProgramTime {
+            start_time: Timestamp::from_seconds(1),
+            end_time: Timestamp::from_seconds(36001),
+        },
+        options: Some(fixed_stake::storage::InstantiateOptions {
+            streaming: Some(true),
+            ..Default::default()
+        }),
+    };
+    let stake = router
+        .instantiate_contract(2, admin, &msg, &coins(100, "ustars"), "STAKE", None)
+        .unwrap();
+
+    native_deposit(&mut router, 100);
+
+    // Rewards accrue before a whole interval has passed
+    add_block_time(&mut router, 1800);
+
+    let msg = fixed_stake::contract::ExecMsg::ClaimRewards {
+        of: fixed_stake::storage::Position::Native {
+            address: USER.to_string(),
+        },
+    };
+    router
+        .execute_contract(user.clone(), stake.clone(), &msg, &[])
+        .unwrap();
+
+    let balance = router.wrap().query_balance(USER, "ustars").unwrap();
+    assert_eq!(balance, coin(5u128, "ustars"));
+
+    add_block_time(&mut router, 2700);
+
+    router
+        .execute_contract(user.clone(), stake.clone(), &msg, &[])
+        .unwrap();
+
+    let balance = router.wrap().query_balance(USER, "ustars").unwrap();
+    assert_eq!(balance, coin(12u128, "ustars"));
+
+    let query_msg = fixed_stake::contract::QueryMsg::QueryLastClaim {
+        of: fixed_stake::storage::Position::Native {
+            address: USER.to_string(),
+        },
+    };
+    let res: Timestamp = router.wrap().query_wasm_smart(stake, &query_msg).unwrap();
+    assert_eq!(res, Timestamp::from_seconds(4501));
+}
+
+#[test]
+fn vested_claims() {
+    let mut router = setup_native_contracts();
+    let admin = Addr::unchecked(ADMIN);
+    let user = Addr::unchecked(USER);
+
+    // Set up a streaming FixedStake contract vesting over an hour with a 30 minute cliff
+    let msg = fixed_stake::contract::InstantiateMsg {
+        lockup_contract: NATIVE_LOCKUP.to_string(),
+        distribution_interval: Timestamp::from_seconds(3600),
+        rewards: vec![(
+            fixed_stake::storage::AssetInfoInput::Native("ustars".to_string()),
+            Uint128::from(100u128),
+        )],
+        time: fixed_stake::storage::ProgramTime {
+            start_time: Timestamp::from_seconds(1),
+            end_time: Timestamp::from_seconds(36001),
+        },
+        options: Some(fixed_stake::storage::InstantiateOptions {
+            streaming: Some(true),
+            vesting: Some(fixed_stake::storage::VestingConfig {
+                duration: Timestamp::from_seconds(3600),
+                cliff: Timestamp::from_seconds(1800),
+            }),
+            ..Default::default()
+        }),
+    };
+    let stake = router
+        .instantiate_contract(2, admin, &msg, &coins(100, "ustars"), "STAKE", None)
+        .unwrap();
+
+    native_deposit(&mut router, 100);
+    add_block_time(&mut router, 1800);
+
+    // Claimed rewards go into the vesting ledger instead of the user's wallet
+    let msg = fixed_stake::contract::ExecMsg::ClaimRewards {
+        of: fixed_stake::storage::Position::Native {
+            address: USER.to_string(),
+        },
+    };
+    router
+        .execute_contract(user.clone(), stake.clone(), &msg, &[])
+        .unwrap();
+
+    let balance = router.wrap().query_balance(USER, "ustars").unwrap();
+    assert_eq!(balance, coin(0u128, "ustars"));
+
+    // Nothing is released before the cliff
+    add_block_time(&mut router, 900);
+
+    let msg = fixed_stake::contract::ExecMsg::ClaimVested {};
+    let err = router
+        .execute_contract(user.clone(), stake.clone(), &msg, &[])
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<StdError>().unwrap(),
+        StdError::generic_err("No vested rewards to claim")
+    );
+
+    // Half of the tranche has vested at the cliff
+    add_block_time(&mut router, 900);
+
+    router
+        .execute_contract(user.clone(), stake.clone(), &msg, &[])
+        .unwrap();
+
+    let balance = router.wrap().query_balance(USER, "ustars").unwrap();
+    assert_eq!(balance, coin(2u128, "ustars"));
+
+    let query_msg = fixed_stake::contract::QueryMsg::VestingSchedule {
+        address: USER.to_string(),
+    };
+    let res: fixed_stake::msg::VestingScheduleResponse = router
+        .wrap()
+        .query_wasm_smart(stake.clone(), &query_msg)
+        .unwrap();
+    assert_eq!(res.tranches.len(), 1);
+    assert_eq!(res.tranches[0].released[0].1, Uint128::from(2u128));
+    assert_eq!(res.releasable[0].1, Uint128::zero());
+
+    // The rest is released once the vesting duration has passed
+    add_block_time(&mut router, 3600);
+
+    router
+        .execute_contract(user.clone(), stake.clone(), &msg, &[])
+        .unwrap();
+
+    let balance = router.wrap().query_balance(USER, "ustars").unwrap();
+    assert_eq!(balance, coin(5u128, "ustars"));
+
+    let res: fixed_stake::msg::VestingScheduleResponse =
+        router.wrap().query_wasm_smart(stake, &query_msg).unwrap();
+    assert!(res.tranches.is_empty());
+}
+
+#[test]
+fn vested_claims_share_tranches() {
+    let mut router = setup_native_contracts();
+    let admin = Addr::unchecked(ADMIN);
+    let user = Addr::unchecked(USER);
+
+    // Set up a streaming FixedStake contract emitting 1 ustars per second, vesting over an hour
+    let msg = fixed_stake::contract::InstantiateMsg {
+        lockup_contract: NATIVE_LOCKUP.to_string(),
+        distribution_interval: Timestamp::from_seconds(3600),
+        rewards: vec![(
+            fixed_stake::storage::AssetInfoInput::Native("ustars".to_string()),
+            Uint128::from(36000u128),
+        )],
+        time: fixed_stake::storage::ProgramTime {
+            start_time: Timestamp::from_seconds(1),
+            end_time: Timestamp::from_seconds(36001),
+        },
+        options: Some(fixed_stake::storage::InstantiateOptions {
+            streaming: Some(true),
+            vesting: Some(fixed_stake::storage::VestingConfig {
+                duration: Timestamp::from_seconds(3600),
+                cliff: Timestamp::from_seconds(0),
+            }),
+            ..Default::default()
+        }),
+    };
+    router
+        .init_modules(|router, _, storage| {
+            router
+                .bank
+                .init_balance(storage, &admin, coins(36100, "ustars"))
+        })
+        .unwrap();
+    let stake = router
+        .instantiate_contract(2, admin, &msg, &coins(36000, "ustars"), "STAKE", None)
+        .unwrap();
+
+    native_deposit(&mut router, 100);
+
+    // Claim every minute for three hours
+    let msg = fixed_stake::contract::ExecMsg::ClaimRewards {
+        of: fixed_stake::storage::Position::Native {
+            address: USER.to_string(),
+        },
+    };
+    for _ in 0..180 {
+        add_block_time(&mut router, 60);
+        router
+            .execute_contract(user.clone(), stake.clone(), &msg, &[])
+            .unwrap();
+    }
+
+    // Claims within a bucket share a tranche and vested tranches are folded into one
+    let query_msg = fixed_stake::contract::QueryMsg::VestingSchedule {
+        address: USER.to_string(),
+    };
+    let res: fixed_stake::msg::VestingScheduleResponse = router
+        .wrap()
+        .query_wasm_smart(stake.clone(), &query_msg)
+        .unwrap();
+    assert!(res.tranches.len() as u64 <= fixed_stake::storage::VESTING_BUCKETS + 2);
+
+    let vesting: u128 = res
+        .tranches
+        .iter()
+        .map(|tranche| tranche.amounts[0].1.u128())
+        .sum();
+    assert!(vesting > 10000);
+
+    // Everything claimed is released once vested
+    add_block_time(&mut router, 3600);
+
+    let msg = fixed_stake::contract::ExecMsg::ClaimVested {};
+    router
+        .execute_contract(user.clone(), stake.clone(), &msg, &[])
+        .unwrap();
+
+    let balance = router.wrap().query_balance(USER, "ustars").unwrap();
+    assert_eq!(balance, coin(vesting, "ustars"));
+
+    let res: fixed_stake::msg::VestingScheduleResponse =
+        router.wrap().query_wasm_smart(stake, &query_msg).unwrap();
+    assert!(res.tranches.is_empty());
+}
+
+#[test]
+fn redirected_vested_claims_stay_bounded() {
+    let mut router = setup_native_contracts();
+    let admin = Addr::unchecked(ADMIN);
+    let user = Addr::unchecked(USER);
+    let victim = Addr::unchecked("victim");
+
+    // Set up a streaming FixedStake contract emitting 1 ustars per second, vesting over an hour
+    let msg = fixed_stake::contract::InstantiateMsg {
+        lockup_contract: NATIVE_LOCKUP.to_string(),
+        distribution_interval: Timestamp::from_seconds(3600),
+        rewards: vec![(
+            fixed_stake::storage::AssetInfoInput::Native("ustars".to_string()),
+            Uint128::from(36000u128),
+        )],
+        time: fixed_stake::storage::ProgramTime {
+            start_time: Timestamp::from_seconds(1),
+            end_time: Timestamp::from_seconds(36001),
+        },
+        options: Some(fixed_stake::storage::InstantiateOptions {
+            streaming: Some(true),
+            vesting: Some(fixed_stake::storage::VestingConfig {
+                duration: Timestamp::from_seconds(3600),
+                cliff: Timestamp::from_seconds(0),
+            }),
+            ..Default::default()
+        }),
+    };
+    router
+        .init_modules(|router, _, storage| {
+            router
+                .bank
+                .init_balance(storage, &admin, coins(36100, "ustars"))
+        })
+        .unwrap();
+    let stake = router
+        .instantiate_contract(2, admin, &msg, &coins(36000, "ustars"), "STAKE", None)
+        .unwrap();
+
+    native_deposit(&mut router, 100);
+
+    // User names the victim as recipient without their consent
+    let msg = fixed_stake::contract::ExecMsg::SetRewardRecipient {
+        recipient: victim.to_string(),
+    };
+    router
+        .execute_contract(user.clone(), stake.clone(), &msg, &[])
+        .unwrap();
+
+    // And claims in every block for a full vesting duration
+    let msg = fixed_stake::contract::ExecMsg::ClaimRewards {
+        of: fixed_stake::storage::Position::Native {
+            address: USER.to_string(),
+        },
+    };
+    for _ in 0..720 {
+        add_block_time(&mut router, 5);
+        router
+            .execute_contract(user.clone(), stake.clone(), &msg, &[])
+            .unwrap();
+    }
+
+    // The victim's schedule is bounded by the number of vesting buckets
+    let query_msg = fixed_stake::contract::QueryMsg::VestingSchedule {
+        address: victim.to_string(),
+    };
+    let res: fixed_stake::msg::VestingScheduleResponse = router
+        .wrap()
+        .query_wasm_smart(stake.clone(), &query_msg)
+        .unwrap();
+    assert!(res.tranches.len() as u64 <= fixed_stake::storage::VESTING_BUCKETS + 2);
+
+    // And the victim can still release everything in one claim
+    add_block_time(&mut router, 3600);
+
+    let msg = fixed_stake::contract::ExecMsg::ClaimVested {};
+    router
+        .execute_contract(victim.clone(), stake.clone(), &msg, &[])
+        .unwrap();
+
+    let res: fixed_stake::msg::VestingScheduleResponse =
+        router.wrap().query_wasm_smart(stake, &query_msg).unwrap();
+    assert!(res.tranches.is_empty());
+}
+
+#[test]
+fn claim_on_behalf_of_owner() {
+    let mut router = setup_native_contracts();
+    let user = Addr::unchecked(USER);
+    let operator = Addr::unchecked("operator");
+    let treasury = Addr::unchecked("treasury");
+
+    native_deposit(&mut router, 100);
+    add_block_time(&mut router, 3600);
+
+    // Operator cannot claim before being approved
+    let claim_msg = fixed_stake::contract::ExecMsg::ClaimRewards {
+        of: fixed_stake::storage::Position::Native {
+            address: USER.to_string(),
+        },
+    };
+    let err = router
+        .execute_contract(
+            operator.clone(),
+            Addr::unchecked(NATIVE_STAKE),
+            &claim_msg,
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<StdError>().unwrap(),
+        StdError::generic_err("Unauthorized")
+    );
+
+    // User redirects rewards to the treasury and approves the operator
+    let msg = fixed_stake::contract::ExecMsg::SetRewardRecipient {
+        recipient: treasury.to_string(),
+    };
+    router
+        .execute_contract(user.clone(), Addr::unchecked(NATIVE_STAKE), &msg, &[])
+        .unwrap();
+
+    let msg = fixed_stake::contract::ExecMsg::ApproveClaimer {
+        operator: operator.to_string(),
+    };
+    router
+        .execute_contract(user.clone(), Addr::unchecked(NATIVE_STAKE), &msg, &[])
+        .unwrap();
+
+    let query_msg = fixed_stake::contract::QueryMsg::Claimers {
+        owner: USER.to_string(),
+    };
+    let res: Vec<Addr> = router
+        .wrap()
+        .query_wasm_smart(NATIVE_STAKE, &query_msg)
+        .unwrap();
+    assert_eq!(res, vec![operator.clone()]);
+
+    // Operator claims, and the rewards go to the treasury
+    router
+        .execute_contract(
+            operator.clone(),
+            Addr::unchecked(NATIVE_STAKE),
+            &claim_msg,
+            &[],
+        )
+        .unwrap();
+
+    let balance = router.wrap().query_balance(&treasury, "ustars").unwrap();
+    assert_eq!(balance, coin(10u128, "ustars"));
+    let balance = router.wrap().query_balance(&operator, "ustars").unwrap();
+    assert_eq!(balance, coin(0u128, "ustars"));
+
+    // Once revoked, the operator can no longer claim
+    let msg = fixed_stake::contract::ExecMsg::RevokeClaimer {
+        operator: operator.to_string(),
+    };
+    router
+        .execute_contract(user.clone(), Addr::unchecked(NATIVE_STAKE), &msg, &[])
+        .unwrap();
+
+    add_block_time(&mut router, 3600);
+
+    let err = router
+        .execute_contract(operator, Addr::unchecked(NATIVE_STAKE), &claim_msg, &[])
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<StdError>().unwrap(),
+        StdError::generic_err("Unauthorized")
+    );
+}
+
+#[test]
+fn migrate_legacy_claims() {
+    let mut router = setup_native_contracts();
+    let admin = Addr::unchecked(ADMIN);
+    let user = Addr::unchecked(USER);
+
+    // Set up a FixedStake contract deployed before multiple rewards, holding a claim
+    // stored under the legacy key. The first interval was already paid out, so the
+    // contract holds 90 of the 100 ustars
+    let legacy_id = router.store_code(contract_legacy_stake());
+    let msg = BaselineInstantiateMsg {
+        lockup_contract: NATIVE_LOCKUP.to_string(),
+        distribution_interval: Timestamp::from_seconds(3600),
+        reward_denom: "ustars".to_string(),
+        total_rewards: Uint128::from(100u128),
+        start_time: Timestamp::from_seconds(1),
+        end_time: Timestamp::from_seconds(36001),
+    };
+    let stake = router
+        .instantiate_contract(
+            legacy_id,
+            admin.clone(),
+            &msg,
+            &coins(90, "ustars"),
+            "STAKE",
+            Some(ADMIN.to_string()),
+        )
+        .unwrap();
+
+    let query_msg = fixed_stake::contract::QueryMsg::QueryLastClaim {
+        of: fixed_stake::storage::Position::Native {
+            address: USER.to_string(),
+        },
+    };
+    router
+        .wrap()
+        .query_wasm_smart::<Timestamp>(stake.clone(), &query_msg)
+        .unwrap_err();
+
+    // Migrating moves the claim to its position
+    router
+        .migrate_contract(
+            admin,
+            stake.clone(),
+            &fixed_stake::contract::MigrateMsg {},
+            2,
+        )
+        .unwrap();
+
+    let res: Timestamp = router
+        .wrap()
+        .query_wasm_smart(stake.clone(), &query_msg)
+        .unwrap();
+    assert_eq!(res, Timestamp::from_seconds(3601));
+
+    // The legacy reward becomes the only reward, distributed evenly over the program
+    let asset = fixed_stake::storage::AssetInfo::Native("ustars".to_string());
+    let query_msg = fixed_stake::contract::QueryMsg::Config {};
+    let res: fixed_stake::msg::ConfigResponse = router
+        .wrap()
+        .query_wasm_smart(stake.clone(), &query_msg)
+        .unwrap();
+    assert_eq!(res.rewards, vec![(asset.clone(), Uint128::from(100u128))]);
+
+    let query_msg = fixed_stake::contract::QueryMsg::Periods {};
+    let res: Vec<fixed_stake::storage::Period> = router
+        .wrap()
+        .query_wasm_smart(stake.clone(), &query_msg)
+        .unwrap();
+    assert_eq!(
+        res,
+        vec![fixed_stake::storage::Period::new(
+            Timestamp::from_seconds(1),
+            Timestamp::from_seconds(36001),
+            Timestamp::from_seconds(3600),
+            vec![(asset.clone(), Uint128::from(100u128))],
+            fixed_stake::storage::Emission::Flat,
+        )]
+    );
+
+    // The total is recorded as funded and what the contract no longer holds as distributed
+    let query_msg = fixed_stake::contract::QueryMsg::Funding {};
+    let res: fixed_stake::msg::FundingResponse = router
+        .wrap()
+        .query_wasm_smart(stake.clone(), &query_msg)
+        .unwrap();
+    assert_eq!(
+        res.funded_amount,
+        vec![(asset.clone(), Uint128::from(100u128))]
+    );
+    assert_eq!(
+        res.distributed_amount,
+        vec![(asset.clone(), Uint128::from(10u128))]
+    );
+    assert!(res.fully_funded);
+
+    // The migrated claim pays out from its last claim on
+    native_deposit(&mut router, 100);
+    add_block_time(&mut router, 7200);
+
+    let msg = fixed_stake::contract::ExecMsg::ClaimRewards {
+        of: fixed_stake::storage::Position::Native {
+            address: USER.to_string(),
+        },
+    };
+    router
+        .execute_contract(user, stake.clone(), &msg, &[])
+        .unwrap();
+
+    let balance = router.wrap().query_balance(USER, "ustars").unwrap();
+    assert_eq!(balance, coin(10u128, "ustars"));
+
+    let query_msg = fixed_stake::contract::QueryMsg::Funding {};
+    let res: fixed_stake::msg::FundingResponse =
+        router.wrap().query_wasm_smart(stake, &query_msg).unwrap();
+    assert_eq!(res.distributed_amount, vec![(asset, Uint128::from(20u128))]);
+}
+
+#[test]
+fn protocol_fee_on_claims() {
+    let mut router = setup_native_contracts();
+    let admin = Addr::unchecked(ADMIN);
+    let user = Addr::unchecked(USER);
+
+    // Set up a FixedStake contract taking a 10% protocol fee
+    let msg = fixed_stake::contract::InstantiateMsg {
+        lockup_contract: NATIVE_LOCKUP.to_string(),
+        distribution_interval: Timestamp::from_seconds(3600),
+        rewards: vec![(
+            fixed_stake::storage::AssetInfoInput::Native("ustars".to_string()),
+            Uint128::from(100u128),
+        )],
+        time: fixed_stake::storage::ProgramTime {
+            start_time: Timestamp::from_seconds(1),
+            end_time: Timestamp::from_seconds(36001),
+        },
+        options: Some(fixed_stake::storage::InstantiateOptions {
+            fee: Some(fixed_stake::storage::FeeConfigInput {
+                fee_bps: 1000,
+                collector: "collector".to_string(),
+            }),
+            ..Default::default()
+        }),
+    };
+    let stake = router
+        .instantiate_contract(2, admin.clone(), &msg, &coins(100, "ustars"), "STAKE", None)
+        .unwrap();
+
+    let query_msg = fixed_stake::contract::QueryMsg::FeeConfig {};
+    let res: fixed_stake::msg::FeeConfigResponse = router
+        .wrap()
+        .query_wasm_smart(stake.clone(), &query_msg)
+        .unwrap();
+    assert_eq!(res.fee.unwrap().fee_bps, 1000);
+    assert_eq!(res.max_fee_bps, 1000);
+
+    native_deposit(&mut router, 100);
+    add_block_time(&mut router, 3600);
+
+    // The claimer receives the reward minus the fee, which goes to the collector
+    let msg = fixed_stake::contract::ExecMsg::ClaimRewards {
+        of: fixed_stake::storage::Position::Native {
+            address: USER.to_string(),
+        },
+    };
+    let res = router
+        .execute_contract(user.clone(), stake.clone(), &msg, &[])
+        .unwrap();
+
+    let balance = router.wrap().query_balance(USER, "ustars").unwrap();
+    assert_eq!(balance, coin(9u128, "ustars"));
+    let balance = router.wrap().query_balance("collector", "ustars").unwrap();
+    assert_eq!(balance, coin(1u128, "ustars"));
+
+    let event = res
+        .events
+        .iter()
+        .find(|event| event.ty == "wasm-protocol_fee")
+        .unwrap();
+    assert!(event
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "amount" && attr.value == "1"));
+
+    // The fee cannot be raised above the maximum, nor updated by anyone but the admin
+    let msg = fixed_stake::contract::ExecMsg::UpdateFee {
+        fee: Some(fixed_stake::storage::FeeConfigInput {
+            fee_bps: 1001,
+            collector: "collector".to_string(),
+        }),
+    };
+    let err = router
+        .execute_contract(admin.clone(), stake.clone(), &msg, &[])
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<StdError>().unwrap(),
+        StdError::generic_err("Fee must not exceed 1000 basis points")
+    );
+
+    let msg = fixed_stake::contract::ExecMsg::UpdateFee { fee: None };
+    let err = router
+        .execute_contract(user, stake.clone(), &msg, &[])
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<StdError>().unwrap(),
+        StdError::generic_err("Unauthorized")
+    );
+
+    router
+        .execute_contract(admin, stake.clone(), &msg, &[])
+        .unwrap();
+
+    let res: fixed_stake::msg::FeeConfigResponse =
+        router.wrap().query_wasm_smart(stake, &query_msg).unwrap();
+    assert_eq!(res.fee, None);
+}
@@ -0,0 +1,265 @@
+#![cfg(test)]
+
+use cosmwasm_std::{Decimal, StdError, Timestamp, Uint128};
+use fixed_stake::helpers::accrued_rewards;
+use fixed_stake::storage::{AssetInfo, Emission, Period};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+const RUNS: usize = 500;
+
+fn ustars() -> AssetInfo {
+    AssetInfo::Native("ustars".to_string())
+}
+
+// Extreme inputs may overflow the reward calculation, but must not fail otherwise
+fn assert_overflow(err: StdError) {
+    match &err {
+        StdError::Overflow { .. } | StdError::ConversionOverflow { .. } => {}
+        StdError::GenericErr { msg, .. } if msg.starts_with("Reward calculation") => {}
+        _ => panic!("Unexpected error: {err}"),
+    }
+}
+
+// Runs skipped on overflow must remain a small minority for the properties to mean anything
+fn assert_mostly_checked(skipped: usize) {
+    assert!(skipped * 10 <= RUNS, "{skipped} of {RUNS} runs overflowed");
+}
+
+// Random emission schedule, including degenerate parameters
+fn random_emission(rng: &mut StdRng) -> Emission {
+    match rng.gen_range(0..4) {
+        0 => Emission::Flat,
+        1 => Emission::LinearDecay {
+            intervals: rng.gen_range(1..=u64::MAX),
+        },
+        2 => Emission::Halving {
+            intervals: rng.gen_range(1..=u64::MAX),
+        },
+        _ => Emission::Table {
+            weights: (0..rng.gen_range(1..5))
+                .map(|_| Decimal::percent(rng.gen_range(1..1000)))
+                .collect(),
+        },
+    }
+}
+
+// Random period with extreme durations, intervals and totals
+fn random_period(rng: &mut StdRng) -> Period {
+    let start = rng.gen_range(0..u32::MAX as u64);
+    let duration = match rng.gen_bool(0.5) {
+        true => rng.gen_range(1..1_000_000),
+        false => rng.gen_range(1..u32::MAX as u64),
+    };
+    let interval = rng.gen_range(1..=duration);
+    let total = match rng.gen_bool(0.5) {
+        true => rng.gen_range(0..1_000_000_000),
+        false => rng.gen_range(u64::MAX as u128..=u128::MAX),
+    };
+
+    Period::new(
+        Timestamp::from_seconds(start),
+        Timestamp::from_seconds(start + duration),
+        Timestamp::from_seconds(interval),
+        vec![(ustars(), Uint128::from(total))],
+        random_emission(rng),
+    )
+}
+
+#[test]
+fn zero_count_is_an_error() {
+    let period = Period::new(
+        Timestamp::from_seconds(0),
+        Timestamp::from_seconds(100),
+        Timestamp::from_seconds(10),
+        vec![(ustars(), Uint128::from(100u128))],
+        Emission::Flat,
+    );
+
+    let err = accrued_rewards(
+        &[period],
+        Timestamp::from_seconds(0),
+        Timestamp::from_seconds(50),
+        Uint128::from(1u128),
+        0,
+        false,
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        StdError::generic_err("Lockup count must be greater than 0")
+    );
+}
+
+#[test]
+fn large_totals_are_not_paid_as_zero() {
+    // Well above the range of `Decimal`
+    let total = Uint128::from(10u128.pow(30));
+    let period = Period::new(
+        Timestamp::from_seconds(0),
+        Timestamp::from_seconds(100),
+        Timestamp::from_seconds(10),
+        vec![(ustars(), total)],
+        Emission::Flat,
+    );
+
+    let (rewards, _) = accrued_rewards(
+        &[period],
+        Timestamp::from_seconds(0),
+        Timestamp::from_seconds(10),
+        Uint128::from(1u128),
+        1,
+        false,
+    )
+    .unwrap();
+    assert_eq!(rewards, vec![(ustars(), Uint128::from(10u128.pow(29)))]);
+}
+
+#[test]
+fn emission_at_prorates_intervals_crossing_period_bounds() {
+    let mut period = Period::new(
+        Timestamp::from_seconds(0),
+        Timestamp::from_seconds(7200),
+        Timestamp::from_seconds(3600),
+        vec![(ustars(), Uint128::from(200u128))],
+        Emission::Flat,
+    );
+
+    // Settle halfway through the second interval, as reconfiguring the program does
+    let remaining = period.split_at(Timestamp::from_seconds(5400)).unwrap();
+    let mut next = Period::new(
+        Timestamp::from_seconds(5400),
+        Timestamp::from_seconds(7200),
+        Timestamp::from_seconds(3600),
+        remaining,
+        Emission::Flat,
+    );
+    next.emission_start = period.emission_start;
+
+    // The straddling interval is split between both periods as it accrues
+    assert_eq!(
+        period.emission_at(Timestamp::from_seconds(1)).unwrap(),
+        vec![(ustars(), Uint128::from(100u128))]
+    );
+    assert_eq!(
+        period.emission_at(Timestamp::from_seconds(3601)).unwrap(),
+        vec![(ustars(), Uint128::from(50u128))]
+    );
+    assert_eq!(
+        next.emission_at(Timestamp::from_seconds(5401)).unwrap(),
+        vec![(ustars(), Uint128::from(50u128))]
+    );
+    assert_eq!(
+        period.rewards[0].1 + next.rewards[0].1,
+        Uint128::from(200u128)
+    );
+}
+
+#[test]
+fn accrued_rewards_never_exceed_total() {
+    let mut rng = StdRng::seed_from_u64(0x5eed);
+    let mut skipped = 0;
+
+    for _ in 0..RUNS {
+        let period = random_period(&mut rng);
+        let total = period.rewards[0].1;
+        let count = match rng.gen_bool(0.5) {
+            true => rng.gen_range(1..1_000_000),
+            false => rng.gen_range(1..=u128::MAX),
+        };
+        let amount = Uint128::from(rng.gen_range(1..=count));
+
+        let from = rng.gen_range(period.start_time.seconds()..period.end_time.seconds());
+        let to = rng.gen_range(from..=period.end_time.seconds() + 1);
+        let streaming = rng.gen_bool(0.5);
+
+        // Either a bounded reward or an overflow error, never a panic
+        let res = accrued_rewards(
+            std::slice::from_ref(&period),
+            Timestamp::from_seconds(from),
+            Timestamp::from_seconds(to),
+            amount,
+            count,
+            streaming,
+        );
+        let (rewards, paid_until) = match res {
+            Ok(res) => res,
+            Err(err) => {
+                assert_overflow(err);
+                skipped += 1;
+                continue;
+            }
+        };
+
+        assert!(rewards.iter().all(|(_, reward)| *reward <= total));
+        assert!(paid_until.seconds() >= from && paid_until.seconds() <= to);
+    }
+
+    assert_mostly_checked(skipped);
+}
+
+#[test]
+fn sole_holder_receives_whole_program() {
+    let mut rng = StdRng::seed_from_u64(0xfeed);
+    let mut skipped = 0;
+
+    for _ in 0..RUNS {
+        let period = random_period(&mut rng);
+        let total = period.rewards[0].1;
+        let amount = rng.gen_range(1..=u128::MAX);
+
+        // Claiming once the period is over settles it exactly
+        let res = accrued_rewards(
+            std::slice::from_ref(&period),
+            period.start_time,
+            period.end_time.plus_seconds(1),
+            Uint128::from(amount),
+            amount,
+            false,
+        );
+        let rewards = match res {
+            Ok((rewards, _)) => rewards,
+            Err(err) => {
+                assert_overflow(err);
+                skipped += 1;
+                continue;
+            }
+        };
+
+        let reward = rewards
+            .first()
+            .map(|(_, reward)| *reward)
+            .unwrap_or_default();
+        assert!(reward <= total);
+        // Rounding never loses more than a single unit per operation
+        assert!(total - reward <= Uint128::from(4u128), "{total} {reward}");
+    }
+
+    assert_mostly_checked(skipped);
+}
+
+#[test]
+fn split_preserves_totals() {
+    let mut rng = StdRng::seed_from_u64(0xbeef);
+    let mut skipped = 0;
+
+    for _ in 0..RUNS {
+        let mut period = random_period(&mut rng);
+        let total = period.rewards[0].1;
+        let split = rng.gen_range(period.start_time.seconds()..period.end_time.seconds());
+
+        let remaining = match period.split_at(Timestamp::from_seconds(split)) {
+            Ok(remaining) => remaining,
+            Err(err) => {
+                assert_overflow(err);
+                skipped += 1;
+                continue;
+            }
+        };
+
+        assert_eq!(period.end_time, Timestamp::from_seconds(split));
+        assert_eq!(period.rewards[0].1 + remaining[0].1, total);
+    }
+
+    assert_mostly_checked(skipped);
+}
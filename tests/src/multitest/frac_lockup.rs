@@ -1,8 +1,10 @@
 #![cfg(test)]
 
-use cosmwasm_std::{coins, Addr, Empty};
+use cosmwasm_std::{
+    coin, coins, to_json_binary, Addr, Empty, HexBinary, StdError, Timestamp, Uint128,
+};
 use cw_multi_test::{App, Contract, ContractWrapper, Executor};
-use frac_lockup::storage::CollectionInput;
+use frac_lockup::storage::{CollectionInput, DenomInput, PriorityWindow};
 
 pub fn contract_cw721() -> Box<dyn Contract<Empty>> {
     let contract = ContractWrapper::new(
@@ -22,6 +24,24 @@ pub fn contract_lockup() -> Box<dyn Contract<Empty>> {
     Box::new(contract)
 }
 
+pub fn contract_native_lockup() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        native_lockup::contract::entry_points::execute,
+        native_lockup::contract::entry_points::instantiate,
+        native_lockup::contract::entry_points::query,
+    );
+    Box::new(contract)
+}
+
+pub fn contract_nois_proxy() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        nois_proxy_mock::contract::entry_points::execute,
+        nois_proxy_mock::contract::entry_points::instantiate,
+        nois_proxy_mock::contract::entry_points::query,
+    );
+    Box::new(contract)
+}
+
 const CW721: &str = "contract0";
 const LOCKUP: &str = "contract1";
 
@@ -32,7 +52,10 @@ const USER: &str = "user";
 fn setup_contracts() -> App {
     let admin = Addr::unchecked(ADMIN);
 
-    let init_funds = coins(2000, "ustars");
+    let init_funds = vec![
+        coin(2000, "ustars"),
+        coin(2000000, format!("factory/{}/{}", ADMIN, "bad-kids")),
+    ];
 
     let mut router = App::new(|router, _, storage| {
         router
@@ -59,8 +82,15 @@ fn setup_contracts() -> App {
         collections: vec![CollectionInput {
             address: cw721_addr.to_string(),
             tokens: 1000000u128,
+            deposit_fee_bps: None,
+            redeem_fee_bps: None,
+            swap_fee_bps: None,
+            subdenom: None,
         }],
-        denom: format!("factory/{}/{}", admin.to_string(), "bad-kids"),
+        denom: DenomInput::Existing(format!("factory/{}/{}", admin.to_string(), "bad-kids")),
+        nois_proxy: None,
+        redeem_premium: None,
+        treasury: None,
     };
 
     router
@@ -74,3 +104,535 @@ fn setup_contracts() -> App {
 fn proper_initialization() {
     setup_contracts();
 }
+
+// Set up a mock randomness proxy and a FracLockup contract using it
+fn setup_random_redemption(router: &mut App) -> Addr {
+    let admin = Addr::unchecked(ADMIN);
+
+    let proxy_id = router.store_code(contract_nois_proxy());
+    let proxy_addr = router
+        .instantiate_contract(
+            proxy_id,
+            admin.clone(),
+            &nois_proxy_mock::contract::InstantiateMsg {},
+            &[],
+            "NOIS_PROXY",
+            None,
+        )
+        .unwrap();
+
+    let msg = frac_lockup::contract::InstantiateMsg {
+        collections: vec![CollectionInput {
+            address: CW721.to_string(),
+            tokens: 1000000u128,
+            deposit_fee_bps: None,
+            redeem_fee_bps: None,
+            swap_fee_bps: None,
+            subdenom: None,
+        }],
+        denom: DenomInput::Existing(format!("factory/{}/{}", ADMIN, "bad-kids")),
+        nois_proxy: Some(proxy_addr.to_string()),
+        redeem_premium: None,
+        treasury: None,
+    };
+
+    router
+        .instantiate_contract(2, admin, &msg, &[], "LOCKUP", None)
+        .unwrap()
+}
+
+#[test]
+fn random_redemption_requires_proxy() {
+    let mut router = setup_contracts();
+
+    let msg = frac_lockup::contract::ExecMsg::RedeemRandom {
+        collection: CW721.to_string(),
+    };
+    let err = router
+        .execute_contract(
+            Addr::unchecked(ADMIN),
+            Addr::unchecked(LOCKUP),
+            &msg,
+            &coins(1000000, format!("factory/{}/{}", ADMIN, "bad-kids")),
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<StdError>().unwrap(),
+        StdError::generic_err("Random redemption is not enabled")
+    );
+}
+
+#[test]
+fn random_redemption_requires_tokens() {
+    let mut router = setup_contracts();
+    let lockup = setup_random_redemption(&mut router);
+
+    // No token is locked up, so there is nothing to redeem
+    let msg = frac_lockup::contract::ExecMsg::RedeemRandom {
+        collection: CW721.to_string(),
+    };
+    let err = router
+        .execute_contract(
+            Addr::unchecked(ADMIN),
+            lockup.clone(),
+            &msg,
+            &coins(1000000, format!("factory/{}/{}", ADMIN, "bad-kids")),
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<StdError>().unwrap(),
+        StdError::generic_err("No tokens available for redemption")
+    );
+
+    // Only the proxy can deliver randomness
+    let msg = frac_lockup::contract::ExecMsg::NoisReceive {
+        callback: frac_lockup::msg::NoisCallback {
+            job_id: "redeem-1".to_string(),
+            published: Timestamp::from_seconds(0),
+            randomness: HexBinary::from(vec![0u8; 32]),
+        },
+    };
+    let err = router
+        .execute_contract(Addr::unchecked(USER), lockup.clone(), &msg, &[])
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<StdError>().unwrap(),
+        StdError::generic_err("Unauthorized")
+    );
+
+    // Only pending redemptions can be cancelled
+    let msg = frac_lockup::contract::ExecMsg::CancelRandomRedemption {
+        job_id: "redeem-1".to_string(),
+    };
+    let err = router
+        .execute_contract(Addr::unchecked(ADMIN), lockup, &msg, &[])
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<StdError>().unwrap(),
+        StdError::generic_err("Redemption not found")
+    );
+}
+
+#[test]
+fn fee_quotes() {
+    let mut router = setup_contracts();
+    let admin = Addr::unchecked(ADMIN);
+
+    // Fees cannot be charged without a treasury
+    let msg = frac_lockup::contract::ExecMsg::UpdateCollectionFees {
+        collection_address: CW721.to_string(),
+        deposit_fee_bps: 100,
+        redeem_fee_bps: 200,
+        swap_fee_bps: None,
+    };
+    let err = router
+        .execute_contract(admin.clone(), Addr::unchecked(LOCKUP), &msg, &[])
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<StdError>().unwrap(),
+        StdError::generic_err("Treasury must be set to charge fees")
+    );
+
+    // Nor can a redemption premium
+    let mut msg = frac_lockup::contract::InstantiateMsg {
+        collections: vec![CollectionInput {
+            address: CW721.to_string(),
+            tokens: 1000000u128,
+            deposit_fee_bps: None,
+            redeem_fee_bps: None,
+            swap_fee_bps: None,
+            subdenom: None,
+        }],
+        denom: DenomInput::Existing(format!("factory/{}/{}", ADMIN, "bad-kids")),
+        nois_proxy: None,
+        redeem_premium: Some(Uint128::from(500000u128)),
+        treasury: None,
+    };
+    let err = router
+        .instantiate_contract(2, admin.clone(), &msg, &[], "LOCKUP", None)
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<StdError>().unwrap(),
+        StdError::generic_err("Treasury must be set to charge a redemption premium")
+    );
+
+    // Set up a FracLockup contract with a treasury and fees
+    msg.collections[0].deposit_fee_bps = Some(100);
+    msg.collections[0].redeem_fee_bps = Some(200);
+    msg.treasury = Some("treasury".to_string());
+    let lockup = router
+        .instantiate_contract(2, admin.clone(), &msg, &[], "LOCKUP", None)
+        .unwrap();
+
+    // Fees are capped like the protocol fee of stake contracts
+    let msg = frac_lockup::contract::ExecMsg::UpdateCollectionFees {
+        collection_address: CW721.to_string(),
+        deposit_fee_bps: 100,
+        redeem_fee_bps: 1001,
+        swap_fee_bps: None,
+    };
+    let err = router
+        .execute_contract(admin.clone(), lockup.clone(), &msg, &[])
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<StdError>().unwrap(),
+        StdError::generic_err("Fee must not exceed 1000 basis points")
+    );
+
+    // Updating the other fees keeps the swap fee when none is given
+    let msg = frac_lockup::contract::ExecMsg::UpdateCollectionFees {
+        collection_address: CW721.to_string(),
+        deposit_fee_bps: 50,
+        redeem_fee_bps: 200,
+        swap_fee_bps: Some(300),
+    };
+    router
+        .execute_contract(admin.clone(), lockup.clone(), &msg, &[])
+        .unwrap();
+
+    let msg = frac_lockup::contract::ExecMsg::UpdateCollectionFees {
+        collection_address: CW721.to_string(),
+        deposit_fee_bps: 100,
+        redeem_fee_bps: 200,
+        swap_fee_bps: None,
+    };
+    router
+        .execute_contract(admin, lockup.clone(), &msg, &[])
+        .unwrap();
+
+    let query_msg = frac_lockup::contract::QueryMsg::Config {};
+    let res: frac_lockup::msg::ConfigResponse = router
+        .wrap()
+        .query_wasm_smart(lockup.clone(), &query_msg)
+        .unwrap();
+    assert_eq!(res.collections[0].deposit_fee_bps, 100);
+    assert_eq!(res.collections[0].swap_fee_bps, 300);
+
+    let query_msg = frac_lockup::contract::QueryMsg::DepositQuote {
+        collection_address: CW721.to_string(),
+    };
+    let res: frac_lockup::msg::DepositQuoteResponse = router
+        .wrap()
+        .query_wasm_smart(lockup.clone(), &query_msg)
+        .unwrap();
+    assert_eq!(res.minted, Uint128::from(990000u128));
+    assert_eq!(res.fee, Uint128::from(10000u128));
+
+    // Specific redemptions pay the premium on top of the fee, random ones do not
+    let query_msg = frac_lockup::contract::QueryMsg::RedeemQuote {
+        collection_address: CW721.to_string(),
+        random: false,
+        token_id: None,
+        redeemer: None,
+    };
+    let res: frac_lockup::msg::RedeemQuoteResponse = router
+        .wrap()
+        .query_wasm_smart(lockup.clone(), &query_msg)
+        .unwrap();
+    assert_eq!(res.price, Uint128::from(1520000u128));
+    assert_eq!(res.fee, Uint128::from(20000u128));
+
+    let query_msg = frac_lockup::contract::QueryMsg::RedeemQuote {
+        collection_address: CW721.to_string(),
+        random: true,
+        token_id: None,
+        redeemer: None,
+    };
+    let res: frac_lockup::msg::RedeemQuoteResponse =
+        router.wrap().query_wasm_smart(lockup, &query_msg).unwrap();
+    assert_eq!(res.price, Uint128::from(1020000u128));
+}
+
+#[test]
+fn transfer_denom_admin() {
+    let mut router = setup_contracts();
+
+    // Admin only
+    let msg = frac_lockup::contract::ExecMsg::TransferDenomAdmin {
+        denom: format!("factory/{}/{}", ADMIN, "bad-kids"),
+        new_admin: USER.to_string(),
+    };
+    let err = router
+        .execute_contract(Addr::unchecked(USER), Addr::unchecked(LOCKUP), &msg, &[])
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<StdError>().unwrap(),
+        StdError::generic_err("Unauthorized")
+    );
+
+    // Only the denoms of the contract can be handed over
+    let msg = frac_lockup::contract::ExecMsg::TransferDenomAdmin {
+        denom: format!("factory/{}/{}", ADMIN, "other"),
+        new_admin: USER.to_string(),
+    };
+    let err = router
+        .execute_contract(Addr::unchecked(ADMIN), Addr::unchecked(LOCKUP), &msg, &[])
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<StdError>().unwrap(),
+        StdError::generic_err("Denom is not managed by the contract")
+    );
+}
+
+#[test]
+fn swap_credit() {
+    let mut router = setup_contracts();
+    let denom = format!("factory/{}/{}", ADMIN, "bad-kids");
+
+    // Swaps are paid in the collection's tokens
+    let msg = frac_lockup::contract::ExecMsg::FundSwap {
+        collection_address: CW721.to_string(),
+    };
+    let err = router
+        .execute_contract(
+            Addr::unchecked(ADMIN),
+            Addr::unchecked(LOCKUP),
+            &msg,
+            &coins(1000, "ustars"),
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<StdError>().unwrap(),
+        StdError::generic_err("Unsupported token sent")
+    );
+
+    router
+        .execute_contract(
+            Addr::unchecked(ADMIN),
+            Addr::unchecked(LOCKUP),
+            &msg,
+            &coins(500000, denom.clone()),
+        )
+        .unwrap();
+
+    let query_msg = frac_lockup::contract::QueryMsg::SwapCredit {
+        address: ADMIN.to_string(),
+        collection_address: CW721.to_string(),
+    };
+    let res: cosmwasm_std::Coin = router.wrap().query_wasm_smart(LOCKUP, &query_msg).unwrap();
+    assert_eq!(res, coin(500000, denom.clone()));
+
+    // The credit can be reclaimed in full, once
+    let msg = frac_lockup::contract::ExecMsg::ReclaimSwapCredit {
+        collection_address: CW721.to_string(),
+    };
+    router
+        .execute_contract(Addr::unchecked(ADMIN), Addr::unchecked(LOCKUP), &msg, &[])
+        .unwrap();
+
+    let balance = router.wrap().query_balance(ADMIN, denom).unwrap();
+    assert_eq!(balance.amount, Uint128::from(2000000u128));
+
+    let err = router
+        .execute_contract(Addr::unchecked(ADMIN), Addr::unchecked(LOCKUP), &msg, &[])
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<StdError>().unwrap(),
+        StdError::generic_err("No swap credit to reclaim")
+    );
+}
+
+#[test]
+fn swap_requires_locked_target() {
+    let mut router = setup_contracts();
+
+    let msg: cw721_base::ExecuteMsg<Empty, Empty> = cw721_base::ExecuteMsg::Mint {
+        token_id: "1".to_string(),
+        owner: USER.to_string(),
+        token_uri: None,
+        extension: Empty {},
+    };
+    router
+        .execute_contract(Addr::unchecked(ADMIN), Addr::unchecked(CW721), &msg, &[])
+        .unwrap();
+
+    // Token 2 is not in the pool
+    let msg: cw721_base::ExecuteMsg<Empty, Empty> = cw721_base::ExecuteMsg::SendNft {
+        contract: LOCKUP.to_string(),
+        token_id: "1".to_string(),
+        msg: to_json_binary(&frac_lockup::msg::ReceiveNftMsg::SwapNft {
+            target_token_id: "2".to_string(),
+        })
+        .unwrap(),
+    };
+    let err = router
+        .execute_contract(Addr::unchecked(USER), Addr::unchecked(CW721), &msg, &[])
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<StdError>().unwrap(),
+        StdError::generic_err("Lockup entry not found")
+    );
+}
+
+#[test]
+fn stake_into_requires_collection_token() {
+    let mut router = setup_contracts();
+    let admin = Addr::unchecked(ADMIN);
+
+    // Set up a native lockup of another token
+    let native_lockup_id = router.store_code(contract_native_lockup());
+    let native_lockup = router
+        .instantiate_contract(
+            native_lockup_id,
+            admin.clone(),
+            &native_lockup::contract::InstantiateMsg {
+                token: "ustars".to_string(),
+                lockup_interval: None,
+            },
+            &[],
+            "NATIVE_LOCKUP",
+            None,
+        )
+        .unwrap();
+
+    let msg: cw721_base::ExecuteMsg<Empty, Empty> = cw721_base::ExecuteMsg::Mint {
+        token_id: "1".to_string(),
+        owner: ADMIN.to_string(),
+        token_uri: None,
+        extension: Empty {},
+    };
+    router
+        .execute_contract(admin.clone(), Addr::unchecked(CW721), &msg, &[])
+        .unwrap();
+
+    // The minted tokens cannot be staked into it
+    let msg: cw721_base::ExecuteMsg<Empty, Empty> = cw721_base::ExecuteMsg::SendNft {
+        contract: LOCKUP.to_string(),
+        token_id: "1".to_string(),
+        msg: to_json_binary(&frac_lockup::msg::ReceiveNftMsg::Deposit {
+            options: frac_lockup::msg::DepositOptions::StakeInto {
+                lockup: native_lockup.to_string(),
+            },
+        })
+        .unwrap(),
+    };
+    let err = router
+        .execute_contract(admin, Addr::unchecked(CW721), &msg, &[])
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<StdError>().unwrap(),
+        StdError::generic_err("Lockup does not hold the collection's tokens")
+    );
+}
+
+#[test]
+fn backing() {
+    let router = setup_contracts();
+    let denom = format!("factory/{}/{}", ADMIN, "bad-kids");
+
+    let query_msg = frac_lockup::contract::QueryMsg::Backing {};
+    let res: frac_lockup::msg::BackingResponse =
+        router.wrap().query_wasm_smart(LOCKUP, &query_msg).unwrap();
+
+    assert_eq!(res.collections.len(), 1);
+    assert_eq!(res.collections[0].count, 0);
+    assert_eq!(res.collections[0].backing, Uint128::zero());
+
+    // The tokens given to the admin on setup are not backed by any NFT
+    assert_eq!(res.denoms.len(), 1);
+    assert_eq!(res.denoms[0].denom, denom);
+    assert_eq!(res.denoms[0].expected_supply, Uint128::zero());
+    assert_eq!(res.denoms[0].supply, Uint128::from(2000000u128));
+    assert_eq!(res.health, frac_lockup::msg::Health::Undercollateralized);
+}
+
+#[test]
+fn retire_and_remove_collection() {
+    let mut router = setup_contracts();
+
+    // Admin only
+    let msg = frac_lockup::contract::ExecMsg::RetireCollection {
+        address: CW721.to_string(),
+    };
+    let err = router
+        .execute_contract(Addr::unchecked(USER), Addr::unchecked(LOCKUP), &msg, &[])
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<StdError>().unwrap(),
+        StdError::generic_err("Unauthorized")
+    );
+
+    router
+        .execute_contract(Addr::unchecked(ADMIN), Addr::unchecked(LOCKUP), &msg, &[])
+        .unwrap();
+
+    // Retired collections take no more deposits
+    let msg: cw721_base::ExecuteMsg<Empty, Empty> = cw721_base::ExecuteMsg::Mint {
+        token_id: "1".to_string(),
+        owner: USER.to_string(),
+        token_uri: None,
+        extension: Empty {},
+    };
+    router
+        .execute_contract(Addr::unchecked(ADMIN), Addr::unchecked(CW721), &msg, &[])
+        .unwrap();
+
+    let msg: cw721_base::ExecuteMsg<Empty, Empty> = cw721_base::ExecuteMsg::SendNft {
+        contract: LOCKUP.to_string(),
+        token_id: "1".to_string(),
+        msg: b"{}".to_vec().into(),
+    };
+    let err = router
+        .execute_contract(Addr::unchecked(USER), Addr::unchecked(CW721), &msg, &[])
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<StdError>().unwrap(),
+        StdError::generic_err("Collection is retired")
+    );
+
+    // Collections can be removed once none of their tokens are held
+    let msg = frac_lockup::contract::ExecMsg::RemoveCollection {
+        address: CW721.to_string(),
+    };
+    router
+        .execute_contract(Addr::unchecked(ADMIN), Addr::unchecked(LOCKUP), &msg, &[])
+        .unwrap();
+
+    let query_msg = frac_lockup::contract::QueryMsg::Config {};
+    let res: frac_lockup::msg::ConfigResponse =
+        router.wrap().query_wasm_smart(LOCKUP, &query_msg).unwrap();
+    assert!(res.collections.is_empty());
+}
+
+#[test]
+fn update_priority_window() {
+    let mut router = setup_contracts();
+
+    let msg = frac_lockup::contract::ExecMsg::UpdatePriorityWindow {
+        priority_window: Some(PriorityWindow {
+            hours: 0,
+            fee_discount_bps: 5000,
+        }),
+    };
+    let err = router
+        .execute_contract(Addr::unchecked(ADMIN), Addr::unchecked(LOCKUP), &msg, &[])
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<StdError>().unwrap(),
+        StdError::generic_err("Priority window must be greater than 0")
+    );
+
+    let priority_window = PriorityWindow {
+        hours: 24,
+        fee_discount_bps: 5000,
+    };
+    let msg = frac_lockup::contract::ExecMsg::UpdatePriorityWindow {
+        priority_window: Some(priority_window.clone()),
+    };
+    let err = router
+        .execute_contract(Addr::unchecked(USER), Addr::unchecked(LOCKUP), &msg, &[])
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<StdError>().unwrap(),
+        StdError::generic_err("Unauthorized")
+    );
+
+    router
+        .execute_contract(Addr::unchecked(ADMIN), Addr::unchecked(LOCKUP), &msg, &[])
+        .unwrap();
+
+    let query_msg = frac_lockup::contract::QueryMsg::Config {};
+    let res: frac_lockup::msg::ConfigResponse =
+        router.wrap().query_wasm_smart(LOCKUP, &query_msg).unwrap();
+    assert_eq!(res.priority_window, Some(priority_window));
+}
@@ -1,8 +1,12 @@
 #[cfg(test)]
+pub mod cw20_lockup;
+#[cfg(test)]
 pub mod cw721_lockup;
 #[cfg(test)]
 pub mod fixed_stake;
 #[cfg(test)]
+pub mod fixed_stake_math;
+#[cfg(test)]
 pub mod frac_lockup;
 #[cfg(test)]
 pub mod native_lockup;
@@ -1,7 +1,12 @@
 #![cfg(test)]
 
-use cosmwasm_std::{coin, coins, Addr, BankMsg, Empty, StdError, Timestamp, Uint128};
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{
+    coin, coins, Addr, BankMsg, DepsMut, Empty, Env, MessageInfo, Response, StdError, StdResult,
+    Timestamp, Uint128,
+};
 use cw_multi_test::{App, Contract, ContractWrapper, Executor};
+use cw_storage_plus::Map;
 
 pub fn contract_lockup() -> Box<dyn Contract<Empty>> {
     let contract = ContractWrapper::new(
@@ -12,6 +17,52 @@ pub fn contract_lockup() -> Box<dyn Contract<Empty>> {
     Box::new(contract)
 }
 
+pub fn contract_legacy_lockup() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        native_lockup::contract::entry_points::execute,
+        legacy_lockup_instantiate,
+        native_lockup::contract::entry_points::query,
+    )
+    .with_migrate(native_lockup::contract::migrate);
+    Box::new(contract)
+}
+
+// Lockup entry as stored before lockup positions were represented by receipts
+#[cw_serde]
+struct BaselineLockup {
+    amount: Uint128,
+    locked_since: Timestamp,
+    locked_until: Timestamp,
+}
+
+// Native lockup instantiation leaving the storage of a contract that predates receipts,
+// holding a lockup for the user
+fn legacy_lockup_instantiate(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: native_lockup::contract::InstantiateMsg,
+) -> StdResult<Response> {
+    let res = native_lockup::contract::entry_points::instantiate(deps.branch(), env, info, msg)?;
+
+    lockup_common::receipts::ReceiptNfts::default()
+        .contract_info
+        .remove(deps.storage);
+
+    let lockup: Map<Addr, BaselineLockup> = Map::new("lockup");
+    lockup.save(
+        deps.storage,
+        Addr::unchecked(USER),
+        &BaselineLockup {
+            amount: Uint128::from(1000u128),
+            locked_since: Timestamp::from_seconds(1),
+            locked_until: Timestamp::from_seconds(3601),
+        },
+    )?;
+
+    Ok(res)
+}
+
 const LOCKUP: &str = "contract0";
 
 const ADMIN: &str = "admin";
@@ -121,6 +172,90 @@ fn try_deposit() {
     assert_eq!(res.amount.u128(), deposit_amount[0].amount.u128() * 2);
 }
 
+#[test]
+fn try_deposit_for() {
+    let mut router = setup_contracts();
+    let admin = Addr::unchecked(ADMIN);
+    let user = Addr::unchecked(USER);
+
+    // Only approved depositors can deposit for others
+    let msg = native_lockup::contract::ExecMsg::DepositFor {
+        recipient: user.to_string(),
+    };
+    let err = router
+        .execute_contract(
+            admin.clone(),
+            Addr::unchecked(LOCKUP),
+            &msg,
+            &coins(500, "ustars"),
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<StdError>().unwrap(),
+        StdError::generic_err("Unauthorized")
+    );
+
+    // Only the admin can approve depositors
+    let update_msg = native_lockup::contract::ExecMsg::UpdateDepositors {
+        depositors: vec![admin.to_string()],
+    };
+    let err = router
+        .execute_contract(user.clone(), Addr::unchecked(LOCKUP), &update_msg, &[])
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<StdError>().unwrap(),
+        StdError::generic_err("Unauthorized")
+    );
+    router
+        .execute_contract(admin.clone(), Addr::unchecked(LOCKUP), &update_msg, &[])
+        .unwrap();
+
+    let query_msg = native_lockup::contract::QueryMsg::Config {};
+    let res: native_lockup::msg::ConfigResponse =
+        router.wrap().query_wasm_smart(LOCKUP, &query_msg).unwrap();
+    assert_eq!(res.depositors, vec![admin.clone()]);
+
+    // Admin deposits 500 ustars for user
+    router
+        .execute_contract(
+            admin.clone(),
+            Addr::unchecked(LOCKUP),
+            &msg,
+            &coins(500, "ustars"),
+        )
+        .unwrap();
+
+    // The lockup belongs to user
+    let query_msg = native_lockup::contract::QueryMsg::Lockup {
+        address: user.to_string(),
+    };
+    let res: native_lockup::storage::Lockup =
+        router.wrap().query_wasm_smart(LOCKUP, &query_msg).unwrap();
+    assert_eq!(res.amount, Uint128::from(500u128));
+    let locked_until = res.locked_until;
+
+    let query_msg = native_lockup::contract::QueryMsg::Lockup {
+        address: admin.to_string(),
+    };
+    let res: StdResult<native_lockup::storage::Lockup> =
+        router.wrap().query_wasm_smart(LOCKUP, &query_msg);
+    assert!(res.is_err());
+
+    // Deposits for others do not extend the lockup
+    add_block_time(&mut router, 600);
+    router
+        .execute_contract(admin, Addr::unchecked(LOCKUP), &msg, &coins(500, "ustars"))
+        .unwrap();
+
+    let query_msg = native_lockup::contract::QueryMsg::Lockup {
+        address: user.to_string(),
+    };
+    let res: native_lockup::storage::Lockup =
+        router.wrap().query_wasm_smart(LOCKUP, &query_msg).unwrap();
+    assert_eq!(res.amount, Uint128::from(1000u128));
+    assert_eq!(res.locked_until, locked_until);
+}
+
 #[test]
 fn try_withdraw() {
     let mut router = setup_contracts();
@@ -199,3 +334,239 @@ fn try_withdraw() {
     //     StdError::generic_err("Querier contract error: type: native_lockup::storage::Lockup; key: [00, 06, 6C, 6F, 63, 6B, 75, 70, 75, 73, 65, 72] not found")
     // );
 }
+
+#[test]
+fn try_transfer_lockup() {
+    let mut router = setup_contracts();
+    let admin = Addr::unchecked(ADMIN);
+    let user = Addr::unchecked(USER);
+    let recipient = Addr::unchecked("recipient");
+
+    let deposit_amount = coins(1000, "ustars");
+
+    // Admin deposits 1000 ustars
+    let msg = native_lockup::contract::ExecMsg::Deposit {};
+    router
+        .execute_contract(
+            admin.clone(),
+            Addr::unchecked(LOCKUP),
+            &msg,
+            &deposit_amount,
+        )
+        .unwrap();
+
+    let query_msg = native_lockup::contract::QueryMsg::Lockup {
+        address: admin.to_string(),
+    };
+    let lockup: native_lockup::storage::Lockup =
+        router.wrap().query_wasm_smart(LOCKUP, &query_msg).unwrap();
+
+    // User without a lockup cannot transfer
+    let msg = native_lockup::contract::ExecMsg::TransferLockup {
+        recipient: recipient.to_string(),
+    };
+    router
+        .execute_contract(user.clone(), Addr::unchecked(LOCKUP), &msg, &[])
+        .unwrap_err();
+
+    // Admin transfers the lockup to recipient
+    router
+        .execute_contract(admin.clone(), Addr::unchecked(LOCKUP), &msg, &[])
+        .unwrap();
+
+    // Recipient now holds the lockup with the same lock period
+    let query_msg = native_lockup::contract::QueryMsg::Lockup {
+        address: recipient.to_string(),
+    };
+    let res: native_lockup::storage::Lockup =
+        router.wrap().query_wasm_smart(LOCKUP, &query_msg).unwrap();
+    assert_eq!(res, lockup);
+
+    // Admin no longer has a lockup
+    let query_msg = native_lockup::contract::QueryMsg::Lockup {
+        address: admin.to_string(),
+    };
+    router
+        .wrap()
+        .query_wasm_smart::<native_lockup::storage::Lockup>(LOCKUP, &query_msg)
+        .unwrap_err();
+
+    // Admin deposits again, and cannot transfer onto the existing lockup
+    let msg = native_lockup::contract::ExecMsg::Deposit {};
+    router
+        .execute_contract(
+            admin.clone(),
+            Addr::unchecked(LOCKUP),
+            &msg,
+            &deposit_amount,
+        )
+        .unwrap();
+
+    let msg = native_lockup::contract::ExecMsg::TransferLockup {
+        recipient: recipient.to_string(),
+    };
+    let err = router
+        .execute_contract(admin.clone(), Addr::unchecked(LOCKUP), &msg, &[])
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<StdError>().unwrap(),
+        StdError::generic_err("Recipient already has a lockup")
+    );
+}
+
+#[test]
+fn try_transfer_receipt() {
+    let mut router = setup_contracts();
+    let admin = Addr::unchecked(ADMIN);
+    let user = Addr::unchecked(USER);
+
+    // Admin deposits 1000 ustars and receives a lockup receipt
+    let msg = native_lockup::contract::ExecMsg::Deposit {};
+    router
+        .execute_contract(
+            admin.clone(),
+            Addr::unchecked(LOCKUP),
+            &msg,
+            &coins(1000, "ustars"),
+        )
+        .unwrap();
+
+    let query_msg = native_lockup::contract::QueryMsg::Lockup {
+        address: admin.to_string(),
+    };
+    let lockup: native_lockup::storage::Lockup =
+        router.wrap().query_wasm_smart(LOCKUP, &query_msg).unwrap();
+
+    let query_msg = lockup_common::receipts::QueryMsg::OwnerOf {
+        token_id: lockup.receipt_id.clone(),
+        include_expired: None,
+    };
+    let res: cw721::OwnerOfResponse = router.wrap().query_wasm_smart(LOCKUP, &query_msg).unwrap();
+    assert_eq!(res.owner, admin.to_string());
+
+    // User cannot transfer the admin's receipt
+    let msg = lockup_common::receipts::ExecMsg::TransferNft {
+        recipient: user.to_string(),
+        token_id: lockup.receipt_id.clone(),
+    };
+    let err = router
+        .execute_contract(user.clone(), Addr::unchecked(LOCKUP), &msg, &[])
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<StdError>().unwrap(),
+        StdError::generic_err("Sender is not the owner of the lockup receipt")
+    );
+
+    // Admin transfers the receipt to user, moving the lockup along with it
+    router
+        .execute_contract(admin.clone(), Addr::unchecked(LOCKUP), &msg, &[])
+        .unwrap();
+
+    let res: cw721::OwnerOfResponse = router.wrap().query_wasm_smart(LOCKUP, &query_msg).unwrap();
+    assert_eq!(res.owner, user.to_string());
+
+    let query_msg = native_lockup::contract::QueryMsg::Lockup {
+        address: user.to_string(),
+    };
+    let res: native_lockup::storage::Lockup =
+        router.wrap().query_wasm_smart(LOCKUP, &query_msg).unwrap();
+    assert_eq!(res, lockup);
+
+    // User withdraws the full amount after the lockup period, burning the receipt
+    add_block_time(&mut router, 3700);
+    let msg = native_lockup::contract::ExecMsg::Withdraw { amount: None };
+    router
+        .execute_contract(user.clone(), Addr::unchecked(LOCKUP), &msg, &[])
+        .unwrap();
+
+    let balance = router.wrap().query_balance(USER, "ustars").unwrap();
+    assert_eq!(balance, coin(1000u128, "ustars"));
+
+    let query_msg = lockup_common::receipts::QueryMsg::NumTokens {};
+    let res: cw721::NumTokensResponse = router.wrap().query_wasm_smart(LOCKUP, &query_msg).unwrap();
+    assert_eq!(res.count, 0);
+}
+
+#[test]
+fn migrate_legacy_lockups() {
+    let admin = Addr::unchecked(ADMIN);
+
+    let mut router = App::new(|router, _, storage| {
+        router
+            .bank
+            .init_balance(storage, &admin, coins(1000, "ustars"))
+            .unwrap();
+    });
+
+    // Set up a NativeLockup contract holding a lockup without a receipt
+    let legacy_id = router.store_code(contract_legacy_lockup());
+    let msg = native_lockup::contract::InstantiateMsg {
+        lockup_interval: Some(Timestamp::from_seconds(3600)),
+        token: "ustars".to_string(),
+    };
+    let lockup = router
+        .instantiate_contract(
+            legacy_id,
+            admin.clone(),
+            &msg,
+            &[],
+            "LOCKUP",
+            Some(ADMIN.to_string()),
+        )
+        .unwrap();
+    router
+        .send_tokens(admin.clone(), lockup.clone(), &coins(1000, "ustars"))
+        .unwrap();
+
+    let query_msg = native_lockup::contract::QueryMsg::Lockup {
+        address: USER.to_string(),
+    };
+    router
+        .wrap()
+        .query_wasm_smart::<native_lockup::storage::Lockup>(lockup.clone(), &query_msg)
+        .unwrap_err();
+
+    // Migrating issues a receipt for the lockup
+    router
+        .migrate_contract(
+            admin,
+            lockup.clone(),
+            &native_lockup::contract::MigrateMsg {},
+            legacy_id,
+        )
+        .unwrap();
+
+    let res: native_lockup::storage::Lockup = router
+        .wrap()
+        .query_wasm_smart(lockup.clone(), &query_msg)
+        .unwrap();
+    assert_eq!(res.amount, Uint128::from(1000u128));
+    assert_eq!(res.locked_until, Timestamp::from_seconds(3601));
+
+    let query_msg = lockup_common::receipts::QueryMsg::OwnerOf {
+        token_id: res.receipt_id,
+        include_expired: None,
+    };
+    let res: cw721::OwnerOfResponse = router
+        .wrap()
+        .query_wasm_smart(lockup.clone(), &query_msg)
+        .unwrap();
+    assert_eq!(res.owner, USER);
+
+    let query_msg = lockup_common::receipts::QueryMsg::ContractInfo {};
+    let res: cw721::ContractInfoResponse = router
+        .wrap()
+        .query_wasm_smart(lockup.clone(), &query_msg)
+        .unwrap();
+    assert_eq!(res.name, "ustars Lockup Receipt");
+
+    // The user withdraws the lockup through its receipt
+    add_block_time(&mut router, 3700);
+    let msg = native_lockup::contract::ExecMsg::Withdraw { amount: None };
+    router
+        .execute_contract(Addr::unchecked(USER), lockup, &msg, &[])
+        .unwrap();
+
+    let balance = router.wrap().query_balance(USER, "ustars").unwrap();
+    assert_eq!(balance, coin(1000u128, "ustars"));
+}
@@ -2,23 +2,26 @@
 
 use std::rc::Rc;
 
-use cosmwasm_std::{coin, coins, Uint128};
+use cosmwasm_std::{coin, coins, HexBinary, Uint128};
 
 use cw_orch::prelude::*;
 use cw_orch_osmosis_test_tube::OsmosisTestTube;
 use frac_lockup::{
-    msg::{ConfigResponse, LockupsReponse},
-    storage::CollectionInput,
+    msg::{
+        BackingResponse, ConfigResponse, CountResponse, DepositOptions, DepositQuoteResponse,
+        Health, LockupsReponse, ReceiveNftMsg, RedeemQuoteResponse,
+    },
+    storage::{CollectionInput, DenomInput, DenomMetadata, PriorityWindow, RandomRedemption},
 };
 use osmosis_test_tube::osmosis_std::types::{
+    cosmos::base::v1beta1::Coin as ProtoCoin,
     cosmwasm::wasm::v1::{MsgInstantiateContract, MsgInstantiateContractResponse},
-    osmosis::tokenfactory::v1beta1::{
-        MsgChangeAdmin, MsgChangeAdminResponse, MsgCreateDenom, MsgCreateDenomResponse,
-    },
+    osmosis::tokenfactory::v1beta1::{MsgMint, MsgMintResponse},
 };
 use osmosis_test_tube::{Account, SigningAccount};
 use prost::Message;
 use prost_types::Any;
+use rand::{rngs::StdRng, Rng, SeedableRng};
 
 use crate::interface::cw721_base::{
     Cw721Base, Cw721ExecuteMsg, InstantiateMsg as Cw721InstantiateMsg,
@@ -27,24 +30,42 @@ use crate::interface::frac_lockup::{
     ExecuteMsg as FracExecuteMsg, FracLockup, InstantiateMsg as FracInstantiateMsg,
     QueryMsg as FracQueryMsg,
 };
+use crate::interface::native_lockup::{
+    ExecuteMsg as NativeExecuteMsg, InstantiateMsg as NativeInstantiateMsg, NativeLockup,
+    QueryMsg as NativeQueryMsg,
+};
+use crate::interface::nois_proxy_mock::{
+    ExecuteMsg as NoisExecuteMsg, InstantiateMsg as NoisInstantiateMsg, NoisProxyMock,
+    QueryMsg as NoisQueryMsg,
+};
 
 pub const SUBDENOM: &str = "bad-kids";
 
 #[derive(Clone)]
 struct TestState {
     pub admin: Rc<SigningAccount>,
+    pub treasury: Rc<SigningAccount>,
     pub chain: OsmosisTestTube,
     pub cw721_base: Cw721Base<OsmosisTestTube>,
     pub frac_lockup: FracLockup<OsmosisTestTube>,
+    pub nois_proxy: NoisProxyMock<OsmosisTestTube>,
     pub denom: String,
 }
 
 fn setup_contracts() -> cw_orch::anyhow::Result<TestState> {
+    setup_contracts_with(None, None)
+}
+
+fn setup_contracts_with(
+    redeem_premium: Option<Uint128>,
+    collection_subdenom: Option<&str>,
+) -> cw_orch::anyhow::Result<TestState> {
     let _ = env_logger::try_init();
     let mut chain = OsmosisTestTube::new(coins(1_000_000_000_000, "uosmo"));
 
     let admin = chain.init_account(coins(1_000_000_000_000, "uosmo"))?;
     let admin_address = Addr::unchecked(admin.address());
+    let treasury = chain.init_account(coins(1_000_000_000, "uosmo"))?;
 
     let cw721_base_contract = Cw721Base::new(chain.clone());
     cw721_base_contract.upload()?;
@@ -58,20 +79,14 @@ fn setup_contracts() -> cw_orch::anyhow::Result<TestState> {
         None,
     )?;
 
-    chain.call_as(&admin).commit_any::<MsgCreateDenomResponse>(
-        vec![Any {
-            type_url: MsgCreateDenom::TYPE_URL.to_string(),
-            value: MsgCreateDenom {
-                sender: admin_address.to_string(),
-                subdenom: SUBDENOM.to_string(),
-            }
-            .encode_to_vec(),
-        }],
+    let nois_proxy_contract = NoisProxyMock::new(chain.clone());
+    nois_proxy_contract.upload()?;
+    nois_proxy_contract.call_as(&admin).instantiate(
+        &NoisInstantiateMsg {},
+        Some(&admin_address),
         None,
     )?;
 
-    let denom = format!("factory/{}/{}", admin_address.to_string(), SUBDENOM);
-
     let frac_lockup_contract = FracLockup::new(chain.clone());
     let frac_lockup_code_id = frac_lockup_contract.upload()?.uploaded_code_id()?;
 
@@ -79,8 +94,21 @@ fn setup_contracts() -> cw_orch::anyhow::Result<TestState> {
         collections: vec![CollectionInput {
             address: cw721_base_contract.addr_str()?,
             tokens: 1000000,
+            deposit_fee_bps: None,
+            redeem_fee_bps: None,
+            swap_fee_bps: None,
+            subdenom: collection_subdenom.map(|subdenom| subdenom.to_string()),
         }],
-        denom: denom.clone(),
+        denom: DenomInput::Create(DenomMetadata {
+            subdenom: SUBDENOM.to_string(),
+            name: "Bad Kids".to_string(),
+            symbol: "BADKIDS".to_string(),
+            exponent: 6,
+            description: None,
+        }),
+        nois_proxy: Some(nois_proxy_contract.addr_str()?),
+        redeem_premium,
+        treasury: Some(treasury.address()),
     };
 
     let frac_lockup_init_response = chain
@@ -93,7 +121,14 @@ fn setup_contracts() -> cw_orch::anyhow::Result<TestState> {
                     admin: admin_address.to_string(),
                     code_id: frac_lockup_code_id,
                     label: "frac-lockup".to_string(),
-                    funds: vec![],
+                    // Pays for the creation of the contract's denom, and the collection's own
+                    funds: vec![ProtoCoin {
+                        denom: "uosmo".to_string(),
+                        amount: match collection_subdenom {
+                            Some(_) => "20000000".to_string(),
+                            None => "10000000".to_string(),
+                        },
+                    }],
                     msg: cosmwasm_std::to_json_binary(&frac_lockup_instantiate_msg)?.into(),
                 }
                 .encode_to_vec(),
@@ -103,24 +138,15 @@ fn setup_contracts() -> cw_orch::anyhow::Result<TestState> {
 
     frac_lockup_contract.set_address(&frac_lockup_init_response.instantiated_contract_address()?);
 
-    chain.call_as(&admin).commit_any::<MsgChangeAdminResponse>(
-        vec![Any {
-            type_url: MsgChangeAdmin::TYPE_URL.to_string(),
-            value: MsgChangeAdmin {
-                sender: admin_address.to_string(),
-                denom: denom.clone(),
-                new_admin: frac_lockup_contract.addr_str()?,
-            }
-            .encode_to_vec(),
-        }],
-        None,
-    )?;
+    let denom = format!("factory/{}/{}", frac_lockup_contract.addr_str()?, SUBDENOM);
 
     Ok(TestState {
         admin,
+        treasury,
         chain,
         cw721_base: cw721_base_contract,
         frac_lockup: frac_lockup_contract,
+        nois_proxy: nois_proxy_contract,
         denom,
     })
 }
@@ -270,3 +296,898 @@ fn try_withdraw() {
         .unwrap();
     assert_eq!(balance, Uint128::zero());
 }
+
+#[test]
+fn try_withdraw_with_premium() {
+    let state = setup_contracts_with(Some(Uint128::from(500_000u128)), None).unwrap();
+
+    for token_id in ["1", "2"] {
+        mint_cw721(state.clone(), state.admin.address(), token_id);
+        send_cw721(
+            state.clone(),
+            state.admin.clone(),
+            state.frac_lockup.address().unwrap(),
+            token_id,
+        );
+    }
+
+    // Redeeming a specific token without the premium fails
+    let msg = FracExecuteMsg::Withdraw {
+        collection_address: state.cw721_base.addr_str().unwrap(),
+        token_id: "1".to_string(),
+    };
+    let res = state
+        .frac_lockup
+        .call_as(&state.admin)
+        .execute(&msg, Some(&[coin(1_000_000, state.denom.clone())]));
+    assert!(res.is_err());
+
+    state
+        .frac_lockup
+        .call_as(&state.admin)
+        .execute(&msg, Some(&[coin(1_500_000, state.denom.clone())]))
+        .unwrap();
+
+    let balance = state
+        .chain
+        .query_balance(&state.admin.address(), &state.denom)
+        .unwrap();
+    assert_eq!(balance, Uint128::from(500_000u128));
+
+    // The premium goes to the treasury rather than being burned
+    let balance = state
+        .chain
+        .query_balance(&state.treasury.address(), &state.denom)
+        .unwrap();
+    assert_eq!(balance, Uint128::from(500_000u128));
+}
+
+#[test]
+fn try_redeem_random() {
+    let state = setup_contracts().unwrap();
+
+    for token_id in ["1", "2", "3"] {
+        mint_cw721(state.clone(), state.admin.address(), token_id);
+        send_cw721(
+            state.clone(),
+            state.admin.clone(),
+            state.frac_lockup.address().unwrap(),
+            token_id,
+        );
+    }
+
+    // Request a random token, holding the fractional tokens until fulfillment
+    let msg = FracExecuteMsg::RedeemRandom {
+        collection: state.cw721_base.addr_str().unwrap(),
+    };
+    state
+        .frac_lockup
+        .call_as(&state.admin)
+        .execute(&msg, Some(&[coin(1_000_000, state.denom.clone())]))
+        .unwrap();
+
+    let redemption = state
+        .frac_lockup
+        .query::<RandomRedemption>(&FracQueryMsg::RandomRedemption {
+            job_id: "redeem-1".to_string(),
+        })
+        .unwrap();
+    assert_eq!(redemption.redeemer, state.admin.address());
+    assert_eq!(redemption.amount, Uint128::from(1_000_000u128));
+
+    let jobs = state
+        .nois_proxy
+        .query::<Vec<nois_proxy_mock::msg::Job>>(&NoisQueryMsg::Jobs {})
+        .unwrap();
+    assert_eq!(jobs.len(), 1);
+
+    // The proxy delivers randomness, which picks one of the three tokens
+    let msg = NoisExecuteMsg::Deliver {
+        randomness: HexBinary::from(vec![0u8; 32]),
+    };
+    state
+        .nois_proxy
+        .call_as(&state.admin)
+        .execute(&msg, None)
+        .unwrap();
+
+    let response = state
+        .frac_lockup
+        .query::<CountResponse>(&FracQueryMsg::Count {})
+        .unwrap();
+    assert_eq!(response.count, 2);
+
+    let owner = state
+        .cw721_base
+        .query::<cw721::OwnerOfResponse>(&cw721::Cw721QueryMsg::OwnerOf {
+            token_id: "1".to_string(),
+            include_expired: None,
+        })
+        .unwrap();
+    assert_eq!(owner.owner, state.admin.address());
+
+    let balance = state
+        .chain
+        .query_balance(&state.admin.address(), &state.denom)
+        .unwrap();
+    assert_eq!(balance, Uint128::from(2_000_000u128));
+}
+
+#[test]
+fn try_cancel_random_redemption() {
+    let state = setup_contracts().unwrap();
+
+    mint_cw721(state.clone(), state.admin.address(), "1");
+    send_cw721(
+        state.clone(),
+        state.admin.clone(),
+        state.frac_lockup.address().unwrap(),
+        "1",
+    );
+
+    let msg = FracExecuteMsg::RedeemRandom {
+        collection: state.cw721_base.addr_str().unwrap(),
+    };
+    state
+        .frac_lockup
+        .call_as(&state.admin)
+        .execute(&msg, Some(&[coin(1_000_000, state.denom.clone())]))
+        .unwrap();
+
+    // The proxy has not delivered randomness, but the redemption has not timed out yet
+    let jobs = state
+        .nois_proxy
+        .query::<Vec<nois_proxy_mock::msg::Job>>(&NoisQueryMsg::Jobs {})
+        .unwrap();
+    assert_eq!(jobs.len(), 1);
+
+    let msg = FracExecuteMsg::CancelRandomRedemption {
+        job_id: "redeem-1".to_string(),
+    };
+    let res = state.frac_lockup.call_as(&state.admin).execute(&msg, None);
+    assert!(res.is_err());
+
+    state
+        .chain
+        .wait_seconds(frac_lockup::storage::RANDOM_REDEMPTION_TIMEOUT)
+        .unwrap();
+
+    // Only the redeemer can cancel
+    let res = state
+        .frac_lockup
+        .call_as(&state.treasury)
+        .execute(&msg, None);
+    assert!(res.is_err());
+
+    // Cancelling refunds the tokens held and leaves the token in the pool
+    state
+        .frac_lockup
+        .call_as(&state.admin)
+        .execute(&msg, None)
+        .unwrap();
+
+    let balance = state
+        .chain
+        .query_balance(&state.admin.address(), &state.denom)
+        .unwrap();
+    assert_eq!(balance, Uint128::from(1_000_000u128));
+
+    let res = state
+        .frac_lockup
+        .query::<RandomRedemption>(&FracQueryMsg::RandomRedemption {
+            job_id: "redeem-1".to_string(),
+        });
+    assert!(res.is_err());
+
+    let response = state
+        .frac_lockup
+        .query::<CountResponse>(&FracQueryMsg::Count {})
+        .unwrap();
+    assert_eq!(response.count, 1);
+}
+
+#[test]
+fn try_deposit_and_withdraw_with_fees() {
+    let state = setup_contracts().unwrap();
+
+    // Charge a 1% deposit fee and a 2% redemption fee
+    let msg = FracExecuteMsg::UpdateCollectionFees {
+        collection_address: state.cw721_base.addr_str().unwrap(),
+        deposit_fee_bps: 100,
+        redeem_fee_bps: 200,
+        swap_fee_bps: None,
+    };
+    state
+        .frac_lockup
+        .call_as(&state.admin)
+        .execute(&msg, None)
+        .unwrap();
+
+    for token_id in ["1", "2"] {
+        mint_cw721(state.clone(), state.admin.address(), token_id);
+        send_cw721(
+            state.clone(),
+            state.admin.clone(),
+            state.frac_lockup.address().unwrap(),
+            token_id,
+        );
+    }
+
+    let balance = state
+        .chain
+        .query_balance(&state.admin.address(), &state.denom)
+        .unwrap();
+    assert_eq!(balance, Uint128::from(1_980_000u128));
+    let balance = state
+        .chain
+        .query_balance(&state.treasury.address(), &state.denom)
+        .unwrap();
+    assert_eq!(balance, Uint128::from(20_000u128));
+
+    // Redeeming costs the tokens of the NFT plus the redemption fee
+    let quote = state
+        .frac_lockup
+        .query::<RedeemQuoteResponse>(&FracQueryMsg::RedeemQuote {
+            collection_address: state.cw721_base.addr_str().unwrap(),
+            random: false,
+            token_id: None,
+            redeemer: None,
+        })
+        .unwrap();
+    assert_eq!(quote.price, Uint128::from(1_020_000u128));
+
+    let msg = FracExecuteMsg::Withdraw {
+        collection_address: state.cw721_base.addr_str().unwrap(),
+        token_id: "1".to_string(),
+    };
+    state
+        .frac_lockup
+        .call_as(&state.admin)
+        .execute(&msg, Some(&[coin(1_020_000, state.denom.clone())]))
+        .unwrap();
+
+    let balance = state
+        .chain
+        .query_balance(&state.admin.address(), &state.denom)
+        .unwrap();
+    assert_eq!(balance, Uint128::from(960_000u128));
+    let balance = state
+        .chain
+        .query_balance(&state.treasury.address(), &state.denom)
+        .unwrap();
+    assert_eq!(balance, Uint128::from(40_000u128));
+}
+
+#[test]
+fn try_collection_denom() {
+    let state = setup_contracts_with(None, Some("bad-kids-lsd")).unwrap();
+    let collection_denom = format!(
+        "factory/{}/{}",
+        state.frac_lockup.addr_str().unwrap(),
+        "bad-kids-lsd"
+    );
+
+    let quote = state
+        .frac_lockup
+        .query::<DepositQuoteResponse>(&FracQueryMsg::DepositQuote {
+            collection_address: state.cw721_base.addr_str().unwrap(),
+        })
+        .unwrap();
+    assert_eq!(quote.denom, collection_denom);
+
+    // Deposits mint the collection's own denom
+    mint_cw721(state.clone(), state.admin.address(), "1");
+    send_cw721(
+        state.clone(),
+        state.admin.clone(),
+        state.frac_lockup.address().unwrap(),
+        "1",
+    );
+
+    let balance = state
+        .chain
+        .query_balance(&state.admin.address(), &collection_denom)
+        .unwrap();
+    assert_eq!(balance, Uint128::from(1_000_000u128));
+
+    // Withdrawals only accept the collection's denom
+    let msg = FracExecuteMsg::Withdraw {
+        collection_address: state.cw721_base.addr_str().unwrap(),
+        token_id: "1".to_string(),
+    };
+    let res = state
+        .frac_lockup
+        .call_as(&state.admin)
+        .execute(&msg, Some(&[coin(1_000_000, "uosmo")]));
+    assert!(res.is_err());
+
+    state
+        .frac_lockup
+        .call_as(&state.admin)
+        .execute(&msg, Some(&[coin(1_000_000, collection_denom.clone())]))
+        .unwrap();
+
+    let balance = state
+        .chain
+        .query_balance(&state.admin.address(), &collection_denom)
+        .unwrap();
+    assert_eq!(balance, Uint128::zero());
+}
+
+#[test]
+fn try_transfer_denom_admin() {
+    let state = setup_contracts().unwrap();
+
+    // The contract cannot mint once the denom is handed over
+    let msg = FracExecuteMsg::TransferDenomAdmin {
+        denom: state.denom.clone(),
+        new_admin: state.admin.address(),
+    };
+    state
+        .frac_lockup
+        .call_as(&state.admin)
+        .execute(&msg, None)
+        .unwrap();
+
+    mint_cw721(state.clone(), state.admin.address(), "1");
+    let res = state.cw721_base.call_as(&state.admin).execute(
+        &Cw721ExecuteMsg::SendNft {
+            contract: state.frac_lockup.addr_str().unwrap(),
+            token_id: "1".to_string(),
+            msg: b"{}".to_vec().into(),
+        },
+        None,
+    );
+    assert!(res.is_err());
+
+    // While the new admin can
+    state
+        .chain
+        .call_as(&state.admin)
+        .commit_any::<MsgMintResponse>(
+            vec![Any {
+                type_url: MsgMint::TYPE_URL.to_string(),
+                value: MsgMint {
+                    sender: state.admin.address(),
+                    amount: Some(ProtoCoin {
+                        denom: state.denom.clone(),
+                        amount: "1000000".to_string(),
+                    }),
+                    mint_to_address: state.admin.address(),
+                }
+                .encode_to_vec(),
+            }],
+            None,
+        )
+        .unwrap();
+
+    let balance = state
+        .chain
+        .query_balance(&state.admin.address(), &state.denom)
+        .unwrap();
+    assert_eq!(balance, Uint128::from(1_000_000u128));
+}
+
+#[test]
+fn try_swap_nft() {
+    let state = setup_contracts_with(Some(Uint128::from(100_000u128)), None).unwrap();
+
+    // Charge a 5% swap fee on top of the redeem premium
+    let msg = FracExecuteMsg::UpdateCollectionFees {
+        collection_address: state.cw721_base.addr_str().unwrap(),
+        deposit_fee_bps: 0,
+        redeem_fee_bps: 0,
+        swap_fee_bps: Some(500),
+    };
+    state
+        .frac_lockup
+        .call_as(&state.admin)
+        .execute(&msg, None)
+        .unwrap();
+
+    mint_cw721(state.clone(), state.admin.address(), "1");
+    send_cw721(
+        state.clone(),
+        state.admin.clone(),
+        state.frac_lockup.address().unwrap(),
+        "1",
+    );
+    mint_cw721(state.clone(), state.admin.address(), "2");
+
+    // Swapping without paying the fee in advance fails
+    let swap_msg = Cw721ExecuteMsg::SendNft {
+        contract: state.frac_lockup.addr_str().unwrap(),
+        token_id: "2".to_string(),
+        msg: cosmwasm_std::to_json_binary(&ReceiveNftMsg::SwapNft {
+            target_token_id: "1".to_string(),
+        })
+        .unwrap(),
+    };
+    let res = state
+        .cw721_base
+        .call_as(&state.admin)
+        .execute(&swap_msg, None);
+    assert!(res.is_err());
+
+    let quote = state
+        .frac_lockup
+        .query::<RedeemQuoteResponse>(&FracQueryMsg::SwapQuote {
+            collection_address: state.cw721_base.addr_str().unwrap(),
+        })
+        .unwrap();
+    assert_eq!(quote.price, Uint128::from(150_000u128));
+
+    state
+        .frac_lockup
+        .call_as(&state.admin)
+        .execute(
+            &FracExecuteMsg::FundSwap {
+                collection_address: state.cw721_base.addr_str().unwrap(),
+            },
+            Some(&[coin(150_000, state.denom.clone())]),
+        )
+        .unwrap();
+    state
+        .cw721_base
+        .call_as(&state.admin)
+        .execute(&swap_msg, None)
+        .unwrap();
+
+    // Token 2 took the place of token 1 in the pool, with no tokens minted or burned
+    // and the fee and premium paid to the treasury
+    let response = state
+        .frac_lockup
+        .query::<LockupsReponse>(&FracQueryMsg::LockupsByDepositor {
+            depositor: state.admin.address(),
+        })
+        .unwrap();
+    assert_eq!(response.lockups.len(), 1);
+    assert_eq!(response.lockups[0].token_id, "2");
+
+    let balance = state
+        .chain
+        .query_balance(&state.admin.address(), &state.denom)
+        .unwrap();
+    assert_eq!(balance, Uint128::from(850_000u128));
+    let balance = state
+        .chain
+        .query_balance(&state.treasury.address(), &state.denom)
+        .unwrap();
+    assert_eq!(balance, Uint128::from(150_000u128));
+
+    assert_backed(&state);
+}
+
+fn assert_backed(state: &TestState) {
+    let backing = state
+        .frac_lockup
+        .query::<BackingResponse>(&FracQueryMsg::Backing {})
+        .unwrap();
+
+    assert_eq!(backing.health, Health::Backed);
+    for denom in backing.denoms {
+        assert_eq!(denom.supply, denom.expected_supply);
+    }
+}
+
+#[test]
+fn try_backing_invariant() {
+    let state =
+        setup_contracts_with(Some(Uint128::from(100_000u128)), Some("bad-kids-lsd")).unwrap();
+    let collection_address = state.cw721_base.addr_str().unwrap();
+    let collection_denom = format!(
+        "factory/{}/{}",
+        state.frac_lockup.addr_str().unwrap(),
+        "bad-kids-lsd"
+    );
+
+    // Fees and premiums move tokens to the treasury without changing the supply
+    let msg = FracExecuteMsg::UpdateCollectionFees {
+        collection_address: collection_address.clone(),
+        deposit_fee_bps: 100,
+        redeem_fee_bps: 200,
+        swap_fee_bps: Some(500),
+    };
+    state
+        .frac_lockup
+        .call_as(&state.admin)
+        .execute(&msg, None)
+        .unwrap();
+
+    // Staked tokens stay in the supply while held by a native lockup
+    let native_lockup = NativeLockup::new(state.chain.clone());
+    native_lockup.upload().unwrap();
+    native_lockup
+        .call_as(&state.admin)
+        .instantiate(
+            &NativeInstantiateMsg {
+                token: collection_denom.clone(),
+                lockup_interval: None,
+            },
+            None,
+            None,
+        )
+        .unwrap();
+    native_lockup
+        .call_as(&state.admin)
+        .execute(
+            &NativeExecuteMsg::UpdateDepositors {
+                depositors: vec![state.frac_lockup.addr_str().unwrap()],
+            },
+            None,
+        )
+        .unwrap();
+
+    assert_backed(&state);
+
+    let redeem_price = Uint128::from(1_120_000u128);
+    let random_price = Uint128::from(1_020_000u128);
+    let swap_price = Uint128::from(150_000u128);
+
+    let mut rng = StdRng::seed_from_u64(42);
+    let mut next_token_id = 0u32;
+    let mut actions = [0usize; 5];
+
+    for _ in 0..50 {
+        let balance = state
+            .chain
+            .query_balance(&state.admin.address(), &collection_denom)
+            .unwrap();
+        let locked: Vec<String> = state
+            .frac_lockup
+            .query::<LockupsReponse>(&FracQueryMsg::LockupsByCollection {
+                collection_address: collection_address.clone(),
+            })
+            .unwrap()
+            .lockups
+            .into_iter()
+            .map(|lockup| lockup.token_id)
+            .collect();
+
+        // Pick an action the admin can afford, falling back to a deposit
+        let action = match rng.gen_range(0..5) {
+            2 if !locked.is_empty() && balance >= redeem_price => 2,
+            3 if !locked.is_empty() && balance >= random_price => 3,
+            4 if !locked.is_empty() && balance >= swap_price => 4,
+            1 => 1,
+            _ => 0,
+        };
+        actions[action] += 1;
+
+        match action {
+            // Deposit a new token, minting its tokens or staking them
+            0 | 1 => {
+                next_token_id += 1;
+                let token_id = next_token_id.to_string();
+                mint_cw721(state.clone(), state.admin.address(), &token_id);
+
+                let msg = Cw721ExecuteMsg::SendNft {
+                    contract: state.frac_lockup.addr_str().unwrap(),
+                    token_id,
+                    msg: match action {
+                        0 => b"{}".to_vec().into(),
+                        _ => cosmwasm_std::to_json_binary(&ReceiveNftMsg::Deposit {
+                            options: DepositOptions::StakeInto {
+                                lockup: native_lockup.addr_str().unwrap(),
+                            },
+                        })
+                        .unwrap(),
+                    },
+                };
+                state
+                    .cw721_base
+                    .call_as(&state.admin)
+                    .execute(&msg, None)
+                    .unwrap();
+            }
+            // Redeem a specific token, paying the fee and premium
+            2 => {
+                let msg = FracExecuteMsg::Withdraw {
+                    collection_address: collection_address.clone(),
+                    token_id: locked[rng.gen_range(0..locked.len())].clone(),
+                };
+                state
+                    .frac_lockup
+                    .call_as(&state.admin)
+                    .execute(
+                        &msg,
+                        Some(&[coin(redeem_price.u128(), collection_denom.clone())]),
+                    )
+                    .unwrap();
+            }
+            // Redeem a random token, the tokens being held until randomness is delivered
+            3 => {
+                let msg = FracExecuteMsg::RedeemRandom {
+                    collection: collection_address.clone(),
+                };
+                state
+                    .frac_lockup
+                    .call_as(&state.admin)
+                    .execute(
+                        &msg,
+                        Some(&[coin(random_price.u128(), collection_denom.clone())]),
+                    )
+                    .unwrap();
+                assert_backed(&state);
+
+                let msg = NoisExecuteMsg::Deliver {
+                    randomness: HexBinary::from(rng.gen::<[u8; 32]>().to_vec()),
+                };
+                state
+                    .nois_proxy
+                    .call_as(&state.admin)
+                    .execute(&msg, None)
+                    .unwrap();
+            }
+            // Swap a new token for a locked one, paying the fee and premium in advance
+            _ => {
+                next_token_id += 1;
+                let token_id = next_token_id.to_string();
+                mint_cw721(state.clone(), state.admin.address(), &token_id);
+
+                let msg = FracExecuteMsg::FundSwap {
+                    collection_address: collection_address.clone(),
+                };
+                state
+                    .frac_lockup
+                    .call_as(&state.admin)
+                    .execute(
+                        &msg,
+                        Some(&[coin(swap_price.u128(), collection_denom.clone())]),
+                    )
+                    .unwrap();
+
+                let msg = Cw721ExecuteMsg::SendNft {
+                    contract: state.frac_lockup.addr_str().unwrap(),
+                    token_id,
+                    msg: cosmwasm_std::to_json_binary(&ReceiveNftMsg::SwapNft {
+                        target_token_id: locked[rng.gen_range(0..locked.len())].clone(),
+                    })
+                    .unwrap(),
+                };
+                state
+                    .cw721_base
+                    .call_as(&state.admin)
+                    .execute(&msg, None)
+                    .unwrap();
+            }
+        }
+
+        assert_backed(&state);
+    }
+
+    // Every kind of action was exercised
+    assert!(actions.iter().all(|count| *count > 0), "{actions:?}");
+
+    let locked = state
+        .frac_lockup
+        .query::<LockupsReponse>(&FracQueryMsg::LockupsByCollection { collection_address })
+        .unwrap()
+        .lockups;
+    let backing = state
+        .frac_lockup
+        .query::<BackingResponse>(&FracQueryMsg::Backing {})
+        .unwrap();
+    assert_eq!(backing.collections[0].count, locked.len() as u64);
+}
+
+#[test]
+fn try_retire_and_remove_collection() {
+    let state = setup_contracts().unwrap();
+
+    mint_cw721(state.clone(), state.admin.address(), "1");
+    send_cw721(
+        state.clone(),
+        state.admin.clone(),
+        state.frac_lockup.address().unwrap(),
+        "1",
+    );
+
+    state
+        .frac_lockup
+        .call_as(&state.admin)
+        .execute(
+            &FracExecuteMsg::RetireCollection {
+                address: state.cw721_base.addr_str().unwrap(),
+            },
+            None,
+        )
+        .unwrap();
+
+    // The collection cannot be removed while one of its tokens is held
+    let remove_msg = FracExecuteMsg::RemoveCollection {
+        address: state.cw721_base.addr_str().unwrap(),
+    };
+    let res = state
+        .frac_lockup
+        .call_as(&state.admin)
+        .execute(&remove_msg, None);
+    assert!(res.is_err());
+
+    // Tokens of a retired collection can still be redeemed
+    let msg = FracExecuteMsg::Withdraw {
+        collection_address: state.cw721_base.addr_str().unwrap(),
+        token_id: "1".to_string(),
+    };
+    state
+        .frac_lockup
+        .call_as(&state.admin)
+        .execute(&msg, Some(&[coin(1_000_000, state.denom.clone())]))
+        .unwrap();
+
+    state
+        .frac_lockup
+        .call_as(&state.admin)
+        .execute(&remove_msg, None)
+        .unwrap();
+
+    let config = state
+        .frac_lockup
+        .query::<ConfigResponse>(&FracQueryMsg::config())
+        .unwrap();
+    assert!(config.collections.is_empty());
+}
+
+#[test]
+fn try_priority_window() {
+    let state = setup_contracts_with(Some(Uint128::from(500_000u128)), None).unwrap();
+    let user = state
+        .chain
+        .clone()
+        .init_account(coins(1_000_000_000, "uosmo"))
+        .unwrap();
+
+    // Charge a 2% redemption fee, halved for depositors within a day of their deposit
+    let msg = FracExecuteMsg::UpdateCollectionFees {
+        collection_address: state.cw721_base.addr_str().unwrap(),
+        deposit_fee_bps: 0,
+        redeem_fee_bps: 200,
+        swap_fee_bps: None,
+    };
+    state
+        .frac_lockup
+        .call_as(&state.admin)
+        .execute(&msg, None)
+        .unwrap();
+    let msg = FracExecuteMsg::UpdatePriorityWindow {
+        priority_window: Some(PriorityWindow {
+            hours: 24,
+            fee_discount_bps: 5000,
+        }),
+    };
+    state
+        .frac_lockup
+        .call_as(&state.admin)
+        .execute(&msg, None)
+        .unwrap();
+
+    for token_id in ["1", "2"] {
+        mint_cw721(state.clone(), state.admin.address(), token_id);
+        send_cw721(
+            state.clone(),
+            state.admin.clone(),
+            state.frac_lockup.address().unwrap(),
+            token_id,
+        );
+    }
+    mint_cw721(state.clone(), user.address(), "3");
+    send_cw721(
+        state.clone(),
+        user.clone(),
+        state.frac_lockup.address().unwrap(),
+        "3",
+    );
+
+    // Other holders cannot redeem a token within its window
+    let msg = FracExecuteMsg::Withdraw {
+        collection_address: state.cw721_base.addr_str().unwrap(),
+        token_id: "1".to_string(),
+    };
+    let res = state
+        .frac_lockup
+        .call_as(&user)
+        .execute(&msg, Some(&[coin(1_000_000, state.denom.clone())]));
+    assert!(res.is_err());
+
+    let quote_msg = FracQueryMsg::RedeemQuote {
+        collection_address: state.cw721_base.addr_str().unwrap(),
+        random: false,
+        token_id: Some("1".to_string()),
+        redeemer: Some(user.address()),
+    };
+    let res = state.frac_lockup.query::<RedeemQuoteResponse>(&quote_msg);
+    assert!(res.is_err());
+
+    // The depositor buys it back with no premium and half the fee, as quoted
+    let quote_msg = FracQueryMsg::RedeemQuote {
+        collection_address: state.cw721_base.addr_str().unwrap(),
+        random: false,
+        token_id: Some("1".to_string()),
+        redeemer: Some(state.admin.address()),
+    };
+    let quote = state
+        .frac_lockup
+        .query::<RedeemQuoteResponse>(&quote_msg)
+        .unwrap();
+    assert_eq!(quote.price, Uint128::from(1_010_000u128));
+    assert_eq!(quote.fee, Uint128::from(10_000u128));
+    assert_eq!(quote.premium, Uint128::zero());
+
+    state
+        .frac_lockup
+        .call_as(&state.admin)
+        .execute(&msg, Some(&[coin(quote.price.u128(), state.denom.clone())]))
+        .unwrap();
+
+    let balance = state
+        .chain
+        .query_balance(&state.admin.address(), &state.denom)
+        .unwrap();
+    assert_eq!(balance, Uint128::from(990_000u128));
+    let balance = state
+        .chain
+        .query_balance(&state.treasury.address(), &state.denom)
+        .unwrap();
+    assert_eq!(balance, Uint128::from(10_000u128));
+}
+
+#[test]
+fn try_deposit_and_stake() {
+    let state = setup_contracts().unwrap();
+
+    let native_lockup = NativeLockup::new(state.chain.clone());
+    native_lockup.upload().unwrap();
+    native_lockup
+        .call_as(&state.admin)
+        .instantiate(
+            &NativeInstantiateMsg {
+                token: state.denom.clone(),
+                lockup_interval: None,
+            },
+            None,
+            None,
+        )
+        .unwrap();
+
+    // The frac-lockup contract must be approved to deposit on behalf of depositors
+    native_lockup
+        .call_as(&state.admin)
+        .execute(
+            &NativeExecuteMsg::UpdateDepositors {
+                depositors: vec![state.frac_lockup.addr_str().unwrap()],
+            },
+            None,
+        )
+        .unwrap();
+
+    // The minted tokens go straight into the depositor's native lockup
+    mint_cw721(state.clone(), state.admin.address(), "1");
+    let msg = Cw721ExecuteMsg::SendNft {
+        contract: state.frac_lockup.addr_str().unwrap(),
+        token_id: "1".to_string(),
+        msg: cosmwasm_std::to_json_binary(&ReceiveNftMsg::Deposit {
+            options: DepositOptions::StakeInto {
+                lockup: native_lockup.addr_str().unwrap(),
+            },
+        })
+        .unwrap(),
+    };
+    state
+        .cw721_base
+        .call_as(&state.admin)
+        .execute(&msg, None)
+        .unwrap();
+
+    let lockup = native_lockup
+        .query::<native_lockup::storage::Lockup>(&NativeQueryMsg::Lockup {
+            address: state.admin.address(),
+        })
+        .unwrap();
+    assert_eq!(lockup.amount, Uint128::from(1_000_000u128));
+
+    let balance = state
+        .chain
+        .query_balance(&state.admin.address(), &state.denom)
+        .unwrap();
+    assert_eq!(balance, Uint128::zero());
+}